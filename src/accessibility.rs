@@ -0,0 +1,258 @@
+use crate::combat::{BossKilled, EnemyReachedBase};
+use crate::common::{FrameSet, GameAssets, Lives, Shield};
+use crate::enemy::WaveStarted;
+use crate::run_end::RunEndText;
+use crate::theme::{ThemedPanel, ThemedText};
+use crate::ui::{spawn_toast, DespawnOnTweenComplete, TextAlphaLens, Tween};
+use bevy::prelude::*;
+
+const CAPTION_SECONDS: f32 = 3.0;
+const CAPTION_FONT_SIZE: f32 = 40.0;
+const CAPTION_PANEL: Color = Color::srgba(0.0, 0.0, 0.0, 0.7);
+
+#[derive(Resource)]
+pub struct Accessibility {
+    pub narrated: bool,
+    pub high_contrast: bool,
+}
+
+impl Accessibility {
+    pub fn from_args() -> Self {
+        let has = |flag: &str| std::env::args().any(|arg| arg == flag);
+        Accessibility {
+            narrated: has("--narrated"),
+            high_contrast: has("--high-contrast"),
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct VisualAccessibility {
+    pub reduced_motion: bool,
+    pub screen_shake: bool,
+    pub flashing: bool,
+    pub strobes: bool,
+}
+
+impl VisualAccessibility {
+    pub fn from_args() -> Self {
+        let has = |flag: &str| std::env::args().any(|arg| arg == flag);
+        let photosensitive = has("--photosensitive");
+        VisualAccessibility {
+            reduced_motion: has("--reduced-motion"),
+            screen_shake: !has("--no-screen-shake"),
+            flashing: !photosensitive && !has("--no-flashing"),
+            strobes: !photosensitive && !has("--no-strobes"),
+        }
+    }
+
+    pub fn allows_shake(&self) -> bool {
+        self.screen_shake && !self.reduced_motion
+    }
+}
+
+fn toggle_accessibility(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut accessibility: ResMut<Accessibility>,
+    mut visuals: ResMut<VisualAccessibility>,
+) {
+    let on_off = |enabled: bool| if enabled { "on" } else { "off" };
+    if keys.just_pressed(KeyCode::F1) {
+        accessibility.narrated = !accessibility.narrated;
+        let message = format!("Narration {}", on_off(accessibility.narrated));
+        spawn_toast(&mut commands, &message);
+    }
+    if keys.just_pressed(KeyCode::F2) {
+        accessibility.high_contrast = !accessibility.high_contrast;
+        let message = format!("High contrast {}", on_off(accessibility.high_contrast));
+        spawn_toast(&mut commands, &message);
+    }
+    if keys.just_pressed(KeyCode::F3) {
+        visuals.reduced_motion = !visuals.reduced_motion;
+        let message = format!("Reduced motion {}", on_off(visuals.reduced_motion));
+        spawn_toast(&mut commands, &message);
+    }
+    if keys.just_pressed(KeyCode::F4) {
+        let safe = visuals.flashing || visuals.strobes;
+        visuals.flashing = !safe;
+        visuals.strobes = !safe;
+        let message = format!("Photosensitivity mode {}", on_off(safe));
+        spawn_toast(&mut commands, &message);
+    }
+}
+
+#[derive(Component)]
+struct Caption;
+
+fn announce(commands: &mut Commands, captions: &Query<Entity, With<Caption>>, message: &str) {
+    for caption in captions {
+        commands.entity(caption).despawn();
+    }
+    commands.spawn((
+        Text::new(message),
+        TextFont::from_font_size(CAPTION_FONT_SIZE),
+        TextColor(Color::WHITE),
+        ThemedText,
+        TextLayout::new_with_justify(JustifyText::Center),
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Percent(20.0),
+            width: Val::Percent(100.0),
+            padding: UiRect::all(Val::Px(8.0)),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        BackgroundColor(CAPTION_PANEL),
+        ThemedPanel(CAPTION_PANEL),
+        Caption,
+        DespawnOnTweenComplete,
+        Tween::new(
+            TextAlphaLens {
+                start: 1.0,
+                end: 0.0,
+            },
+            CAPTION_SECONDS,
+            EaseFunction::QuarticIn,
+        ),
+    ));
+}
+
+fn narrated(accessibility: Res<Accessibility>) -> bool {
+    accessibility.narrated
+}
+
+fn narrate_events(
+    mut commands: Commands,
+    mut wave_started: EventReader<WaveStarted>,
+    lives: Res<Lives>,
+    shield: Res<Shield>,
+    mut reached: EventReader<EnemyReachedBase>,
+    mut boss_killed: EventReader<BossKilled>,
+    run_end: Query<&Text, Added<RunEndText>>,
+    captions: Query<Entity, With<Caption>>,
+    mut last_lives: Local<Option<u32>>,
+) {
+    let previous_lives = last_lives.replace(lives.0).unwrap_or(lives.0);
+    let mut message = None;
+    if let Some(WaveStarted(wave)) = wave_started.read().last() {
+        message = Some(format!("Wave {wave} started"));
+    }
+    if reached.read().count() > 0 {
+        message = Some(if lives.0 < previous_lives {
+            format!("Base hit, {} lives left", lives.0)
+        } else {
+            format!("Shield hit, {} shield left", shield.0)
+        });
+    }
+    if boss_killed.read().count() > 0 {
+        message = Some("Boss defeated".to_string());
+    }
+    if let Some(text) = run_end.iter().next() {
+        message = Some(text.0.clone());
+    }
+    if let Some(message) = message {
+        announce(&mut commands, &captions, &message);
+    }
+}
+
+#[derive(Default)]
+struct StandardPalette {
+    clear_color: Option<Color>,
+    materials: Vec<(Handle<ColorMaterial>, Color)>,
+}
+
+fn apply_contrast_theme(
+    mut commands: Commands,
+    accessibility: Res<Accessibility>,
+    assets: Res<GameAssets>,
+    clear_color: Option<Res<ClearColor>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut standard: Local<StandardPalette>,
+) {
+    if !accessibility.is_changed() {
+        return;
+    }
+    let high_contrast = [
+        (&assets.enemy_material, Color::srgb(1.0, 0.0, 0.0)),
+        (&assets.flyer_material, Color::srgb(1.0, 0.0, 1.0)),
+        (&assets.boss_material, Color::srgb(1.0, 0.5, 0.0)),
+        (&assets.tank_material, Color::srgb(0.6, 0.3, 0.0)),
+        (&assets.runner_material, Color::srgb(0.6, 1.0, 0.2)),
+        (&assets.warlord_material, Color::srgb(1.0, 0.0, 0.5)),
+        (&assets.splitter_material, Color::srgb(0.0, 0.8, 0.4)),
+        (&assets.carrier_material, Color::srgb(0.2, 0.4, 1.0)),
+        (&assets.healer_material, Color::srgb(0.6, 1.0, 0.0)),
+        (&assets.phantom_material, Color::srgba(1.0, 1.0, 1.0, 0.4)),
+        (&assets.dropship_material, Color::srgb(0.0, 1.0, 1.0)),
+        (&assets.necromancer_material, Color::srgb(0.4, 0.0, 0.8)),
+        (&assets.zombie_material, Color::srgb(0.5, 0.7, 0.3)),
+        (&assets.revealed_material, Color::srgb(1.0, 0.0, 1.0)),
+        (&assets.slowed_material, Color::srgb(0.0, 0.6, 1.0)),
+        (&assets.burning_material, Color::srgb(1.0, 0.4, 0.0)),
+        (&assets.poisoned_material, Color::srgb(0.7, 0.0, 1.0)),
+        (&assets.health_bar_material, Color::srgb(0.0, 1.0, 0.0)),
+        (&assets.projectile_material, Color::srgb(1.0, 1.0, 0.0)),
+        (&assets.acid_projectile_material, Color::srgb(0.0, 1.0, 0.0)),
+        (
+            &assets.frost_projectile_material,
+            Color::srgb(0.5, 1.0, 1.0),
+        ),
+        (&assets.corpse_material, Color::srgb(0.3, 0.3, 0.3)),
+        (&assets.tower_material, Color::WHITE),
+        (&assets.acid_tower_material, Color::srgb(0.0, 1.0, 1.0)),
+        (&assets.arrow_tower_material, Color::srgb(1.0, 0.8, 0.0)),
+        (&assets.frost_tower_material, Color::srgb(0.6, 0.8, 1.0)),
+        (&assets.mortar_tower_material, Color::srgb(1.0, 0.3, 0.0)),
+        (&assets.sniper_tower_material, Color::srgb(0.7, 0.0, 1.0)),
+        (&assets.missile_tower_material, Color::srgb(0.0, 1.0, 0.5)),
+        (&assets.tesla_tower_material, Color::srgb(1.0, 1.0, 0.4)),
+        (&assets.rubble_material, Color::srgb(0.5, 0.5, 0.5)),
+    ];
+    if accessibility.high_contrast {
+        if standard.materials.is_empty() {
+            standard.clear_color = clear_color.map(|clear_color| clear_color.0);
+            standard.materials = high_contrast
+                .iter()
+                .filter_map(|&(handle, _)| Some((handle.clone(), materials.get(handle)?.color)))
+                .collect();
+        }
+        for (handle, color) in high_contrast {
+            if let Some(material) = materials.get_mut(handle) {
+                material.color = color;
+            }
+        }
+        commands.insert_resource(ClearColor(Color::BLACK));
+    } else if !standard.materials.is_empty() {
+        for (handle, color) in standard.materials.drain(..) {
+            if let Some(material) = materials.get_mut(&handle) {
+                material.color = color;
+            }
+        }
+        commands.insert_resource(ClearColor(
+            standard
+                .clear_color
+                .take()
+                .unwrap_or(ClearColor::default().0),
+        ));
+    }
+}
+
+pub struct AccessibilityPlugin;
+
+impl Plugin for AccessibilityPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Accessibility::from_args());
+        app.insert_resource(VisualAccessibility::from_args());
+        app.add_systems(
+            Update,
+            (
+                toggle_accessibility.in_set(FrameSet::Input),
+                (apply_contrast_theme, narrate_events.run_if(narrated))
+                    .chain()
+                    .after(toggle_accessibility)
+                    .in_set(FrameSet::Hud),
+            ),
+        );
+    }
+}
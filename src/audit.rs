@@ -0,0 +1,158 @@
+use crate::common::{Dependents, Enemy, FrameSet, Tower};
+use crate::enemy::{Corpse, WaveStarted};
+use crate::projectile::Projectile;
+use crate::ui::spawn_toast;
+use bevy::ecs::entity::Entities;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+const GROWTH_WAVES: usize = 3;
+
+#[derive(Clone, Copy)]
+struct AuditCounts {
+    meshes: usize,
+    materials: usize,
+    enemies: usize,
+    towers: usize,
+    projectiles: usize,
+    corpses: usize,
+    ui_nodes: usize,
+    other: usize,
+}
+
+impl AuditCounts {
+    fn reusable(&self) -> [(&'static str, usize); 4] {
+        [
+            ("meshes", self.meshes),
+            ("materials", self.materials),
+            ("ui nodes", self.ui_nodes),
+            ("other entities", self.other),
+        ]
+    }
+
+    fn report(&self) -> String {
+        format!(
+            "{} meshes, {} materials | entities: {} enemies, {} towers, {} projectiles, {} corpses, {} ui nodes, {} other",
+            self.meshes,
+            self.materials,
+            self.enemies,
+            self.towers,
+            self.projectiles,
+            self.corpses,
+            self.ui_nodes,
+            self.other
+        )
+    }
+}
+
+#[derive(SystemParam)]
+struct Census<'w, 's> {
+    meshes: Res<'w, Assets<Mesh>>,
+    materials: Res<'w, Assets<ColorMaterial>>,
+    entities: &'w Entities,
+    enemies: Query<'w, 's, Option<&'static Dependents>, With<Enemy>>,
+    towers: Query<'w, 's, Option<&'static Dependents>, With<Tower>>,
+    projectiles: Query<'w, 's, (), With<Projectile>>,
+    corpses: Query<'w, 's, (), With<Corpse>>,
+    ui_nodes: Query<'w, 's, (), With<Node>>,
+}
+
+impl Census<'_, '_> {
+    fn counts(&self) -> AuditCounts {
+        let with_dependents =
+            |dependents: Option<&Dependents>| 1 + dependents.map_or(0, |d| d.0.len());
+        let enemies = self.enemies.iter().map(with_dependents).sum();
+        let towers = self.towers.iter().map(with_dependents).sum();
+        let projectiles = self.projectiles.iter().count();
+        let corpses = self.corpses.iter().count();
+        let ui_nodes = self.ui_nodes.iter().count();
+        let classified = enemies + towers + projectiles + corpses + ui_nodes;
+        AuditCounts {
+            meshes: self.meshes.len(),
+            materials: self.materials.len(),
+            enemies,
+            towers,
+            projectiles,
+            corpses,
+            ui_nodes,
+            other: (self.entities.len() as usize).saturating_sub(classified),
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+struct AuditHistory(Vec<AuditCounts>);
+
+impl AuditHistory {
+    fn growing(&self) -> Vec<String> {
+        if self.0.len() <= GROWTH_WAVES {
+            return Vec::new();
+        }
+        (0..4)
+            .filter_map(|index| {
+                let values: Vec<usize> = self.0.iter().map(|c| c.reusable()[index].1).collect();
+                let name = self.0[0].reusable()[index].0;
+                values
+                    .windows(2)
+                    .all(|pair| pair[1] > pair[0])
+                    .then(|| format!("{name} grew every wave for {GROWTH_WAVES} waves: {values:?}"))
+            })
+            .collect()
+    }
+}
+
+fn track_asset_growth(
+    mut wave_started: EventReader<WaveStarted>,
+    census: Census,
+    mut history: ResMut<AuditHistory>,
+) {
+    if wave_started.read().count() == 0 {
+        return;
+    }
+    if history.0.len() > GROWTH_WAVES {
+        history.0.remove(0);
+    }
+    history.0.push(census.counts());
+    for warning in history.growing() {
+        warn!("possible leak: {warning}");
+    }
+}
+
+fn report_asset_audit(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    census: Census,
+    history: Res<AuditHistory>,
+) {
+    if !keys.just_pressed(KeyCode::F10) {
+        return;
+    }
+    let counts = census.counts();
+    info!("asset audit: {}", counts.report());
+    let growing = history.growing();
+    for warning in &growing {
+        warn!("possible leak: {warning}");
+    }
+    let summary = format!(
+        "{} meshes, {} materials, {} possible leaks",
+        counts.meshes,
+        counts.materials,
+        growing.len()
+    );
+    spawn_toast(&mut commands, &summary);
+}
+
+pub struct AuditPlugin;
+
+impl Plugin for AuditPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AuditHistory>();
+        app.add_systems(
+            Update,
+            (
+                track_asset_growth,
+                report_asset_audit.in_set(FrameSet::Input),
+            ),
+        );
+    }
+}
@@ -0,0 +1,89 @@
+use crate::combat::EnemyKilled;
+use crate::common::Enemy;
+use crate::enemy::EnemyKind;
+use bevy::audio::{Pitch, Volume};
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use std::time::Duration;
+
+const MAX_CONCURRENT_BARKS: usize = 4;
+const BARK_COOLDOWN: f32 = 0.3;
+const BARK_VOLUME: f32 = 0.4;
+const SPAWN_BARK: Duration = Duration::from_millis(80);
+const DEATH_BARK: Duration = Duration::from_millis(140);
+const DEATH_PITCH_RATIO: f32 = 0.75;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Bark {
+    Spawn,
+    Death,
+}
+
+impl Bark {
+    fn tone(self, kind: EnemyKind) -> Pitch {
+        match self {
+            Bark::Spawn => Pitch::new(kind.bark_pitch(), SPAWN_BARK),
+            Bark::Death => Pitch::new(kind.bark_pitch() * DEATH_PITCH_RATIO, DEATH_BARK),
+        }
+    }
+}
+
+#[derive(Component)]
+struct BarkSound;
+
+#[derive(Resource, Default)]
+struct Barks {
+    tones: HashMap<(EnemyKind, Bark), Handle<Pitch>>,
+    last_played: HashMap<(EnemyKind, Bark), f32>,
+}
+
+fn play_enemy_barks(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    spawned: Query<&EnemyKind, Added<Enemy>>,
+    mut killed: EventReader<EnemyKilled>,
+    playing: Query<(), With<BarkSound>>,
+    mut pitches: ResMut<Assets<Pitch>>,
+    mut barks: ResMut<Barks>,
+) {
+    let now = time.elapsed_secs();
+    let mut voices = playing.iter().count();
+    let requested = spawned
+        .iter()
+        .map(|&kind| (kind, Bark::Spawn))
+        .chain(killed.read().map(|killed| (killed.kind, Bark::Death)));
+    for (kind, bark) in requested {
+        if voices >= MAX_CONCURRENT_BARKS
+            || barks
+                .last_played
+                .get(&(kind, bark))
+                .is_some_and(|&at| now - at < BARK_COOLDOWN)
+        {
+            continue;
+        }
+        barks.last_played.insert((kind, bark), now);
+        let tone = barks
+            .tones
+            .entry((kind, bark))
+            .or_insert_with(|| pitches.add(bark.tone(kind)))
+            .clone();
+        commands.spawn((
+            AudioPlayer(tone),
+            PlaybackSettings::DESPAWN.with_volume(Volume::new(BARK_VOLUME)),
+            BarkSound,
+        ));
+        voices += 1;
+    }
+}
+
+pub struct BarksPlugin;
+
+impl Plugin for BarksPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Barks>();
+        app.add_systems(
+            Update,
+            play_enemy_barks.run_if(resource_exists::<Assets<Pitch>>),
+        );
+    }
+}
@@ -0,0 +1,113 @@
+use crate::common::FrameSet;
+use crate::ui::{spawn_toast, KillCam};
+use bevy::prelude::*;
+use bevy::render::view::screenshot::{save_to_disk, Screenshot};
+use std::fs;
+
+const SLOW_MOTION_SCALES: [f32; 3] = [0.5, 0.25, 0.1];
+const FRAME_DUMP_DIR: &str = "saves/frames";
+const FRAME_DUMP_DEFAULT_SECONDS: f32 = 0.1;
+
+#[derive(Resource)]
+pub struct SlowMotion {
+    index: usize,
+}
+
+impl SlowMotion {
+    fn scale(&self) -> f32 {
+        SLOW_MOTION_SCALES[self.index]
+    }
+}
+
+#[derive(Resource)]
+struct FrameDump {
+    timer: Timer,
+    frame: u32,
+}
+
+fn frame_dump_seconds() -> Option<f32> {
+    let mut args = std::env::args().skip_while(|arg| arg != "--frame-dump");
+    args.next()?;
+    Some(
+        args.next()
+            .and_then(|arg| arg.parse().ok())
+            .unwrap_or(FRAME_DUMP_DEFAULT_SECONDS),
+    )
+}
+
+fn toggle_slow_motion(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    slow_motion: Option<ResMut<SlowMotion>>,
+    dump: Option<Res<FrameDump>>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+) {
+    if !keys.just_pressed(KeyCode::KeyO) {
+        return;
+    }
+    let index = slow_motion
+        .as_ref()
+        .map_or(0, |slow_motion| slow_motion.index + 1);
+    if index == SLOW_MOTION_SCALES.len() {
+        commands.remove_resource::<SlowMotion>();
+        virtual_time.set_relative_speed(1.0);
+        spawn_toast(&mut commands, "Slow motion off");
+        return;
+    }
+    match slow_motion {
+        Some(mut slow_motion) => slow_motion.index = index,
+        None => commands.insert_resource(SlowMotion { index }),
+    }
+    let mut message = format!("Slow motion {}x", SLOW_MOTION_SCALES[index]);
+    if dump.is_some() {
+        let _ = fs::create_dir_all(FRAME_DUMP_DIR);
+        message.push_str(&format!(", dumping frames to {FRAME_DUMP_DIR}"));
+    }
+    spawn_toast(&mut commands, &message);
+}
+
+fn hold_slow_motion(
+    slow_motion: Res<SlowMotion>,
+    kill_cam: Res<KillCam>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+) {
+    if kill_cam.timer.is_none() && virtual_time.relative_speed() != slow_motion.scale() {
+        virtual_time.set_relative_speed(slow_motion.scale());
+    }
+}
+
+fn dump_frames(mut commands: Commands, real_time: Res<Time<Real>>, mut dump: ResMut<FrameDump>) {
+    if !dump.timer.tick(real_time.delta()).just_finished() {
+        return;
+    }
+    let path = format!("{FRAME_DUMP_DIR}/frame_{:05}.png", dump.frame);
+    dump.frame += 1;
+    commands
+        .spawn(Screenshot::primary_window())
+        .observe(save_to_disk(path));
+}
+
+pub struct CinematicPlugin;
+
+impl Plugin for CinematicPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                toggle_slow_motion.in_set(FrameSet::Input),
+                hold_slow_motion
+                    .after(toggle_slow_motion)
+                    .run_if(resource_exists::<SlowMotion>),
+            ),
+        );
+        if let Some(seconds) = frame_dump_seconds() {
+            if app.world().contains_resource::<Assets<Image>>() {
+                app.insert_resource(FrameDump {
+                    timer: Timer::from_seconds(seconds, TimerMode::Repeating),
+                    frame: 0,
+                });
+                app.add_systems(Update, dump_frames.run_if(resource_exists::<SlowMotion>));
+            }
+        }
+    }
+}
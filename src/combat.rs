@@ -0,0 +1,847 @@
+use crate::accessibility::VisualAccessibility;
+use crate::common::{
+    Base, Bounty, Damage, Enemy, GameAssets, GameRng, Hp, Kills, Lives, ProjectedHp, Shield,
+    SimulationSet, Target, Tower, BASE_SIZE,
+};
+use crate::enemy::{
+    follow_path, Armor, Corpse, Direction, EnemyKind, Generation, Invisible, PathProgress,
+    Raisable, Resistances, Revealed, Targetable, Velocity, CORPSE_LAYER, CORPSE_LIFETIME,
+    ENEMY_SIZE,
+};
+use crate::projectile::{
+    lift_lobbed_projectiles, spawn_projectile, update_projectiles_position, EntityCaps, FiredBy,
+    Homing, Projectile, ProjectileKind, SpawnedAt, PROJECTILE_LIFETIME, PROJECTILE_RADIUS,
+};
+use crate::run_end::LastStand;
+use crate::status::{Stacking, StatusEffects, StatusKind};
+use crate::tower::{
+    CommanderAbility, Cooldown, Detector, FireRate, FocusTarget, Range, Synergy, TargetingStrategy,
+    ThreatWeights, TowerKind, UltimateCharge, TOWER_HALF_SIZE, ULTIMATE_CHARGE,
+};
+use crate::ui::{DespawnOnTweenComplete, FrameWatchdog, ScaleLens, Tween};
+use bevy::math::bounding::{Aabb2d, AabbCast2d, BoundingCircle, BoundingVolume, IntersectsVolume};
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use rand::prelude::*;
+
+#[derive(Event)]
+pub struct EnemyKilled {
+    pub kind: EnemyKind,
+    pub bounty: Bounty,
+    pub generation: u32,
+}
+
+#[derive(Event)]
+pub struct BossKilled(pub Vec3);
+
+pub const ACID_SHRED: u32 = 6;
+const ACID_MAX_STACKS: usize = 5;
+const ACID_DURATION: f32 = 4.0;
+pub const FROST_SLOW: u32 = 20;
+const FROST_MAX_STACKS: usize = 3;
+const FROST_DURATION: f32 = 3.0;
+pub const POISON_DAMAGE: u32 = 2;
+const POISON_MAX_STACKS: usize = 3;
+const POISON_DURATION: f32 = 3.0;
+pub const BURN_DAMAGE: u32 = 3;
+const BURN_DURATION: f32 = 2.0;
+const STUN_DURATION: f32 = 0.25;
+const HOMING_RETARGET_RADIUS: f32 = 150.0;
+const CHAIN_FALLOFF: f32 = 0.7;
+const SPLASH_MIN_FALLOFF: f32 = 0.25;
+const MIN_PROJECTILE_DAMAGE: u32 = 5;
+
+#[derive(Event)]
+pub struct ProjectileHit {
+    pub projectile: Option<Entity>,
+    pub enemy: Entity,
+    pub target: Option<Entity>,
+    pub tower: Entity,
+    pub kind: ProjectileKind,
+    pub damage: Damage,
+    pub position: Vec2,
+    pub splash: Option<f32>,
+}
+
+#[derive(Event)]
+pub struct ChainArc(pub Vec<Vec2>);
+
+#[derive(Event)]
+pub struct EnemyReachedBase {
+    pub enemy: Entity,
+    pub kind: EnemyKind,
+    pub position: Vec2,
+}
+
+const ENEMY_GRID_CELL: f32 = 50.0;
+
+pub fn check_enemy_base_collision(
+    base_transform: Single<&Transform, With<Base>>,
+    query: Query<(Entity, &Transform, &EnemyKind, Option<&PathProgress>), With<Enemy>>,
+    mut reached: EventWriter<EnemyReachedBase>,
+) {
+    let base_bounding = Aabb2d::new(
+        base_transform.translation.truncate(),
+        Vec2::splat(BASE_SIZE / 2.),
+    );
+    for (enemy, enemy_transform, &kind, progress) in &query {
+        // Fast enemies can step clean over the base, so also count anything that has run past
+        // the end of its route.
+        let overshot = progress.is_some_and(|progress| progress.value() >= 1.0);
+        if overshot || enemy_bounds(enemy_transform).intersects(&base_bounding) {
+            reached.send(EnemyReachedBase {
+                enemy,
+                kind,
+                position: enemy_transform.translation.truncate(),
+            });
+        }
+    }
+}
+
+pub fn resolve_base_hits(
+    mut commands: Commands,
+    mut reached: EventReader<EnemyReachedBase>,
+    mut lives: ResMut<Lives>,
+    mut shield: ResMut<Shield>,
+    mut last_stand: ResMut<LastStand>,
+) {
+    for &EnemyReachedBase { enemy, kind, .. } in reached.read() {
+        record_leak(&mut lives, &mut shield, &mut last_stand, kind);
+        commands.entity(enemy).despawn();
+    }
+}
+
+const ENEMY_CONTACT_DAMAGE: u32 = 1;
+
+pub fn damage_towers_on_contact(
+    enemies: Query<&Transform, With<Enemy>>,
+    mut towers: Query<(&Transform, &mut Hp), (With<Tower>, Without<Enemy>)>,
+) {
+    for (tower_transform, mut hp) in &mut towers {
+        let tower_bounds = Aabb2d::new(
+            tower_transform.translation.truncate(),
+            Vec2::splat(TOWER_HALF_SIZE),
+        );
+        let contacts = enemies
+            .iter()
+            .filter(|enemy_transform| enemy_bounds(enemy_transform).intersects(&tower_bounds))
+            .count() as u32;
+        hp.0 = hp.0.saturating_sub(contacts * ENEMY_CONTACT_DAMAGE);
+    }
+}
+
+pub fn record_leak(
+    lives: &mut Lives,
+    shield: &mut Shield,
+    last_stand: &mut LastStand,
+    kind: EnemyKind,
+) {
+    let absorbed = kind.lives_cost().min(shield.0);
+    shield.0 -= absorbed;
+    let lost = kind.lives_cost() - absorbed;
+    if lost == 0 {
+        return;
+    }
+    lives.0 = lives.0.saturating_sub(lost);
+    if last_stand.timer.is_some() {
+        last_stand.breached = true;
+    }
+}
+
+pub fn effective_damage(
+    damage: Damage,
+    kind: ProjectileKind,
+    armor: &Armor,
+    resistances: &Resistances,
+    effects: &StatusEffects,
+) -> u32 {
+    let resist = resistances.percent(kind.damage_type()).min(100);
+    (damage.0.saturating_sub(effects.armor(armor)) * (100 - resist) / 100)
+        .max(MIN_PROJECTILE_DAMAGE)
+}
+
+fn reveal_cloaked_enemies(
+    mut commands: Commands,
+    detectors: Query<(&Transform, &Detector)>,
+    cloaked: Query<(Entity, &Transform, Has<Revealed>), With<Invisible>>,
+) {
+    for (enemy, transform, revealed) in &cloaked {
+        let detected = detectors.iter().any(|(detector_transform, detector)| {
+            detector_transform
+                .translation
+                .distance(transform.translation)
+                < detector.0
+        });
+        if detected && !revealed {
+            commands.entity(enemy).insert(Revealed);
+        } else if !detected && revealed {
+            commands.entity(enemy).remove::<Revealed>();
+        }
+    }
+}
+
+pub fn tower_choose_target(
+    query: Query<
+        (Entity, &Transform, &ProjectedHp, &PathProgress, &Bounty),
+        (With<Enemy>, Targetable),
+    >,
+    focus: Query<(Entity, &Transform, &ProjectedHp), (With<Enemy>, With<FocusTarget>, Targetable)>,
+    mut towers: Query<
+        (
+            &Transform,
+            Option<&Range>,
+            &mut Target,
+            Option<&TargetingStrategy>,
+            Option<&TowerKind>,
+        ),
+        With<Tower>,
+    >,
+) {
+    let focus = focus.get_single().ok();
+    for (tower_transform, range, mut target, strategy, kind) in &mut towers {
+        let strategy = strategy.copied().unwrap_or_default();
+        if let Some((enemy, enemy_transform, projected_hp)) = focus {
+            if strategy.allows_focus()
+                && projected_hp.0 > 0
+                && range.is_some_and(|range| {
+                    enemy_transform
+                        .translation
+                        .distance(tower_transform.translation)
+                        < range.0
+                })
+            {
+                target.0 = Some(enemy);
+                continue;
+            }
+        }
+        let Some(range) = range else {
+            target.0 = None;
+            continue;
+        };
+        let weights =
+            ThreatWeights::for_kind(kind.map_or(ProjectileKind::Shell, |kind| kind.projectile()));
+        target.0 = strategy.pick(tower_transform.translation, range.0, weights, query.iter());
+    }
+}
+
+pub fn tower_shoot_target(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut query: Query<(Entity, &Transform, &mut ProjectedHp), With<Enemy>>,
+    assets: Res<GameAssets>,
+    commander_ability: Res<CommanderAbility>,
+    last_stand: Res<LastStand>,
+    caps: Res<EntityCaps>,
+    projectiles: Query<(), With<Projectile>>,
+    motion: Query<(&Direction, &Velocity, Option<&StatusEffects>), With<Enemy>>,
+    mut hits: EventWriter<ProjectileHit>,
+    mut towers: Query<
+        (
+            Entity,
+            &Range,
+            &FireRate,
+            &mut Cooldown,
+            &Transform,
+            &Target,
+            &TowerKind,
+            &Damage,
+        ),
+        With<Tower>,
+    >,
+) {
+    let multiplier = commander_ability.fire_rate_multiplier() * last_stand.fire_rate_multiplier();
+    let mut projectile_count = projectiles.iter().count();
+    for (tower, tower_range, fire_rate, mut cooldown, tower_transform, target, &kind, &damage) in
+        &mut towers
+    {
+        cooldown
+            .0
+            .tick(time.delta().mul_f32(fire_rate.0 * multiplier));
+        let Some(enemy) = target.0 else {
+            continue;
+        };
+
+        if let Ok((_, enemy_transform, mut enemy_projected_hp)) = query.get_mut(enemy) {
+            let distance_to_tower = enemy_transform
+                .translation
+                .distance(tower_transform.translation);
+
+            if distance_to_tower < tower_range.0
+                && cooldown.0.just_finished()
+                && enemy_projected_hp.0 > 0
+            {
+                let projectile = kind.projectile();
+                if projectile.is_hitscan() {
+                    hits.send(ProjectileHit {
+                        projectile: None,
+                        enemy,
+                        target: Some(enemy),
+                        tower,
+                        kind: projectile,
+                        damage,
+                        position: enemy_transform.translation.truncate(),
+                        splash: projectile.splash_radius(),
+                    });
+                } else if caps.allows_spawn(projectile_count, caps.projectiles) {
+                    projectile_count += 1;
+                    let mut aim = enemy_transform.translation;
+                    if let (Some(flight), Ok((direction, velocity, effects))) =
+                        (projectile.flight_time(), motion.get(enemy))
+                    {
+                        let speed =
+                            velocity.0 * effects.map_or(1.0, StatusEffects::speed_multiplier);
+                        aim += direction.0.normalize_or_zero() * speed * flight;
+                    }
+                    spawn_projectile(
+                        &mut commands,
+                        &assets,
+                        time.elapsed_secs(),
+                        projectile,
+                        damage,
+                        tower,
+                        tower_transform.translation,
+                        enemy,
+                        aim,
+                    );
+                } else {
+                    continue;
+                }
+                enemy_projected_hp.0 = enemy_projected_hp.0.saturating_sub(damage.0);
+            }
+        }
+    }
+}
+
+pub fn enemy_bounds(transform: &Transform) -> Aabb2d {
+    Aabb2d::new(
+        transform.translation.truncate(),
+        transform.scale.truncate() * ENEMY_SIZE / 2.,
+    )
+}
+
+fn swept_hit(start: Vec2, end: Vec2, bounds: Aabb2d) -> Option<f32> {
+    let projectile = Aabb2d::new(Vec2::ZERO, Vec2::splat(PROJECTILE_RADIUS));
+    match Dir2::new(end - start) {
+        Ok(direction) => AabbCast2d::new(projectile, start, direction, start.distance(end))
+            .aabb_collision_at(bounds),
+        Err(_) => Aabb2d::new(end, Vec2::splat(PROJECTILE_RADIUS))
+            .intersects(&bounds)
+            .then_some(0.0),
+    }
+}
+
+#[derive(Resource, Default)]
+struct EnemyGrid {
+    cells: HashMap<IVec2, Vec<(Entity, Aabb2d)>>,
+}
+
+impl EnemyGrid {
+    pub fn cell(position: Vec2) -> IVec2 {
+        (position / ENEMY_GRID_CELL).floor().as_ivec2()
+    }
+
+    fn candidates(&self, area: Aabb2d) -> impl Iterator<Item = &(Entity, Aabb2d)> {
+        let min = Self::cell(area.min - ENEMY_SIZE);
+        let max = Self::cell(area.max + ENEMY_SIZE);
+        (min.x..=max.x)
+            .flat_map(move |x| (min.y..=max.y).map(move |y| IVec2::new(x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+    }
+
+    fn first_hit(&self, start: Vec2, end: Vec2) -> Option<Entity> {
+        let area = Aabb2d {
+            min: start.min(end),
+            max: start.max(end),
+        };
+        self.candidates(area)
+            .filter_map(|&(enemy, bounds)| Some((swept_hit(start, end, bounds)?, enemy)))
+            .min_by(|(a, _), (b, _)| a.total_cmp(b))
+            .map(|(_, enemy)| enemy)
+    }
+
+    fn nearest_within(&self, blast: BoundingCircle) -> Option<Entity> {
+        let center = blast.center();
+        self.candidates(blast.aabb_2d())
+            .filter(|(_, bounds)| blast.intersects(bounds))
+            .min_by(|(_, a), (_, b)| {
+                let a = a.center().distance_squared(center);
+                let b = b.center().distance_squared(center);
+                a.total_cmp(&b)
+            })
+            .map(|&(enemy, _)| enemy)
+    }
+}
+
+fn update_enemy_grid(
+    mut grid: ResMut<EnemyGrid>,
+    enemies: Query<(Entity, &Transform), With<Enemy>>,
+) {
+    for cell in grid.cells.values_mut() {
+        cell.clear();
+    }
+    for (entity, transform) in &enemies {
+        grid.cells
+            .entry(EnemyGrid::cell(transform.translation.truncate()))
+            .or_default()
+            .push((entity, enemy_bounds(transform)));
+    }
+}
+
+pub fn release_projected_hp(
+    enemies: &mut Query<(&Transform, &mut Hp, &mut ProjectedHp), With<Enemy>>,
+    enemy: Option<Entity>,
+    damage: Damage,
+) {
+    if let Some(Ok((_, hp, mut projected_hp))) = enemy.map(|enemy| enemies.get_mut(enemy)) {
+        projected_hp.0 = (projected_hp.0 + damage.0).min(hp.0);
+    }
+}
+
+fn steer_homing_projectiles(
+    mut commands: Commands,
+    time: Res<Time>,
+    grid: Res<EnemyGrid>,
+    mut projectiles: Query<
+        (
+            Entity,
+            &Transform,
+            &Homing,
+            &mut Direction,
+            &mut Target,
+            &Damage,
+        ),
+        With<Projectile>,
+    >,
+    mut enemies: Query<(&Transform, &mut Hp, &mut ProjectedHp), With<Enemy>>,
+) {
+    for (projectile, transform, homing, mut direction, mut target, &damage) in &mut projectiles {
+        let position = transform.translation.truncate();
+        let alive = |enemy: Entity| enemies.get(enemy).is_ok_and(|(_, hp, _)| hp.0 > 0);
+        if !target.0.is_some_and(alive) {
+            let search = BoundingCircle::new(position, HOMING_RETARGET_RADIUS);
+            let Some(enemy) = grid.nearest_within(search).filter(|&enemy| alive(enemy)) else {
+                commands.entity(projectile).despawn();
+                continue;
+            };
+            target.0 = Some(enemy);
+            if let Ok((_, _, mut projected_hp)) = enemies.get_mut(enemy) {
+                projected_hp.0 = projected_hp.0.saturating_sub(damage.0);
+            }
+        }
+        let Some(Ok((enemy_transform, _, _))) = target.0.map(|enemy| enemies.get(enemy)) else {
+            continue;
+        };
+        let heading = direction.0.truncate();
+        let desired = enemy_transform.translation.truncate() - position;
+        let max_turn = homing.turn_rate * time.delta_secs();
+        let turn = heading.angle_to(desired).clamp(-max_turn, max_turn);
+        direction.0 = Vec2::from_angle(turn).rotate(heading).extend(0.0);
+    }
+}
+
+fn check_projectile_collision(
+    mut commands: Commands,
+    time: Res<Time>,
+    grid: Res<EnemyGrid>,
+    mut hits: EventWriter<ProjectileHit>,
+    query: Query<
+        (
+            Entity,
+            &Transform,
+            &Direction,
+            &Velocity,
+            &Target,
+            &FiredBy,
+            &ProjectileKind,
+            &Damage,
+            &SpawnedAt,
+        ),
+        With<Projectile>,
+    >,
+    mut enemies: Query<(&Transform, &mut Hp, &mut ProjectedHp), With<Enemy>>,
+    synergies: Query<&Synergy>,
+) {
+    for (
+        projectile_entity,
+        transform,
+        direction,
+        velocity,
+        target,
+        fired_by,
+        kind,
+        &reserved,
+        spawned_at,
+    ) in &query
+    {
+        let end = transform.translation.truncate();
+        let step = direction.0.truncate().normalize_or_zero() * velocity.0 * time.delta_secs();
+        let start = end - step;
+        let landed = (time.elapsed_secs() - spawned_at.0) * velocity.0 >= direction.0.length();
+        let splash = match synergies.get(fired_by.0) {
+            Ok(synergy) => synergy.splash_radius(*kind),
+            Err(_) => kind.splash_radius(),
+        };
+        let hit = if kind.hits_first_enemy() {
+            grid.first_hit(start, end)
+        } else if let Some(radius) = splash {
+            if !landed {
+                continue;
+            }
+            grid.nearest_within(BoundingCircle::new(end, radius))
+        } else {
+            let Some(Ok((enemy_transform, _, _))) = target.0.map(|enemy| enemies.get(enemy)) else {
+                commands.entity(projectile_entity).despawn();
+                continue;
+            };
+            swept_hit(start, end, enemy_bounds(enemy_transform)).and(target.0)
+        };
+
+        let Some(enemy) = hit else {
+            let missed = landed && splash.is_some();
+            if missed || time.elapsed_secs() - spawned_at.0 > PROJECTILE_LIFETIME {
+                release_projected_hp(&mut enemies, target.0, reserved);
+                commands.entity(projectile_entity).despawn();
+            }
+            continue;
+        };
+        hits.send(ProjectileHit {
+            projectile: Some(projectile_entity),
+            enemy,
+            target: target.0,
+            tower: fired_by.0,
+            kind: *kind,
+            damage: reserved,
+            position: end,
+            splash,
+        });
+    }
+}
+
+fn apply_projectile_hits(
+    mut commands: Commands,
+    mut hits: EventReader<ProjectileHit>,
+    grid: Res<EnemyGrid>,
+    mut enemies: Query<(&Transform, &mut Hp, &mut ProjectedHp), With<Enemy>>,
+    mut defenses: Query<(&Armor, &Resistances, &mut StatusEffects, &EnemyKind), With<Enemy>>,
+    mut towers: Query<(&mut UltimateCharge, &mut Kills), With<Tower>>,
+    mut arcs: EventWriter<ChainArc>,
+    mut splashed: Local<Vec<(Entity, f32)>>,
+) {
+    for &ProjectileHit {
+        projectile,
+        enemy,
+        target,
+        tower,
+        kind,
+        damage: reserved,
+        position,
+        splash,
+    } in hits.read()
+    {
+        if target != Some(enemy) {
+            release_projected_hp(&mut enemies, target, reserved);
+        }
+        let damage = match defenses.get_mut(enemy) {
+            Ok((armor, resistances, mut effects, enemy_kind)) => {
+                let damage = effective_damage(reserved, kind, armor, resistances, &effects)
+                    .min(enemy_kind.max_hit());
+                match kind {
+                    ProjectileKind::Acid => {
+                        effects.apply(
+                            StatusKind::ArmorShred(ACID_SHRED),
+                            ACID_DURATION,
+                            Stacking::Stack(ACID_MAX_STACKS),
+                        );
+                        effects.apply(
+                            StatusKind::Poisoned(POISON_DAMAGE),
+                            POISON_DURATION,
+                            Stacking::Stack(POISON_MAX_STACKS),
+                        );
+                    }
+                    ProjectileKind::Frost => effects.apply(
+                        StatusKind::Slowed(FROST_SLOW),
+                        FROST_DURATION,
+                        Stacking::Stack(FROST_MAX_STACKS),
+                    ),
+                    ProjectileKind::Bomb => effects.apply(
+                        StatusKind::Burning(BURN_DAMAGE),
+                        BURN_DURATION,
+                        Stacking::Refresh,
+                    ),
+                    ProjectileKind::Lightning => {
+                        effects.apply(StatusKind::Stunned, STUN_DURATION, Stacking::Refresh)
+                    }
+                    ProjectileKind::Shell
+                    | ProjectileKind::Bolt
+                    | ProjectileKind::Slug
+                    | ProjectileKind::Missile => {}
+                }
+                damage
+            }
+            Err(_) => reserved.0,
+        };
+        if let Ok((_, mut enemy_hp, mut projected_hp)) = enemies.get_mut(enemy) {
+            let was_alive = enemy_hp.0 > 0;
+            enemy_hp.0 = enemy_hp.0.saturating_sub(damage);
+            projected_hp.0 = if target == Some(enemy) {
+                (projected_hp.0 + reserved.0 - damage).min(enemy_hp.0)
+            } else {
+                projected_hp.0.saturating_sub(damage)
+            };
+            if let Ok((_, mut kills)) = towers.get_mut(tower) {
+                if was_alive && enemy_hp.0 == 0 {
+                    kills.0 += 1;
+                }
+            }
+        }
+        splashed.clear();
+        if let Some(radius) = splash {
+            let blast = BoundingCircle::new(position, radius);
+            splashed.extend(
+                grid.candidates(blast.aabb_2d())
+                    .filter(|&&(other, bounds)| other != enemy && blast.intersects(&bounds))
+                    .map(|&(other, bounds)| {
+                        let distance = bounds
+                            .closest_point(blast.center())
+                            .distance(blast.center());
+                        (other, 1.0 - (1.0 - SPLASH_MIN_FALLOFF) * distance / radius)
+                    }),
+            );
+        }
+        if let Some((jumps, radius)) = kind.chain() {
+            let mut arc = vec![position];
+            let mut falloff = 1.0;
+            for _ in 0..jumps {
+                let reach = BoundingCircle::new(arc[arc.len() - 1], radius);
+                let next = grid
+                    .candidates(reach.aabb_2d())
+                    .filter(|&&(other, bounds)| {
+                        other != enemy
+                            && reach.intersects(&bounds)
+                            && !splashed.iter().any(|&(hit, _)| hit == other)
+                            && enemies.get(other).is_ok_and(|(_, hp, _)| hp.0 > 0)
+                    })
+                    .min_by(|(_, a), (_, b)| {
+                        let a = a.center().distance_squared(reach.center());
+                        let b = b.center().distance_squared(reach.center());
+                        a.total_cmp(&b)
+                    });
+                let Some(&(other, bounds)) = next else {
+                    break;
+                };
+                falloff *= CHAIN_FALLOFF;
+                splashed.push((other, falloff));
+                arc.push(bounds.center());
+            }
+            if arc.len() > 1 {
+                arcs.send(ChainArc(arc));
+            }
+        }
+        for &(other, falloff) in splashed.iter() {
+            let splash = Damage((reserved.0 as f32 * falloff) as u32);
+            let damage = match defenses.get(other) {
+                Ok((armor, resistances, effects, enemy_kind)) => {
+                    effective_damage(splash, kind, armor, resistances, effects)
+                        .min(enemy_kind.max_hit())
+                }
+                Err(_) => splash.0,
+            };
+            if let Ok((_, mut enemy_hp, mut projected_hp)) = enemies.get_mut(other) {
+                let was_alive = enemy_hp.0 > 0;
+                enemy_hp.0 = enemy_hp.0.saturating_sub(damage);
+                projected_hp.0 = projected_hp.0.saturating_sub(damage);
+                if let Ok((_, mut kills)) = towers.get_mut(tower) {
+                    if was_alive && enemy_hp.0 == 0 {
+                        kills.0 += 1;
+                    }
+                }
+            }
+        }
+        if let Ok((mut charge, _)) = towers.get_mut(tower) {
+            charge.0 = (charge.0 + reserved.0).min(ULTIMATE_CHARGE);
+        }
+        if let Some(projectile) = projectile {
+            commands.entity(projectile).despawn();
+        }
+    }
+}
+
+pub fn despawn_dead_enemies(
+    mut commands: Commands,
+    query: Query<
+        (
+            Entity,
+            &Transform,
+            &Hp,
+            &EnemyKind,
+            &Bounty,
+            Option<&Generation>,
+        ),
+        With<Enemy>,
+    >,
+    assets: Res<GameAssets>,
+    watchdog: Res<FrameWatchdog>,
+    time: Res<Time>,
+    caps: Res<EntityCaps>,
+    corpses: Query<(), With<Corpse>>,
+    visuals: Res<VisualAccessibility>,
+    mut rng: ResMut<GameRng>,
+    mut boss_killed: EventWriter<BossKilled>,
+    mut enemy_killed: EventWriter<EnemyKilled>,
+) {
+    let mut corpse_count = corpses.iter().count();
+    for (entity, transform, hp, kind, bounty, generation) in &query {
+        if hp.0 == 0 {
+            enemy_killed.send(EnemyKilled {
+                kind: *kind,
+                bounty: *bounty,
+                generation: generation.map_or(0, |generation| generation.0),
+            });
+            if kind.is_boss() {
+                boss_killed.send(BossKilled(transform.translation));
+            }
+            commands.entity(entity).despawn();
+            let scatter = Vec2::from_angle(rng.0.gen_range(0.0..std::f32::consts::TAU));
+            let scatter_speed = rng.0.gen_range(10.0..30.0);
+            if watchdog.degraded || !caps.allows_spawn(corpse_count, caps.corpses) {
+                continue;
+            }
+            corpse_count += 1;
+            let mut corpse = commands.spawn((
+                Mesh2d(assets.enemy_mesh.clone()),
+                MeshMaterial2d(assets.corpse_material.clone()),
+                Transform::from_translation(transform.translation.with_z(CORPSE_LAYER)),
+                Corpse,
+                SpawnedAt(time.elapsed_secs()),
+                Velocity(if visuals.reduced_motion {
+                    0.0
+                } else {
+                    scatter_speed
+                }),
+                Direction(scatter.extend(0.0)),
+                DespawnOnTweenComplete,
+                Tween::new(
+                    ScaleLens {
+                        start: transform.scale,
+                        end: Vec3::ZERO,
+                    },
+                    CORPSE_LIFETIME,
+                    EaseFunction::QuadraticIn,
+                ),
+            ));
+            if kind.raisable() {
+                corpse.insert(Raisable);
+            }
+        }
+    }
+}
+
+fn clear_splashed_corpses(
+    mut commands: Commands,
+    mut hits: EventReader<ProjectileHit>,
+    corpses: Query<(Entity, &Transform), With<Corpse>>,
+) {
+    for hit in hits.read() {
+        let Some(radius) = hit.splash else {
+            continue;
+        };
+        for (corpse, transform) in &corpses {
+            if transform.translation.truncate().distance(hit.position) <= radius {
+                commands.entity(corpse).despawn();
+            }
+        }
+    }
+}
+
+pub struct CombatPlugin;
+
+impl Plugin for CombatPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<EnemyKilled>();
+        app.add_event::<BossKilled>();
+        app.add_event::<ProjectileHit>();
+        app.add_event::<ChainArc>();
+        app.add_event::<EnemyReachedBase>();
+        app.init_resource::<EnemyGrid>();
+        app.add_systems(
+            FixedUpdate,
+            (
+                despawn_dead_enemies,
+                (
+                    reveal_cloaked_enemies,
+                    tower_choose_target,
+                    tower_shoot_target,
+                )
+                    .chain()
+                    .after(follow_path),
+                (
+                    update_enemy_grid,
+                    check_projectile_collision,
+                    apply_projectile_hits,
+                    clear_splashed_corpses,
+                )
+                    .chain()
+                    .after(lift_lobbed_projectiles),
+                steer_homing_projectiles.before(update_projectiles_position),
+                damage_towers_on_contact,
+                (check_enemy_base_collision, resolve_base_hits)
+                    .chain()
+                    .after(follow_path),
+            )
+                .in_set(SimulationSet::Step),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Mutators;
+    use crate::enemy::{update_enemy_position, AirPath, Enrage, Lane, Path};
+    use std::time::Duration;
+
+    #[test]
+    fn fast_enemy_still_leaks() {
+        let mut app = App::new();
+        app.init_resource::<Time>()
+            .init_resource::<Mutators>()
+            .init_resource::<Enrage>()
+            .init_resource::<Path>()
+            .init_resource::<AirPath>()
+            .add_event::<EnemyReachedBase>()
+            .add_systems(
+                Update,
+                (
+                    update_enemy_position,
+                    follow_path,
+                    check_enemy_base_collision,
+                )
+                    .chain(),
+            );
+        app.world_mut().spawn((Transform::default(), Base));
+        let origin = Vec3::new(-1000.0, 0.0, 0.0);
+        app.world_mut().spawn((
+            Enemy,
+            EnemyKind::Grunt,
+            Lane::Ground,
+            Transform::from_translation(origin),
+            Direction(Vec3::X),
+            Velocity(15_000.0),
+            PathProgress::new(origin),
+        ));
+
+        let mut leaked = false;
+        for _ in 0..4 {
+            app.world_mut()
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_millis(100));
+            app.update();
+            leaked |= !app
+                .world()
+                .resource::<Events<EnemyReachedBase>>()
+                .is_empty();
+        }
+        assert!(leaked);
+    }
+}
@@ -0,0 +1,348 @@
+use bevy::prelude::*;
+use rand::prelude::*;
+
+#[derive(Resource)]
+pub struct Gold(pub u32);
+
+#[derive(Resource)]
+pub struct Lives(pub u32);
+
+#[derive(Resource, Default)]
+pub struct Shield(pub u32);
+
+#[derive(Resource, Default)]
+pub struct RunClock(pub f32);
+
+#[derive(Resource, Default)]
+pub struct Score(pub u32);
+
+#[derive(Resource)]
+pub struct GameRng(pub StdRng);
+
+impl Default for GameRng {
+    fn default() -> Self {
+        GameRng(StdRng::from_entropy())
+    }
+}
+
+#[derive(Resource, Clone, Copy, Default)]
+pub struct Mutators(pub u32);
+
+impl Mutators {
+    const FAST_ENEMIES: u32 = 1 << 0;
+    const HALF_BOUNTIES: u32 = 1 << 1;
+    const FREQUENT_BOSSES: u32 = 1 << 2;
+    const NO_SELLING: u32 = 1 << 3;
+    const HALF_GOLD: u32 = 1 << 4;
+    const TWO_TOWER_KINDS: u32 = 1 << 5;
+    const DOUBLE_SPEED_ENEMIES: u32 = 1 << 6;
+
+    const NAMES: [(u32, &'static str); 7] = [
+        (Self::FAST_ENEMIES, "fast enemies"),
+        (Self::HALF_BOUNTIES, "half bounties"),
+        (Self::FREQUENT_BOSSES, "frequent bosses"),
+        (Self::NO_SELLING, "no selling"),
+        (Self::HALF_GOLD, "half gold"),
+        (Self::TWO_TOWER_KINDS, "only two tower kinds"),
+        (Self::DOUBLE_SPEED_ENEMIES, "double speed enemies"),
+    ];
+    pub const CUSTOM_RULES: [u32; 4] = [
+        Self::NO_SELLING,
+        Self::HALF_GOLD,
+        Self::TWO_TOWER_KINDS,
+        Self::DOUBLE_SPEED_ENEMIES,
+    ];
+
+    pub fn from_seed(seed: u64) -> Self {
+        Mutators(seed as u32 & (Self::FAST_ENEMIES | Self::HALF_BOUNTIES | Self::FREQUENT_BOSSES))
+    }
+
+    pub fn has(self, mutator: u32) -> bool {
+        self.0 & mutator != 0
+    }
+
+    pub fn name(mutator: u32) -> &'static str {
+        Self::NAMES
+            .iter()
+            .find(|&&(flag, _)| flag == mutator)
+            .map_or("unknown", |&(_, name)| name)
+    }
+
+    pub fn enemy_speed(self) -> f32 {
+        let mut speed = 1.0;
+        if self.has(Self::FAST_ENEMIES) {
+            speed *= 1.5;
+        }
+        if self.has(Self::DOUBLE_SPEED_ENEMIES) {
+            speed *= 2.0;
+        }
+        speed
+    }
+
+    pub fn bounty(self, bounty: Bounty) -> u32 {
+        if self.has(Self::HALF_BOUNTIES) {
+            bounty.0 / 2
+        } else {
+            bounty.0
+        }
+    }
+
+    pub fn boss_interval(self) -> u32 {
+        if self.has(Self::FREQUENT_BOSSES) {
+            BOSS_SPAWN_INTERVAL / 2
+        } else {
+            BOSS_SPAWN_INTERVAL
+        }
+    }
+
+    pub fn starting_gold(self) -> u32 {
+        if self.has(Self::HALF_GOLD) {
+            STARTING_GOLD / 2
+        } else {
+            STARTING_GOLD
+        }
+    }
+
+    pub fn selling_allowed(self) -> bool {
+        !self.has(Self::NO_SELLING)
+    }
+
+    pub fn tower_kind_limit(self) -> Option<usize> {
+        self.has(Self::TWO_TOWER_KINDS).then_some(2)
+    }
+
+    pub fn describe(self) -> String {
+        let names: Vec<&str> = Self::NAMES
+            .iter()
+            .filter(|&&(flag, _)| self.has(flag))
+            .map(|&(_, name)| name)
+            .collect();
+
+        if names.is_empty() {
+            "no mutators".to_string()
+        } else {
+            names.join(", ")
+        }
+    }
+}
+
+pub const STARTING_GOLD: u32 = 100;
+pub const STARTING_LIVES: u32 = 20;
+
+const BOSS_SPAWN_INTERVAL: u32 = 20;
+
+#[derive(Component)]
+pub struct Enemy;
+
+#[derive(Component, Clone, Copy)]
+pub struct Bounty(pub u32);
+
+pub const BASE_SIZE: f32 = 40.0;
+
+#[derive(Component)]
+pub struct Base;
+
+#[derive(Component)]
+pub struct Tower;
+
+#[derive(Component, Clone, Copy)]
+pub struct Damage(pub u32);
+
+#[derive(Component)]
+pub struct Kills(pub u32);
+
+#[derive(Component)]
+pub struct Selected;
+
+#[derive(Component)]
+pub struct Target(pub Option<Entity>);
+
+#[derive(Component)]
+pub struct Hp(pub u32);
+
+#[derive(Component)]
+pub struct ProjectedHp(pub u32);
+
+#[derive(Component)]
+pub struct MaxHp(pub u32);
+
+#[derive(Component)]
+pub struct Dependents(pub Vec<Entity>);
+
+#[derive(Resource)]
+pub struct LoadErrors {
+    strict: bool,
+    pending: Vec<String>,
+}
+
+impl LoadErrors {
+    pub fn from_args() -> Self {
+        LoadErrors {
+            strict: std::env::args().any(|arg| arg == "--strict-assets"),
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn report(&mut self, message: String) {
+        if self.strict {
+            panic!("{message}");
+        }
+        warn!("{message}");
+        self.pending.push(message);
+    }
+
+    pub fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    pub fn take(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.pending)
+    }
+}
+
+pub const HEALTH_BAR_WIDTH: f32 = 12.0;
+
+#[derive(Resource)]
+pub struct GameAssets {
+    pub enemy_mesh: Handle<Mesh>,
+    pub enemy_material: Handle<ColorMaterial>,
+    pub flyer_material: Handle<ColorMaterial>,
+    pub boss_material: Handle<ColorMaterial>,
+    pub tank_material: Handle<ColorMaterial>,
+    pub runner_material: Handle<ColorMaterial>,
+    pub warlord_material: Handle<ColorMaterial>,
+    pub splitter_material: Handle<ColorMaterial>,
+    pub carrier_material: Handle<ColorMaterial>,
+    pub healer_material: Handle<ColorMaterial>,
+    pub phantom_material: Handle<ColorMaterial>,
+    pub dropship_material: Handle<ColorMaterial>,
+    pub necromancer_material: Handle<ColorMaterial>,
+    pub zombie_material: Handle<ColorMaterial>,
+    pub revealed_material: Handle<ColorMaterial>,
+    pub hit_flash_material: Handle<ColorMaterial>,
+    pub slowed_material: Handle<ColorMaterial>,
+    pub burning_material: Handle<ColorMaterial>,
+    pub poisoned_material: Handle<ColorMaterial>,
+    pub health_bar_mesh: Handle<Mesh>,
+    pub health_bar_material: Handle<ColorMaterial>,
+    pub projectile_mesh: Handle<Mesh>,
+    pub projectile_material: Handle<ColorMaterial>,
+    pub acid_projectile_material: Handle<ColorMaterial>,
+    pub frost_projectile_material: Handle<ColorMaterial>,
+    pub corpse_material: Handle<ColorMaterial>,
+    pub base_mesh: Handle<Mesh>,
+    pub base_material: Handle<ColorMaterial>,
+    pub tower_mesh: Handle<Mesh>,
+    pub tower_material: Handle<ColorMaterial>,
+    pub acid_tower_material: Handle<ColorMaterial>,
+    pub arrow_tower_material: Handle<ColorMaterial>,
+    pub frost_tower_material: Handle<ColorMaterial>,
+    pub mortar_tower_material: Handle<ColorMaterial>,
+    pub sniper_tower_material: Handle<ColorMaterial>,
+    pub missile_tower_material: Handle<ColorMaterial>,
+    pub tesla_tower_material: Handle<ColorMaterial>,
+    pub planned_tower_material: Handle<ColorMaterial>,
+    pub valid_placement_material: Handle<ColorMaterial>,
+    pub invalid_placement_material: Handle<ColorMaterial>,
+    pub telegraph_mesh: Handle<Mesh>,
+    pub telegraph_material: Handle<ColorMaterial>,
+    pub heal_pulse_mesh: Handle<Mesh>,
+    pub heal_pulse_material: Handle<ColorMaterial>,
+    pub rubble_material: Handle<ColorMaterial>,
+    pub shield_generator_material: Handle<ColorMaterial>,
+}
+
+impl FromWorld for GameAssets {
+    fn from_world(world: &mut World) -> Self {
+        let color = Color::hsl(360., 0.95, 0.7);
+        let mut meshes = world.resource_mut::<Assets<Mesh>>();
+        let enemy_mesh = meshes.add(Rectangle::new(10.0, 10.0));
+        let health_bar_mesh = meshes.add(Rectangle::new(HEALTH_BAR_WIDTH, 2.0));
+        let projectile_mesh = meshes.add(Circle::new(5.0));
+        let tower_mesh = meshes.add(Rectangle::new(24.0, 24.0));
+        let telegraph_mesh = meshes.add(Circle::new(6.0));
+        let heal_pulse_mesh = meshes.add(Annulus::new(0.9, 1.0));
+        let base_mesh = meshes.add(Rectangle::new(BASE_SIZE, BASE_SIZE));
+
+        let mut materials = world.resource_mut::<Assets<ColorMaterial>>();
+        GameAssets {
+            enemy_mesh,
+            enemy_material: materials.add(color),
+            flyer_material: materials.add(Color::hsl(180., 0.8, 0.7)),
+            boss_material: materials.add(Color::hsl(280., 0.8, 0.6)),
+            tank_material: materials.add(Color::hsl(30., 0.5, 0.4)),
+            runner_material: materials.add(Color::hsl(60., 0.9, 0.6)),
+            warlord_material: materials.add(Color::hsl(320., 0.9, 0.45)),
+            splitter_material: materials.add(Color::hsl(140., 0.7, 0.5)),
+            carrier_material: materials.add(Color::hsl(220., 0.6, 0.45)),
+            healer_material: materials.add(Color::hsl(90., 0.8, 0.7)),
+            phantom_material: materials.add(Color::hsla(270., 0.3, 0.8, 0.25)),
+            dropship_material: materials.add(Color::hsl(200., 0.5, 0.55)),
+            necromancer_material: materials.add(Color::hsl(260., 0.5, 0.3)),
+            zombie_material: materials.add(Color::hsl(100., 0.25, 0.45)),
+            revealed_material: materials.add(Color::hsl(270., 0.6, 0.75)),
+            hit_flash_material: materials.add(Color::WHITE),
+            slowed_material: materials.add(Color::hsl(195., 0.8, 0.8)),
+            burning_material: materials.add(Color::hsl(25., 0.9, 0.6)),
+            poisoned_material: materials.add(Color::hsl(290., 0.6, 0.6)),
+            health_bar_mesh,
+            health_bar_material: materials.add(Color::hsl(120., 0.8, 0.5)),
+            projectile_mesh,
+            projectile_material: materials.add(color),
+            acid_projectile_material: materials.add(Color::hsl(90., 0.9, 0.55)),
+            frost_projectile_material: materials.add(Color::hsl(190., 0.9, 0.8)),
+            corpse_material: materials.add(Color::hsla(360., 0.4, 0.3, 0.6)),
+            base_mesh,
+            base_material: materials.add(color),
+            tower_mesh,
+            tower_material: materials.add(Color::hsl(200., 0.7, 0.6)),
+            acid_tower_material: materials.add(Color::hsl(90., 0.7, 0.5)),
+            arrow_tower_material: materials.add(Color::hsl(40., 0.6, 0.55)),
+            frost_tower_material: materials.add(Color::hsl(190., 0.7, 0.75)),
+            mortar_tower_material: materials.add(Color::hsl(10., 0.5, 0.45)),
+            sniper_tower_material: materials.add(Color::hsl(270., 0.4, 0.4)),
+            missile_tower_material: materials.add(Color::hsl(150., 0.35, 0.4)),
+            tesla_tower_material: materials.add(Color::hsl(55., 0.7, 0.6)),
+            planned_tower_material: materials.add(Color::hsla(200., 0.7, 0.6, 0.35)),
+            valid_placement_material: materials.add(Color::hsla(120., 0.8, 0.5, 0.45)),
+            invalid_placement_material: materials.add(Color::hsla(0., 0.8, 0.5, 0.45)),
+            telegraph_mesh,
+            telegraph_material: materials.add(Color::hsla(50., 1.0, 0.6, 0.8)),
+            heal_pulse_mesh,
+            heal_pulse_material: materials.add(Color::hsla(90., 0.8, 0.6, 0.5)),
+            rubble_material: materials.add(Color::hsl(30., 0.15, 0.3)),
+            shield_generator_material: materials.add(Color::hsl(220., 0.9, 0.7)),
+        }
+    }
+}
+
+#[derive(States, Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum GameState {
+    #[default]
+    Loading,
+    Menu,
+    Playing,
+    Paused,
+    GameOver,
+}
+
+pub fn simulating(state: Res<State<GameState>>, next_state: Res<NextState<GameState>>) -> bool {
+    *state.get() == GameState::Playing && matches!(*next_state, NextState::Unchanged)
+}
+
+pub fn in_run(state: Res<State<GameState>>) -> bool {
+    matches!(state.get(), GameState::Playing | GameState::Paused)
+}
+
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SimulationSet {
+    Step,
+    Limit,
+    Record,
+}
+
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FrameSet {
+    Input,
+    Hud,
+}
@@ -0,0 +1,326 @@
+use crate::accessibility::Accessibility;
+use crate::common::{FrameSet, GameAssets, Kills, Tower};
+use crate::enemy::Waves;
+use crate::profile::Profile;
+use crate::run_modes::DemoMode;
+use crate::theme::{ThemedPanel, ThemedText};
+use crate::tower::TowerKind;
+use crate::ui::spawn_toast;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+const VETERAN_KILLS: u32 = 50;
+const SURVIVOR_WAVE: u32 = 20;
+const COSMETICS_PANEL: Color = Color::srgba(0.1, 0.1, 0.15, 0.95);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Achievement {
+    Veteran(TowerKind),
+    Survivor,
+}
+
+impl Achievement {
+    pub fn name(self) -> String {
+        match self {
+            Achievement::Veteran(kind) => format!("veteran-{}", kind.name()),
+            Achievement::Survivor => "survivor".to_string(),
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.split_once('-') {
+            Some(("veteran", kind)) => TowerKind::from_name(kind).map(Achievement::Veteran),
+            _ => (name == "survivor").then_some(Achievement::Survivor),
+        }
+    }
+
+    fn describe(self) -> String {
+        match self {
+            Achievement::Veteran(kind) => {
+                format!("one {} tower reaches {VETERAN_KILLS} kills", kind.name())
+            }
+            Achievement::Survivor => format!("reach wave {SURVIVOR_WAVE}"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TowerSkin {
+    Standard,
+    Veteran,
+    Gilded,
+}
+
+impl TowerSkin {
+    pub const ALL: [TowerSkin; 3] = [TowerSkin::Standard, TowerSkin::Veteran, TowerSkin::Gilded];
+    pub const UNLOCKABLE: [TowerSkin; 2] = [TowerSkin::Veteran, TowerSkin::Gilded];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TowerSkin::Standard => "standard",
+            TowerSkin::Veteran => "veteran",
+            TowerSkin::Gilded => "gilded",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|skin| skin.name() == name)
+    }
+
+    fn unlocked_by(self, kind: TowerKind) -> Option<Achievement> {
+        match self {
+            TowerSkin::Standard => None,
+            TowerSkin::Veteran => Some(Achievement::Veteran(kind)),
+            TowerSkin::Gilded => Some(Achievement::Survivor),
+        }
+    }
+
+    pub fn color(self, kind: TowerKind) -> Color {
+        let hue = match kind {
+            TowerKind::Cannon => 200.,
+            TowerKind::Arrow => 40.,
+            TowerKind::Acid => 90.,
+            TowerKind::Frost => 190.,
+            TowerKind::Mortar => 10.,
+            TowerKind::Sniper => 270.,
+            TowerKind::Missile => 150.,
+            TowerKind::Tesla => 55.,
+        };
+        match self {
+            TowerSkin::Standard => Color::hsl(hue, 0.7, 0.6),
+            TowerSkin::Veteran => Color::hsl(hue, 0.9, 0.3),
+            TowerSkin::Gilded => Color::hsl(45. + hue / 36., 0.9, 0.55),
+        }
+    }
+}
+
+#[derive(Resource, Clone, Default)]
+pub struct Cosmetics {
+    pub achievements: Vec<Achievement>,
+    pub skins: Vec<(TowerKind, TowerSkin)>,
+}
+
+impl Cosmetics {
+    fn skin(&self, kind: TowerKind) -> TowerSkin {
+        self.skins
+            .iter()
+            .find(|(skinned, _)| *skinned == kind)
+            .map_or(TowerSkin::Standard, |&(_, skin)| skin)
+    }
+
+    fn unlocked(&self, kind: TowerKind, skin: TowerSkin) -> bool {
+        skin.unlocked_by(kind)
+            .is_none_or(|achievement| self.achievements.contains(&achievement))
+    }
+
+    fn cycle_skin(&mut self, kind: TowerKind, forward: bool) {
+        let unlocked: Vec<TowerSkin> = TowerSkin::ALL
+            .into_iter()
+            .filter(|&skin| self.unlocked(kind, skin))
+            .collect();
+        let index = unlocked
+            .iter()
+            .position(|&skin| skin == self.skin(kind))
+            .unwrap_or(0);
+        let step = if forward { 1 } else { unlocked.len() - 1 };
+        let skin = unlocked[(index + step) % unlocked.len()];
+        self.skins.retain(|(skinned, _)| *skinned != kind);
+        if skin != TowerSkin::Standard {
+            self.skins.push((kind, skin));
+        }
+    }
+
+    fn save(&self) {
+        let mut profile = Profile::load();
+        profile.cosmetics = self.clone();
+        profile.save();
+    }
+}
+
+#[derive(Resource)]
+struct CosmeticsScreen {
+    index: usize,
+}
+
+#[derive(Component)]
+struct CosmeticsText;
+
+fn cosmetics_text(cosmetics: &Cosmetics, index: usize) -> String {
+    let mut text = String::from("Tower skins\n");
+    for (row, kind) in TowerKind::ALL.into_iter().enumerate() {
+        let cursor = if row == index { ">" } else { " " };
+        text.push_str(&format!(
+            "{cursor} {}: {}\n",
+            kind.name(),
+            cosmetics.skin(kind).name()
+        ));
+    }
+    let kind = TowerKind::ALL[index];
+    for skin in TowerSkin::UNLOCKABLE {
+        if let Some(achievement) = skin
+            .unlocked_by(kind)
+            .filter(|_| !cosmetics.unlocked(kind, skin))
+        {
+            text.push_str(&format!(
+                "\nLocked {}: {}",
+                skin.name(),
+                achievement.describe()
+            ));
+        }
+    }
+    text.push_str("\n\nUp/Down to choose, Left/Right to change skin, Esc to close");
+    text
+}
+
+fn open_cosmetics_screen(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    cosmetics: Res<Cosmetics>,
+    screen: Option<Res<CosmeticsScreen>>,
+) {
+    if !keys.just_pressed(KeyCode::F12) || screen.is_some() {
+        return;
+    }
+    commands.spawn((
+        Text::new(cosmetics_text(&cosmetics, 0)),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(25.0),
+            left: Val::Percent(35.0),
+            width: Val::Percent(30.0),
+            padding: UiRect::all(Val::Px(16.0)),
+            ..default()
+        },
+        BackgroundColor(COSMETICS_PANEL),
+        ThemedPanel(COSMETICS_PANEL),
+        ThemedText,
+        CosmeticsText,
+    ));
+    commands.insert_resource(CosmeticsScreen { index: 0 });
+}
+
+fn navigate_cosmetics_screen(
+    mut commands: Commands,
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    mut screen: ResMut<CosmeticsScreen>,
+    mut cosmetics: ResMut<Cosmetics>,
+    mut text: Query<(Entity, &mut Text), With<CosmeticsText>>,
+) {
+    let close = keys.any_just_pressed([KeyCode::Escape, KeyCode::F12]);
+    if keys.just_pressed(KeyCode::ArrowUp) {
+        screen.index = screen.index.saturating_sub(1);
+    }
+    if keys.just_pressed(KeyCode::ArrowDown) {
+        screen.index = (screen.index + 1).min(TowerKind::ALL.len() - 1);
+    }
+    let kind = TowerKind::ALL[screen.index];
+    for (key, forward) in [(KeyCode::ArrowLeft, false), (KeyCode::ArrowRight, true)] {
+        if keys.just_pressed(key) {
+            cosmetics.cycle_skin(kind, forward);
+            cosmetics.save();
+        }
+    }
+    keys.reset_all();
+
+    if close {
+        commands.remove_resource::<CosmeticsScreen>();
+        for (entity, _) in &text {
+            commands.entity(entity).despawn();
+        }
+        return;
+    }
+    for (_, mut text) in &mut text {
+        text.0 = cosmetics_text(&cosmetics, screen.index);
+    }
+}
+
+fn unlock_achievements(
+    mut commands: Commands,
+    mut cosmetics: ResMut<Cosmetics>,
+    towers: Query<(&TowerKind, &Kills), Changed<Kills>>,
+    waves: Res<Waves>,
+) {
+    let veterans = towers
+        .iter()
+        .filter(|(_, kills)| kills.0 >= VETERAN_KILLS)
+        .map(|(&kind, _)| Achievement::Veteran(kind));
+    let survivor = (waves.number >= SURVIVOR_WAVE).then_some(Achievement::Survivor);
+    let mut unlocked = false;
+    for achievement in veterans.chain(survivor) {
+        if cosmetics.achievements.contains(&achievement) {
+            continue;
+        }
+        unlocked = true;
+        cosmetics.achievements.push(achievement);
+        spawn_toast(
+            &mut commands,
+            &format!(
+                "Achievement: {}, new tower skin unlocked",
+                achievement.describe()
+            ),
+        );
+    }
+    if unlocked {
+        cosmetics.save();
+    }
+}
+
+#[derive(Resource)]
+struct SkinMaterials(HashMap<(TowerKind, TowerSkin), Handle<ColorMaterial>>);
+
+impl FromWorld for SkinMaterials {
+    fn from_world(world: &mut World) -> Self {
+        let mut materials = world.resource_mut::<Assets<ColorMaterial>>();
+        SkinMaterials(
+            TowerKind::ALL
+                .into_iter()
+                .flat_map(|kind| TowerSkin::UNLOCKABLE.map(|skin| (kind, skin)))
+                .map(|(kind, skin)| ((kind, skin), materials.add(skin.color(kind))))
+                .collect(),
+        )
+    }
+}
+
+fn apply_tower_skins(
+    assets: Res<GameAssets>,
+    skins: Res<SkinMaterials>,
+    cosmetics: Res<Cosmetics>,
+    accessibility: Res<Accessibility>,
+    mut towers: Query<(Ref<Tower>, &TowerKind, &mut MeshMaterial2d<ColorMaterial>)>,
+) {
+    for (tower, &kind, mut material) in &mut towers {
+        if !tower.is_added() && !cosmetics.is_changed() && !accessibility.is_changed() {
+            continue;
+        }
+        let skin = if accessibility.high_contrast {
+            TowerSkin::Standard
+        } else {
+            cosmetics.skin(kind)
+        };
+        material.0 = skins
+            .0
+            .get(&(kind, skin))
+            .cloned()
+            .unwrap_or_else(|| kind.material(&assets));
+    }
+}
+
+pub struct CosmeticsPlugin;
+
+impl Plugin for CosmeticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Profile::load().cosmetics);
+        app.init_resource::<SkinMaterials>();
+        app.add_systems(
+            Update,
+            (
+                navigate_cosmetics_screen
+                    .run_if(resource_exists::<CosmeticsScreen>)
+                    .before(FrameSet::Input),
+                open_cosmetics_screen.in_set(FrameSet::Input),
+                unlock_achievements.run_if(not(resource_exists::<DemoMode>)),
+                apply_tower_skins.after(FrameSet::Input),
+            ),
+        );
+    }
+}
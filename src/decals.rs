@@ -0,0 +1,93 @@
+use crate::combat::ProjectileHit;
+use bevy::prelude::*;
+
+const DECAL_POOL_SIZE: usize = 16;
+const DECAL_SECONDS: f32 = 3.0;
+const DECAL_ALPHA: f32 = 0.6;
+const DECAL_LAYER: f32 = -1.2;
+
+#[derive(Component)]
+struct Decal {
+    age: f32,
+}
+
+#[derive(Resource, Default)]
+struct DecalPool {
+    decals: Vec<Entity>,
+    next: usize,
+}
+
+fn decal_color(alpha: f32) -> Color {
+    Color::hsla(20., 0.3, 0.1, alpha)
+}
+
+fn setup_decal_pool(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut pool: ResMut<DecalPool>,
+) {
+    let mesh = meshes.add(Circle::new(1.0));
+    for _ in 0..DECAL_POOL_SIZE {
+        let decal = commands
+            .spawn((
+                Mesh2d(mesh.clone()),
+                MeshMaterial2d(materials.add(decal_color(0.0))),
+                Transform::from_xyz(0.0, 0.0, DECAL_LAYER),
+                Visibility::Hidden,
+                Decal { age: DECAL_SECONDS },
+            ))
+            .id();
+        pool.decals.push(decal);
+    }
+}
+
+fn place_impact_decals(
+    mut hits: EventReader<ProjectileHit>,
+    mut pool: ResMut<DecalPool>,
+    mut decals: Query<(&mut Decal, &mut Transform, &mut Visibility)>,
+) {
+    for hit in hits.read() {
+        let Some(radius) = hit.splash else {
+            continue;
+        };
+        let entity = pool.decals[pool.next];
+        pool.next = (pool.next + 1) % pool.decals.len();
+        if let Ok((mut decal, mut transform, mut visibility)) = decals.get_mut(entity) {
+            decal.age = 0.0;
+            transform.translation = hit.position.extend(DECAL_LAYER);
+            transform.scale = Vec3::splat(radius);
+            *visibility = Visibility::Visible;
+        }
+    }
+}
+
+fn fade_impact_decals(
+    time: Res<Time>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut decals: Query<(&mut Decal, &MeshMaterial2d<ColorMaterial>, &mut Visibility)>,
+) {
+    for (mut decal, material, mut visibility) in &mut decals {
+        if *visibility == Visibility::Hidden {
+            continue;
+        }
+        decal.age += time.delta_secs();
+        if decal.age >= DECAL_SECONDS {
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+        if let Some(material) = materials.get_mut(&material.0) {
+            material.color = decal_color(DECAL_ALPHA * (1.0 - decal.age / DECAL_SECONDS));
+        }
+    }
+}
+
+pub struct DecalsPlugin;
+
+impl Plugin for DecalsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DecalPool>();
+        app.add_systems(Startup, setup_decal_pool);
+        app.add_systems(Update, (place_impact_decals, fade_impact_decals).chain());
+    }
+}
@@ -0,0 +1,62 @@
+use crate::combat::{despawn_dead_enemies, EnemyKilled};
+use crate::common::{Bounty, Gold, Mutators, Score, SimulationSet};
+use crate::enemy::Waves;
+use crate::tower::base_gold_trickle;
+use bevy::prelude::*;
+
+#[derive(Resource)]
+pub struct BountyConfig {
+    pub wave_scaling: f32,
+    pub split_falloff: f32,
+    pub max_split_generation: u32,
+}
+
+impl Default for BountyConfig {
+    fn default() -> Self {
+        Self {
+            wave_scaling: 0.05,
+            split_falloff: 0.5,
+            max_split_generation: 2,
+        }
+    }
+}
+
+impl BountyConfig {
+    pub fn bounty(&self, mutators: Mutators, bounty: Bounty, wave: u32, generation: u32) -> u32 {
+        if generation > self.max_split_generation {
+            return 0;
+        }
+        let scaled = mutators.bounty(bounty) as f32
+            * (1.0 + self.wave_scaling * wave.saturating_sub(1) as f32)
+            * self.split_falloff.powi(generation as i32);
+        scaled.round() as u32
+    }
+}
+
+fn pay_bounties(
+    mut enemy_killed: EventReader<EnemyKilled>,
+    config: Res<BountyConfig>,
+    mutators: Res<Mutators>,
+    waves: Res<Waves>,
+    mut gold: ResMut<Gold>,
+    mut score: ResMut<Score>,
+) {
+    for killed in enemy_killed.read() {
+        let bounty = config.bounty(*mutators, killed.bounty, waves.number, killed.generation);
+        gold.0 += bounty;
+        score.0 += bounty;
+    }
+}
+
+pub struct EconomyPlugin;
+impl Plugin for EconomyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BountyConfig>();
+        app.add_systems(
+            FixedUpdate,
+            (pay_bounties, base_gold_trickle)
+                .after(despawn_dead_enemies)
+                .in_set(SimulationSet::Step),
+        );
+    }
+}
@@ -0,0 +1,1714 @@
+use crate::accessibility::VisualAccessibility;
+use crate::combat::{despawn_dead_enemies, record_leak, resolve_base_hits};
+use crate::common::{
+    Base, Bounty, Dependents, Enemy, GameAssets, GameRng, Hp, Lives, MaxHp, Mutators, ProjectedHp,
+    Shield, SimulationSet, Tower, STARTING_LIVES,
+};
+use crate::level::Level;
+use crate::projectile::DamageType;
+use crate::run_end::LastStand;
+use crate::run_modes::{
+    custom_requested, daily_requested, demo_requested, endless_requested, weekly_requested,
+};
+use crate::status::StatusEffects;
+use crate::tower::{shield_cap, Cooldown, ShieldGenerator};
+use crate::ui::{spawn_toast, DespawnOnTweenComplete, HealthBar, ScaleLens, Tween, WaveBanner};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use rand::prelude::*;
+use std::collections::VecDeque;
+use std::ops::RangeInclusive;
+use std::time::Duration;
+
+#[derive(Event)]
+pub struct WaveStarted(pub u32);
+
+#[derive(Event)]
+pub struct WaveCleared(pub u32);
+
+const WARLORD_MAX_HIT_PERCENT: u32 = 5;
+const CARRIER_EMIT_SECONDS: f32 = 2.5;
+const DROPSHIP_EMIT_SECONDS: f32 = 4.0;
+const HEAL_AMOUNT: u32 = 20;
+const HEAL_RADIUS: f32 = 90.0;
+const HEAL_SECONDS: f32 = 1.0;
+const HEAL_PULSE_SECONDS: f32 = 0.4;
+const RAISE_RADIUS: f32 = 120.0;
+const RAISE_SECONDS: f32 = 1.5;
+const RAISE_COUNT: usize = 2;
+
+#[derive(Resource)]
+pub struct EnemySpawnTimer(Timer);
+
+#[derive(Component, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EnemyKind {
+    Grunt,
+    Flyer,
+    Boss,
+    Tank,
+    Runner,
+    Warlord,
+    Splitter,
+    Carrier,
+    Healer,
+    Phantom,
+    Dropship,
+    Necromancer,
+    Zombie,
+}
+
+impl EnemyKind {
+    pub fn material(self, assets: &GameAssets) -> Handle<ColorMaterial> {
+        match self {
+            EnemyKind::Grunt => assets.enemy_material.clone(),
+            EnemyKind::Flyer => assets.flyer_material.clone(),
+            EnemyKind::Boss => assets.boss_material.clone(),
+            EnemyKind::Tank => assets.tank_material.clone(),
+            EnemyKind::Runner => assets.runner_material.clone(),
+            EnemyKind::Warlord => assets.warlord_material.clone(),
+            EnemyKind::Splitter => assets.splitter_material.clone(),
+            EnemyKind::Carrier => assets.carrier_material.clone(),
+            EnemyKind::Healer => assets.healer_material.clone(),
+            EnemyKind::Phantom => assets.phantom_material.clone(),
+            EnemyKind::Dropship => assets.dropship_material.clone(),
+            EnemyKind::Necromancer => assets.necromancer_material.clone(),
+            EnemyKind::Zombie => assets.zombie_material.clone(),
+        }
+    }
+
+    pub fn max_hp(self) -> u32 {
+        match self {
+            EnemyKind::Grunt => 100,
+            EnemyKind::Flyer => 60,
+            EnemyKind::Boss => 1000,
+            EnemyKind::Tank => 600,
+            EnemyKind::Runner => 40,
+            EnemyKind::Warlord => 6000,
+            EnemyKind::Splitter => 150,
+            EnemyKind::Carrier => 400,
+            EnemyKind::Healer => 120,
+            EnemyKind::Phantom => 90,
+            EnemyKind::Dropship => 250,
+            EnemyKind::Necromancer => 150,
+            EnemyKind::Zombie => 50,
+        }
+    }
+
+    pub fn speed(self) -> f32 {
+        match self {
+            EnemyKind::Flyer => 160.0,
+            EnemyKind::Grunt | EnemyKind::Boss => 100.0,
+            EnemyKind::Tank => 50.0,
+            EnemyKind::Warlord => 60.0,
+            EnemyKind::Splitter => 80.0,
+            EnemyKind::Carrier => 70.0,
+            EnemyKind::Healer => 90.0,
+            EnemyKind::Phantom => 120.0,
+            EnemyKind::Dropship => 60.0,
+            EnemyKind::Necromancer => 80.0,
+            EnemyKind::Zombie => 70.0,
+            EnemyKind::Runner => 220.0,
+        }
+    }
+
+    pub fn scale(self) -> f32 {
+        match self {
+            EnemyKind::Tank => 2.0,
+            EnemyKind::Runner => 0.6,
+            EnemyKind::Warlord => 2.5,
+            EnemyKind::Splitter => 1.3,
+            EnemyKind::Carrier => 1.6,
+            EnemyKind::Healer => 1.1,
+            EnemyKind::Phantom => 0.9,
+            EnemyKind::Dropship => 1.5,
+            EnemyKind::Necromancer => 1.2,
+            EnemyKind::Zombie => 0.9,
+            EnemyKind::Grunt | EnemyKind::Flyer | EnemyKind::Boss => 1.0,
+        }
+    }
+
+    pub fn bounty(self) -> u32 {
+        match self {
+            EnemyKind::Grunt => 5,
+            EnemyKind::Flyer => 8,
+            EnemyKind::Boss => 100,
+            EnemyKind::Tank => 30,
+            EnemyKind::Runner => 4,
+            EnemyKind::Warlord => 400,
+            EnemyKind::Splitter => 10,
+            EnemyKind::Carrier => 25,
+            EnemyKind::Healer => 15,
+            EnemyKind::Phantom => 12,
+            EnemyKind::Dropship => 20,
+            EnemyKind::Necromancer => 18,
+            EnemyKind::Zombie => 1,
+        }
+    }
+
+    pub fn bark_pitch(self) -> f32 {
+        match self {
+            EnemyKind::Grunt => 330.0,
+            EnemyKind::Flyer => 660.0,
+            EnemyKind::Boss => 110.0,
+            EnemyKind::Tank => 82.0,
+            EnemyKind::Runner => 880.0,
+            EnemyKind::Warlord => 65.0,
+            EnemyKind::Splitter => 440.0,
+            EnemyKind::Carrier => 165.0,
+            EnemyKind::Healer => 550.0,
+            EnemyKind::Phantom => 990.0,
+            EnemyKind::Dropship => 220.0,
+            EnemyKind::Necromancer => 140.0,
+            EnemyKind::Zombie => 200.0,
+        }
+    }
+
+    pub fn splits_into(self) -> Option<(EnemyKind, RangeInclusive<u32>)> {
+        match self {
+            EnemyKind::Splitter => Some((EnemyKind::Runner, 2..=3)),
+            EnemyKind::Dropship => Some((EnemyKind::Grunt, 2..=3)),
+            _ => None,
+        }
+    }
+
+    pub fn emits(self) -> Option<(EnemyKind, f32)> {
+        match self {
+            EnemyKind::Carrier => Some((EnemyKind::Grunt, CARRIER_EMIT_SECONDS)),
+            EnemyKind::Dropship => Some((EnemyKind::Grunt, DROPSHIP_EMIT_SECONDS)),
+            _ => None,
+        }
+    }
+
+    pub fn heals(self) -> Option<u32> {
+        match self {
+            EnemyKind::Healer => Some(HEAL_AMOUNT),
+            _ => None,
+        }
+    }
+
+    pub fn raises(self) -> Option<EnemyKind> {
+        match self {
+            EnemyKind::Necromancer => Some(EnemyKind::Zombie),
+            _ => None,
+        }
+    }
+
+    pub fn raisable(self) -> bool {
+        !self.is_boss() && self != EnemyKind::Zombie
+    }
+
+    pub fn is_cloaked(self) -> bool {
+        self == EnemyKind::Phantom
+    }
+
+    pub fn is_boss(self) -> bool {
+        matches!(self, EnemyKind::Boss | EnemyKind::Warlord)
+    }
+
+    pub fn max_hit(self) -> u32 {
+        match self {
+            EnemyKind::Warlord => self.max_hp() * WARLORD_MAX_HIT_PERCENT / 100,
+            _ => u32::MAX,
+        }
+    }
+
+    pub fn lane(self) -> Lane {
+        if matches!(self, EnemyKind::Flyer | EnemyKind::Dropship) {
+            Lane::Air
+        } else {
+            Lane::Ground
+        }
+    }
+
+    pub fn armor(self) -> u32 {
+        match self {
+            EnemyKind::Grunt
+            | EnemyKind::Flyer
+            | EnemyKind::Runner
+            | EnemyKind::Splitter
+            | EnemyKind::Healer
+            | EnemyKind::Phantom
+            | EnemyKind::Dropship
+            | EnemyKind::Necromancer
+            | EnemyKind::Zombie => 0,
+            EnemyKind::Boss | EnemyKind::Warlord => 30,
+            EnemyKind::Tank => 20,
+            EnemyKind::Carrier => 5,
+        }
+    }
+
+    pub fn resistances(self) -> [u32; 3] {
+        match self {
+            EnemyKind::Grunt | EnemyKind::Splitter | EnemyKind::Zombie => [0, 0, 0],
+            EnemyKind::Tank => [30, 0, 0],
+            EnemyKind::Carrier => [20, 0, 0],
+            EnemyKind::Flyer => [0, 50, 0],
+            EnemyKind::Runner => [0, 25, 0],
+            EnemyKind::Boss | EnemyKind::Warlord => [0, 0, 25],
+            EnemyKind::Healer => [0, 0, 30],
+            EnemyKind::Phantom => [0, 0, 40],
+            EnemyKind::Dropship => [0, 30, 0],
+            EnemyKind::Necromancer => [0, 0, 20],
+        }
+    }
+
+    pub fn lives_cost(self) -> u32 {
+        match self {
+            EnemyKind::Grunt
+            | EnemyKind::Flyer
+            | EnemyKind::Runner
+            | EnemyKind::Healer
+            | EnemyKind::Zombie => 1,
+            EnemyKind::Splitter | EnemyKind::Phantom | EnemyKind::Necromancer => 2,
+            EnemyKind::Boss => 5,
+            EnemyKind::Tank => 3,
+            EnemyKind::Carrier | EnemyKind::Dropship => 3,
+            EnemyKind::Warlord => 10,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            EnemyKind::Grunt => "grunt",
+            EnemyKind::Flyer => "flyer",
+            EnemyKind::Boss => "boss",
+            EnemyKind::Tank => "tank",
+            EnemyKind::Runner => "runner",
+            EnemyKind::Warlord => "warlord",
+            EnemyKind::Splitter => "splitter",
+            EnemyKind::Carrier => "carrier",
+            EnemyKind::Healer => "healer",
+            EnemyKind::Phantom => "phantom",
+            EnemyKind::Dropship => "dropship",
+            EnemyKind::Necromancer => "necromancer",
+            EnemyKind::Zombie => "zombie",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "grunt" => Some(EnemyKind::Grunt),
+            "flyer" => Some(EnemyKind::Flyer),
+            "boss" => Some(EnemyKind::Boss),
+            "tank" => Some(EnemyKind::Tank),
+            "runner" => Some(EnemyKind::Runner),
+            "warlord" => Some(EnemyKind::Warlord),
+            "splitter" => Some(EnemyKind::Splitter),
+            "carrier" => Some(EnemyKind::Carrier),
+            "healer" => Some(EnemyKind::Healer),
+            "phantom" => Some(EnemyKind::Phantom),
+            "dropship" => Some(EnemyKind::Dropship),
+            "necromancer" => Some(EnemyKind::Necromancer),
+            "zombie" => Some(EnemyKind::Zombie),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Velocity(pub f32);
+
+#[derive(Component)]
+pub struct Generation(pub u32);
+
+#[derive(Component)]
+struct Carrier(Timer);
+
+#[derive(Component)]
+struct Healer(Timer);
+
+#[derive(Component)]
+struct Necromancer(Timer);
+
+#[derive(Component)]
+pub struct Raisable;
+
+#[derive(Component)]
+pub struct Invisible;
+
+#[derive(Component)]
+pub struct Revealed;
+
+pub type Targetable = Or<(Without<Invisible>, With<Revealed>)>;
+
+#[derive(Component)]
+pub struct Direction(pub Vec3);
+
+#[derive(Component)]
+pub struct Armor(pub u32);
+
+#[derive(Component)]
+pub struct Resistances(pub [u32; 3]);
+
+impl Resistances {
+    pub fn percent(&self, damage_type: DamageType) -> u32 {
+        self.0[damage_type as usize]
+    }
+}
+
+#[derive(Component)]
+pub struct Corpse;
+
+pub const ENEMY_SIZE: f32 = 10.0;
+
+pub const CORPSE_LAYER: f32 = -1.0;
+pub const CORPSE_LIFETIME: f32 = 2.0;
+
+pub fn spawn_enemy_at(
+    commands: &mut Commands,
+    assets: &GameAssets,
+    kind: EnemyKind,
+    translation: Vec3,
+    direction: Vec3,
+    hp: u32,
+) -> Entity {
+    let enemy = commands
+        .spawn((
+            Mesh2d(assets.enemy_mesh.clone()),
+            MeshMaterial2d(kind.material(assets)),
+            Transform::from_translation(translation).with_scale(Vec3::splat(kind.scale())),
+            Enemy,
+            kind,
+            Velocity(kind.speed()),
+            Direction(direction),
+            Hp(hp),
+            ProjectedHp(hp),
+            MaxHp(kind.max_hp()),
+            (
+                Armor(kind.armor()),
+                Resistances(kind.resistances()),
+                kind.lane(),
+            ),
+            Bounty(kind.bounty()),
+            StatusEffects::default(),
+            PathProgress::new(translation),
+        ))
+        .id();
+    if let Some((_, seconds)) = kind.emits() {
+        commands
+            .entity(enemy)
+            .insert(Carrier(Timer::from_seconds(seconds, TimerMode::Repeating)));
+    }
+    if kind.heals().is_some() {
+        commands.entity(enemy).insert(Healer(Timer::from_seconds(
+            HEAL_SECONDS,
+            TimerMode::Repeating,
+        )));
+    }
+    if kind.raises().is_some() {
+        commands
+            .entity(enemy)
+            .insert(Necromancer(Timer::from_seconds(
+                RAISE_SECONDS,
+                TimerMode::Repeating,
+            )));
+    }
+    if kind.is_cloaked() {
+        commands.entity(enemy).insert(Invisible);
+    }
+
+    let health_bar = commands
+        .spawn((
+            Mesh2d(assets.health_bar_mesh.clone()),
+            MeshMaterial2d(assets.health_bar_material.clone()),
+            Transform::from_translation(translation),
+            HealthBar(enemy),
+        ))
+        .id();
+
+    let mut dependents = vec![health_bar];
+    if kind.is_boss() {
+        let telegraph = commands
+            .spawn((
+                Mesh2d(assets.telegraph_mesh.clone()),
+                MeshMaterial2d(assets.telegraph_material.clone()),
+                Transform::from_translation(translation),
+                Visibility::Hidden,
+                Telegraph(enemy),
+            ))
+            .id();
+        dependents.push(telegraph);
+        commands
+            .entity(enemy)
+            .insert(Abilities::new(&BOSS_ABILITIES));
+    }
+
+    commands.entity(enemy).insert(Dependents(dependents));
+    enemy
+}
+
+const WAVE_BASE_SIZE: u32 = 5;
+const WAVE_GROWTH: u32 = 2;
+
+pub struct WaveContext {
+    number: u32,
+    lives: u32,
+    mutators: Mutators,
+}
+
+pub trait WaveGenerator: Send + Sync {
+    fn generate(&mut self, context: &WaveContext, rng: &mut StdRng) -> Vec<EnemyKind>;
+
+    fn rerollable(&self, _number: u32) -> bool {
+        false
+    }
+}
+
+const TANK_MIN_WAVE: u32 = 12;
+const ENDLESS_TANK_INTERVAL: u32 = 7;
+const RUNNER_MIN_WAVE: u32 = 6;
+const RUNNER_BURST: u32 = 5;
+const RUNNER_BURST_GAP: f32 = 0.15;
+const ENDLESS_RUNNER_INTERVAL: u32 = 20;
+const SPLITTER_MIN_WAVE: u32 = 8;
+const ENDLESS_SPLITTER_INTERVAL: u32 = 11;
+const CARRIER_MIN_WAVE: u32 = 10;
+const ENDLESS_CARRIER_INTERVAL: u32 = 13;
+const HEALER_MIN_WAVE: u32 = 7;
+const ENDLESS_HEALER_INTERVAL: u32 = 9;
+const PHANTOM_MIN_WAVE: u32 = 9;
+const ENDLESS_PHANTOM_INTERVAL: u32 = 17;
+const DROPSHIP_MIN_WAVE: u32 = 11;
+const ENDLESS_DROPSHIP_INTERVAL: u32 = 23;
+const NECROMANCER_MIN_WAVE: u32 = 10;
+const ENDLESS_NECROMANCER_INTERVAL: u32 = 19;
+
+#[derive(Default)]
+struct EndlessWaves {
+    spawned: u32,
+}
+
+impl WaveGenerator for EndlessWaves {
+    fn generate(&mut self, context: &WaveContext, _rng: &mut StdRng) -> Vec<EnemyKind> {
+        (0..WAVE_BASE_SIZE + context.number * WAVE_GROWTH)
+            .map(|_| {
+                self.spawned += 1;
+                if self
+                    .spawned
+                    .is_multiple_of(context.mutators.boss_interval())
+                {
+                    EnemyKind::Boss
+                } else if context.number >= TANK_MIN_WAVE
+                    && self.spawned.is_multiple_of(ENDLESS_TANK_INTERVAL)
+                {
+                    EnemyKind::Tank
+                } else if context.number >= SPLITTER_MIN_WAVE
+                    && self.spawned.is_multiple_of(ENDLESS_SPLITTER_INTERVAL)
+                {
+                    EnemyKind::Splitter
+                } else if context.number >= CARRIER_MIN_WAVE
+                    && self.spawned.is_multiple_of(ENDLESS_CARRIER_INTERVAL)
+                {
+                    EnemyKind::Carrier
+                } else if context.number >= HEALER_MIN_WAVE
+                    && self.spawned.is_multiple_of(ENDLESS_HEALER_INTERVAL)
+                {
+                    EnemyKind::Healer
+                } else if context.number >= PHANTOM_MIN_WAVE
+                    && self.spawned.is_multiple_of(ENDLESS_PHANTOM_INTERVAL)
+                {
+                    EnemyKind::Phantom
+                } else if context.number >= DROPSHIP_MIN_WAVE
+                    && self.spawned.is_multiple_of(ENDLESS_DROPSHIP_INTERVAL)
+                {
+                    EnemyKind::Dropship
+                } else if context.number >= NECROMANCER_MIN_WAVE
+                    && self.spawned.is_multiple_of(ENDLESS_NECROMANCER_INTERVAL)
+                {
+                    EnemyKind::Necromancer
+                } else if context.number >= RUNNER_MIN_WAVE
+                    && self.spawned % ENDLESS_RUNNER_INTERVAL < RUNNER_BURST
+                {
+                    EnemyKind::Runner
+                } else {
+                    EnemyKind::Grunt
+                }
+            })
+            .collect()
+    }
+}
+
+struct WaveEntry {
+    kind: EnemyKind,
+    cost: u32,
+    weight: u32,
+    min_wave: u32,
+    max_per_wave: u32,
+    burst: u32,
+}
+
+const BUDGET_TABLE: [WaveEntry; 11] = [
+    WaveEntry {
+        kind: EnemyKind::Grunt,
+        cost: 1,
+        weight: 6,
+        min_wave: 1,
+        max_per_wave: u32::MAX,
+        burst: 1,
+    },
+    WaveEntry {
+        kind: EnemyKind::Flyer,
+        cost: 2,
+        weight: 3,
+        min_wave: 9,
+        max_per_wave: u32::MAX,
+        burst: 1,
+    },
+    WaveEntry {
+        kind: EnemyKind::Boss,
+        cost: 10,
+        weight: 1,
+        min_wave: 5,
+        max_per_wave: 1,
+        burst: 1,
+    },
+    WaveEntry {
+        kind: EnemyKind::Tank,
+        cost: 6,
+        weight: 2,
+        min_wave: TANK_MIN_WAVE,
+        max_per_wave: 3,
+        burst: 1,
+    },
+    WaveEntry {
+        kind: EnemyKind::Runner,
+        cost: 3,
+        weight: 2,
+        min_wave: RUNNER_MIN_WAVE,
+        max_per_wave: 2,
+        burst: RUNNER_BURST,
+    },
+    WaveEntry {
+        kind: EnemyKind::Splitter,
+        cost: 3,
+        weight: 2,
+        min_wave: SPLITTER_MIN_WAVE,
+        max_per_wave: 4,
+        burst: 1,
+    },
+    WaveEntry {
+        kind: EnemyKind::Carrier,
+        cost: 5,
+        weight: 1,
+        min_wave: CARRIER_MIN_WAVE,
+        max_per_wave: 2,
+        burst: 1,
+    },
+    WaveEntry {
+        kind: EnemyKind::Healer,
+        cost: 3,
+        weight: 2,
+        min_wave: HEALER_MIN_WAVE,
+        max_per_wave: 3,
+        burst: 1,
+    },
+    WaveEntry {
+        kind: EnemyKind::Phantom,
+        cost: 3,
+        weight: 2,
+        min_wave: PHANTOM_MIN_WAVE,
+        max_per_wave: 4,
+        burst: 1,
+    },
+    WaveEntry {
+        kind: EnemyKind::Dropship,
+        cost: 5,
+        weight: 1,
+        min_wave: DROPSHIP_MIN_WAVE,
+        max_per_wave: 2,
+        burst: 1,
+    },
+    WaveEntry {
+        kind: EnemyKind::Necromancer,
+        cost: 4,
+        weight: 1,
+        min_wave: NECROMANCER_MIN_WAVE,
+        max_per_wave: 2,
+        burst: 1,
+    },
+];
+
+const WAVE_BASE_BUDGET: u32 = 6;
+const WAVE_BUDGET_GROWTH: u32 = 3;
+
+struct BudgetWaves;
+
+impl WaveGenerator for BudgetWaves {
+    fn generate(&mut self, context: &WaveContext, rng: &mut StdRng) -> Vec<EnemyKind> {
+        let mut budget = WAVE_BASE_BUDGET + context.number * WAVE_BUDGET_GROWTH;
+        let mut counts = [0; BUDGET_TABLE.len()];
+        let mut groups = Vec::new();
+        loop {
+            let affordable = BUDGET_TABLE.iter().enumerate().filter(|&(index, entry)| {
+                entry.min_wave <= context.number
+                    && entry.cost <= budget
+                    && counts[index] < entry.max_per_wave
+            });
+            let Ok((index, entry)) = affordable
+                .collect::<Vec<_>>()
+                .choose_weighted(rng, |&(_, entry)| entry.weight)
+                .copied()
+            else {
+                break;
+            };
+            counts[index] += 1;
+            budget -= entry.cost;
+            groups.push(vec![entry.kind; entry.burst as usize]);
+        }
+        groups.shuffle(rng);
+        groups.concat()
+    }
+
+    fn rerollable(&self, _number: u32) -> bool {
+        true
+    }
+}
+
+struct ScriptedWaves {
+    waves: Vec<Vec<EnemyKind>>,
+    then: Box<dyn WaveGenerator>,
+}
+
+impl WaveGenerator for ScriptedWaves {
+    fn generate(&mut self, context: &WaveContext, rng: &mut StdRng) -> Vec<EnemyKind> {
+        match self.waves.get(context.number as usize - 1) {
+            Some(wave) => wave.clone(),
+            None => self.then.generate(context, rng),
+        }
+    }
+
+    fn rerollable(&self, number: u32) -> bool {
+        number as usize > self.waves.len() && self.then.rerollable(number)
+    }
+}
+
+struct AdaptiveWaves<G>(G);
+
+impl<G: WaveGenerator> WaveGenerator for AdaptiveWaves<G> {
+    fn generate(&mut self, context: &WaveContext, rng: &mut StdRng) -> Vec<EnemyKind> {
+        let mut wave = self.0.generate(context, rng);
+        let health = context.lives.min(STARTING_LIVES) as f32 / STARTING_LIVES as f32;
+        let size = (wave.len() as f32 * (0.75 + 0.5 * health)).round() as usize;
+        wave.resize(size.max(1), EnemyKind::Grunt);
+        wave
+    }
+
+    fn rerollable(&self, number: u32) -> bool {
+        self.0.rerollable(number)
+    }
+}
+
+pub fn describe_wave(composition: &[EnemyKind]) -> String {
+    let mut counts: Vec<(EnemyKind, usize)> = Vec::new();
+    for &kind in composition {
+        match counts.iter_mut().find(|(counted, _)| *counted == kind) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((kind, 1)),
+        }
+    }
+    counts
+        .iter()
+        .map(|(kind, count)| format!("{count} {}", kind.name()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[derive(Resource)]
+pub struct Waves {
+    pub generator: Box<dyn WaveGenerator>,
+    pub number: u32,
+    pub pending: VecDeque<EnemyKind>,
+    pub upcoming: Option<Vec<EnemyKind>>,
+    pub break_timer: Timer,
+    pub history: Vec<(u32, Vec<EnemyKind>)>,
+}
+
+impl Waves {
+    pub fn new(generator: impl WaveGenerator + 'static) -> Self {
+        Waves {
+            generator: Box::new(generator),
+            number: 0,
+            pending: VecDeque::new(),
+            upcoming: None,
+            break_timer: Timer::from_seconds(WAVE_BREAK_SECONDS, TimerMode::Once),
+            history: Vec::new(),
+        }
+    }
+
+    pub fn for_run() -> Self {
+        if demo_requested() {
+            Waves::new(BudgetWaves)
+        } else if daily_requested()
+            || weekly_requested()
+            || custom_requested()
+            || endless_requested()
+        {
+            Waves::new(EndlessWaves::default())
+        } else {
+            Waves::campaign()
+        }
+    }
+
+    pub fn campaign() -> Self {
+        Waves::new(ScriptedWaves {
+            waves: vec![vec![EnemyKind::Grunt; 3], vec![EnemyKind::Grunt; 5]],
+            then: Box::new(AdaptiveWaves(BudgetWaves)),
+        })
+    }
+
+    pub fn seconds_until_next(&self, spawn_timer: &EnemySpawnTimer) -> f32 {
+        let spawning = self.pending.len() as f32 * spawn_timer.0.duration().as_secs_f32();
+        let break_left = if self.in_break() {
+            self.break_timer.remaining_secs()
+        } else {
+            self.break_timer.duration().as_secs_f32()
+        };
+        spawning + break_left
+    }
+
+    pub fn in_break(&self) -> bool {
+        self.pending.is_empty()
+            && self.break_timer.elapsed_secs() > 0.0
+            && !self.break_timer.finished()
+    }
+
+    fn roll(&mut self, lives: u32, mutators: Mutators, rng: &mut StdRng) -> Vec<EnemyKind> {
+        let context = WaveContext {
+            number: self.number + 1,
+            lives,
+            mutators,
+        };
+        let mut wave = self.generator.generate(&context, rng);
+        if context.number.is_multiple_of(MILESTONE_WAVE_INTERVAL) {
+            wave.push(EnemyKind::Warlord);
+        }
+        wave
+    }
+
+    fn prepare_next(&mut self, lives: u32, mutators: Mutators, rng: &mut StdRng) {
+        if self.upcoming.is_none() {
+            self.upcoming = Some(self.roll(lives, mutators, rng));
+        }
+    }
+
+    pub fn can_reroll(&self) -> bool {
+        self.upcoming.is_some() && self.generator.rerollable(self.number + 1)
+    }
+
+    pub fn reroll_next(&mut self, lives: u32, mutators: Mutators, rng: &mut StdRng) {
+        let mut reseeded = StdRng::seed_from_u64(rng.gen());
+        self.upcoming = Some(self.roll(lives, mutators, &mut reseeded));
+    }
+
+    fn start_next(&mut self, lives: u32, mutators: Mutators, rng: &mut StdRng) {
+        self.break_timer.reset();
+        self.pending = match self.upcoming.take() {
+            Some(wave) => wave.into(),
+            None => self.roll(lives, mutators, rng).into(),
+        };
+        self.number += 1;
+        let number = self.number;
+        self.history.retain(|&(wave, _)| wave < number);
+        self.history
+            .push((number, self.pending.iter().copied().collect()));
+    }
+}
+
+pub const WAVE_BREAK_SECONDS: f32 = 3.0;
+const MILESTONE_WAVE_INTERVAL: u32 = 10;
+pub const ENEMY_SPAWN_INTERVAL: f32 = 0.5;
+
+pub fn configure_waves(
+    level: Option<Res<Level>>,
+    mut spawn_timer: ResMut<EnemySpawnTimer>,
+    mut waves: ResMut<Waves>,
+    mut path: ResMut<Path>,
+    mut air_path: ResMut<AirPath>,
+) {
+    let Some(level) = level else {
+        return;
+    };
+    path.0.clone_from(&level.waypoints);
+    air_path.0.clone_from(&level.air_waypoints);
+    spawn_timer
+        .0
+        .set_duration(Duration::from_secs_f32(level.spawn_interval));
+    waves
+        .break_timer
+        .set_duration(Duration::from_secs_f32(level.wave_break));
+}
+
+#[derive(Resource, Default)]
+pub struct NextSpawn(pub Option<Vec3>);
+
+fn roll_spawn_point(rng: &mut StdRng, window: &Window, path: &Path) -> Vec3 {
+    if let Some(entry) = path.0.first() {
+        return entry.extend(0.0);
+    }
+    let dir = Vec2::new(rng.gen_range(-100.0..100.0), rng.gen_range(-100.0..100.0)).normalize();
+    let enemy_center = Vec2::new(window.width() / 2., window.height() / 2.)
+        + Vec2::new(window.width(), window.height()) * dir;
+    enemy_center.extend(0.0)
+}
+
+#[derive(SystemParam)]
+struct SpawnPoints<'w> {
+    next: ResMut<'w, NextSpawn>,
+    path: Res<'w, Path>,
+    window: Single<'w, &'static Window>,
+}
+
+impl SpawnPoints<'_> {
+    fn take(&mut self, rng: &mut StdRng) -> Vec3 {
+        let spawn = self
+            .next
+            .0
+            .take()
+            .unwrap_or_else(|| roll_spawn_point(rng, &self.window, &self.path));
+        self.next.0 = Some(roll_spawn_point(rng, &self.window, &self.path));
+        spawn
+    }
+}
+
+fn spawn_enemy(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut spawn_timer: ResMut<EnemySpawnTimer>,
+    mut waves: ResMut<Waves>,
+    mut wave_started: EventWriter<WaveStarted>,
+    mut wave_cleared: EventWriter<WaveCleared>,
+    assets: Res<GameAssets>,
+    mutators: Res<Mutators>,
+    lives: Res<Lives>,
+    mut rng: ResMut<GameRng>,
+    mut spawn_points: SpawnPoints,
+    last_stand: Res<LastStand>,
+    mut shield: ResMut<Shield>,
+    generators: Query<(), With<ShieldGenerator>>,
+    enemies: Query<(), With<Enemy>>,
+    query: Query<&Transform, With<Base>>,
+) {
+    if last_stand.timer.is_some() {
+        return;
+    }
+    let rng = &mut rng.0;
+    if waves.number > 0 && waves.pending.is_empty() && enemies.is_empty() {
+        if waves.break_timer.elapsed_secs() == 0.0 {
+            wave_cleared.send(WaveCleared(waves.number));
+        }
+        waves.prepare_next(lives.0, *mutators, rng);
+        waves.break_timer.tick(time.delta());
+    }
+    if spawn_timer.0.tick(time.delta()).just_finished() {
+        if waves.pending.is_empty() {
+            if !enemies.is_empty() || (waves.number > 0 && !waves.break_timer.finished()) {
+                return;
+            }
+            waves.start_next(lives.0, *mutators, rng);
+            shield.0 = shield_cap(generators.iter().count());
+            let banner = spawn_toast(&mut commands, &format!("Wave {}", waves.number));
+            commands.entity(banner).insert(WaveBanner);
+            wave_started.send(WaveStarted(waves.number));
+        }
+        let Some(kind) = waves.pending.pop_front() else {
+            return;
+        };
+        for base_transform in &query {
+            let enemy_translation = spawn_points.take(rng);
+
+            spawn_enemy_at(
+                &mut commands,
+                &assets,
+                kind,
+                enemy_translation,
+                base_transform.translation - enemy_translation,
+                kind.max_hp(),
+            );
+        }
+        if kind == EnemyKind::Runner && waves.pending.front() == Some(&EnemyKind::Runner) {
+            let duration = spawn_timer.0.duration();
+            let gap = Duration::from_secs_f32(RUNNER_BURST_GAP).min(duration);
+            spawn_timer.0.set_elapsed(duration - gap);
+        }
+    }
+}
+
+const SPLIT_SPREAD: f32 = 8.0;
+
+fn split_dead_enemies(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    mut rng: ResMut<GameRng>,
+    path: Res<Path>,
+    base: Query<&Transform, With<Base>>,
+    query: Query<
+        (
+            &Transform,
+            &Hp,
+            &EnemyKind,
+            &Direction,
+            &PathProgress,
+            Option<&Generation>,
+        ),
+        With<Enemy>,
+    >,
+) {
+    for (transform, hp, kind, direction, progress, generation) in &query {
+        let Some((child, counts)) = kind.splits_into() else {
+            continue;
+        };
+        if hp.0 > 0 {
+            continue;
+        }
+        let count = rng.0.gen_range(counts);
+        let (translation, heading, progress) =
+            child_placement(*kind, child, transform, direction, progress, &path, &base);
+        let side = heading.truncate().normalize_or_zero().perp().extend(0.0);
+        for index in 0..count {
+            let offset = side * SPLIT_SPREAD * (index as f32 - (count - 1) as f32 / 2.0);
+            spawn_child(
+                &mut commands,
+                &assets,
+                child,
+                translation + offset,
+                heading,
+                progress,
+                generation,
+            );
+        }
+    }
+}
+
+fn emit_carrier_minions(
+    mut commands: Commands,
+    time: Res<Time>,
+    assets: Res<GameAssets>,
+    path: Res<Path>,
+    base: Query<&Transform, With<Base>>,
+    mut carriers: Query<
+        (
+            &mut Carrier,
+            &Transform,
+            &Hp,
+            &EnemyKind,
+            &Direction,
+            &PathProgress,
+            Option<&Generation>,
+        ),
+        With<Enemy>,
+    >,
+) {
+    for (mut carrier, transform, hp, kind, direction, progress, generation) in &mut carriers {
+        let Some((minion, _)) = kind.emits() else {
+            continue;
+        };
+        if hp.0 == 0 || !carrier.0.tick(time.delta()).just_finished() {
+            continue;
+        }
+        let (translation, heading, progress) =
+            child_placement(*kind, minion, transform, direction, progress, &path, &base);
+        spawn_child(
+            &mut commands,
+            &assets,
+            minion,
+            translation,
+            heading,
+            progress,
+            generation,
+        );
+    }
+}
+
+fn heal_nearby_enemies(
+    mut commands: Commands,
+    time: Res<Time>,
+    assets: Res<GameAssets>,
+    visuals: Res<VisualAccessibility>,
+    mut healers: Query<(Entity, &mut Healer, &Transform, &EnemyKind)>,
+    mut enemies: Query<(Entity, &Transform, &mut Hp, &mut ProjectedHp, &MaxHp), With<Enemy>>,
+) {
+    let mut pulses = Vec::new();
+    for (healer, mut timer, transform, kind) in &mut healers {
+        let Some(amount) = kind.heals() else {
+            continue;
+        };
+        let alive = enemies.get(healer).is_ok_and(|(_, _, hp, _, _)| hp.0 > 0);
+        if alive && timer.0.tick(time.delta()).just_finished() {
+            pulses.push((healer, transform.translation, amount));
+        }
+    }
+
+    for &(healer, center, amount) in &pulses {
+        for (enemy, transform, mut hp, mut projected, max_hp) in &mut enemies {
+            if enemy == healer
+                || hp.0 == 0
+                || transform.translation.truncate().distance(center.truncate()) > HEAL_RADIUS
+            {
+                continue;
+            }
+            let healed = amount.min(max_hp.0.saturating_sub(hp.0));
+            hp.0 += healed;
+            projected.0 += healed;
+        }
+        let radius = Vec3::splat(HEAL_RADIUS);
+        commands.spawn((
+            Mesh2d(assets.heal_pulse_mesh.clone()),
+            MeshMaterial2d(assets.heal_pulse_material.clone()),
+            Transform::from_translation(center.with_z(CORPSE_LAYER)),
+            DespawnOnTweenComplete,
+            Tween::new(
+                ScaleLens {
+                    start: if visuals.reduced_motion {
+                        radius
+                    } else {
+                        radius * 0.2
+                    },
+                    end: radius,
+                },
+                HEAL_PULSE_SECONDS,
+                EaseFunction::QuadraticOut,
+            ),
+        ));
+    }
+}
+
+fn raise_corpses(
+    mut commands: Commands,
+    time: Res<Time>,
+    assets: Res<GameAssets>,
+    path: Res<Path>,
+    base: Query<&Transform, With<Base>>,
+    mut necromancers: Query<(&mut Necromancer, &Transform, &Hp, &EnemyKind), With<Enemy>>,
+    corpses: Query<(Entity, &Transform), (With<Corpse>, With<Raisable>)>,
+) {
+    let base = base
+        .iter()
+        .next()
+        .map_or(Vec2::ZERO, |transform| transform.translation.truncate());
+    let mut raised = Vec::new();
+    for (mut necromancer, transform, hp, kind) in &mut necromancers {
+        let Some(minion) = kind.raises() else {
+            continue;
+        };
+        if hp.0 == 0 || !necromancer.0.tick(time.delta()).just_finished() {
+            continue;
+        }
+        let center = transform.translation.truncate();
+        let nearby: Vec<(Entity, Vec3)> = corpses
+            .iter()
+            .filter(|(corpse, corpse_transform)| {
+                !raised.contains(corpse)
+                    && corpse_transform.translation.truncate().distance(center) <= RAISE_RADIUS
+            })
+            .map(|(corpse, corpse_transform)| (corpse, corpse_transform.translation))
+            .take(RAISE_COUNT)
+            .collect();
+        for (corpse, position) in nearby {
+            raised.push(corpse);
+            commands.entity(corpse).despawn();
+            let (translation, heading, progress) =
+                drop_onto_path(&path, base, position.with_z(0.0));
+            let zombie = spawn_enemy_at(
+                &mut commands,
+                &assets,
+                minion,
+                translation,
+                heading,
+                minion.max_hp(),
+            );
+            commands.entity(zombie).insert(progress);
+        }
+    }
+}
+
+fn child_placement(
+    parent: EnemyKind,
+    child: EnemyKind,
+    transform: &Transform,
+    direction: &Direction,
+    progress: &PathProgress,
+    path: &Path,
+    base: &Query<&Transform, With<Base>>,
+) -> (Vec3, Vec3, PathProgress) {
+    if parent.lane() == child.lane() {
+        return (transform.translation, direction.0, *progress);
+    }
+    let base = base
+        .iter()
+        .next()
+        .map_or(Vec2::ZERO, |transform| transform.translation.truncate());
+    drop_onto_path(path, base, transform.translation)
+}
+
+fn drop_onto_path(path: &Path, base: Vec2, translation: Vec3) -> (Vec3, Vec3, PathProgress) {
+    let position = translation.truncate();
+    let (point, waypoint) = (1..=path.0.len())
+        .map(|waypoint| {
+            let (start, end) = lane_segment(&path.0, translation, base, waypoint);
+            let point = start.lerp(end, segment_fraction(start, end, position));
+            (point, waypoint)
+        })
+        .min_by(|(a, _), (b, _)| {
+            a.distance_squared(position)
+                .total_cmp(&b.distance_squared(position))
+        })
+        .unwrap_or((position, 0));
+    let origin = point.extend(translation.z);
+    let (_, end) = lane_segment(&path.0, origin, base, waypoint);
+    let heading = if end != point {
+        end - point
+    } else {
+        base - point
+    };
+    let progress = PathProgress {
+        origin,
+        waypoint,
+        fraction: 0.0,
+        segments: path.0.len() + 1,
+    };
+    (origin, heading.extend(0.0), progress)
+}
+
+fn spawn_child(
+    commands: &mut Commands,
+    assets: &GameAssets,
+    kind: EnemyKind,
+    translation: Vec3,
+    direction: Vec3,
+    progress: PathProgress,
+    generation: Option<&Generation>,
+) {
+    let child = spawn_enemy_at(
+        commands,
+        assets,
+        kind,
+        translation,
+        direction,
+        kind.max_hp(),
+    );
+    commands.entity(child).insert((
+        progress,
+        Generation(generation.map_or(0, |generation| generation.0) + 1),
+    ));
+}
+
+#[derive(Resource, Default)]
+pub struct Path(pub Vec<Vec2>);
+
+#[derive(Resource, Default)]
+pub struct AirPath(pub Vec<Vec2>);
+
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub enum Lane {
+    Ground,
+    Air,
+}
+
+impl Lane {
+    pub fn name(self) -> &'static str {
+        match self {
+            Lane::Ground => "ground",
+            Lane::Air => "air",
+        }
+    }
+}
+
+fn lane_segment(waypoints: &[Vec2], origin: Vec3, base: Vec2, waypoint: usize) -> (Vec2, Vec2) {
+    let start = match waypoint {
+        0 => origin.truncate(),
+        _ => waypoints[waypoint - 1],
+    };
+    (start, waypoints.get(waypoint).copied().unwrap_or(base))
+}
+
+#[derive(Component, Clone, Copy)]
+pub struct PathProgress {
+    pub origin: Vec3,
+    pub waypoint: usize,
+    pub fraction: f32,
+    pub segments: usize,
+}
+
+impl PathProgress {
+    pub fn new(origin: Vec3) -> Self {
+        PathProgress {
+            origin,
+            waypoint: 0,
+            fraction: 0.0,
+            segments: 1,
+        }
+    }
+
+    pub fn value(&self) -> f32 {
+        (self.waypoint as f32 + self.fraction) / self.segments as f32
+    }
+}
+
+fn segment_fraction(start: Vec2, end: Vec2, position: Vec2) -> f32 {
+    let segment = end - start;
+    if segment.length_squared() == 0.0 {
+        return 1.0;
+    }
+    ((position - start).dot(segment) / segment.length_squared()).clamp(0.0, 1.0)
+}
+
+pub fn follow_path(
+    path: Res<Path>,
+    air_path: Res<AirPath>,
+    base: Query<&Transform, With<Base>>,
+    mut enemies: Query<(&Transform, &mut Direction, &mut PathProgress, &Lane), With<Enemy>>,
+) {
+    let Some(base) = base
+        .iter()
+        .next()
+        .map(|transform| transform.translation.truncate())
+    else {
+        return;
+    };
+    for (transform, mut direction, mut progress, lane) in &mut enemies {
+        let position = transform.translation.truncate();
+        let waypoints = match lane {
+            Lane::Ground => &path.0,
+            Lane::Air => &air_path.0,
+        };
+        progress.segments = waypoints.len() + 1;
+        progress.waypoint = progress.waypoint.min(waypoints.len());
+        loop {
+            let (start, end) = lane_segment(waypoints, progress.origin, base, progress.waypoint);
+            progress.fraction = segment_fraction(start, end, position);
+            if progress.fraction < 1.0 || progress.waypoint == waypoints.len() {
+                break;
+            }
+            progress.waypoint += 1;
+        }
+        let (_, end) = lane_segment(waypoints, progress.origin, base, progress.waypoint);
+        if end != position {
+            direction.0 = (end - position).extend(0.0);
+        }
+    }
+}
+
+pub fn update_enemy_position(
+    time: Res<Time>,
+    mutators: Res<Mutators>,
+    enrage: Res<Enrage>,
+    mut query: Query<
+        (
+            &mut Transform,
+            &Direction,
+            &Velocity,
+            Option<&StatusEffects>,
+        ),
+        With<Enemy>,
+    >,
+) {
+    let speed = mutators.enemy_speed() * enrage.speed_multiplier();
+    for (mut transform, direction, velocity, effects) in &mut query {
+        let speed = speed * effects.map_or(1.0, StatusEffects::speed_multiplier);
+        transform.translation += direction.0.normalize() * velocity.0 * speed * time.delta_secs();
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MapEdge {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+impl MapEdge {
+    pub const ALL: [MapEdge; 4] = [MapEdge::Right, MapEdge::Left, MapEdge::Top, MapEdge::Bottom];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            MapEdge::Left => "left",
+            MapEdge::Right => "right",
+            MapEdge::Top => "top",
+            MapEdge::Bottom => "bottom",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|edge| edge.name() == name)
+    }
+
+    pub fn nearest(bounds: Rect, position: Vec2) -> Self {
+        let offset = (position - bounds.min) / bounds.size();
+        let distance = |edge: MapEdge| match edge {
+            MapEdge::Left => offset.x,
+            MapEdge::Right => 1.0 - offset.x,
+            MapEdge::Top => 1.0 - offset.y,
+            MapEdge::Bottom => offset.y,
+        };
+        Self::ALL
+            .into_iter()
+            .min_by(|&a, &b| distance(a).total_cmp(&distance(b)))
+            .unwrap_or(MapEdge::Right)
+    }
+}
+
+#[derive(Resource)]
+pub struct WorldBounds {
+    rect: Rect,
+    leak_edge: MapEdge,
+}
+
+impl WorldBounds {
+    pub fn for_level(level: &Level) -> Self {
+        WorldBounds {
+            rect: level.world_bounds,
+            leak_edge: level
+                .leak_edge
+                .unwrap_or_else(|| MapEdge::nearest(level.world_bounds, level.base)),
+        }
+    }
+
+    fn exit_edge(&self, position: Vec2) -> Option<MapEdge> {
+        if position.x < self.rect.min.x {
+            Some(MapEdge::Left)
+        } else if position.x > self.rect.max.x {
+            Some(MapEdge::Right)
+        } else if position.y > self.rect.max.y {
+            Some(MapEdge::Top)
+        } else if position.y < self.rect.min.y {
+            Some(MapEdge::Bottom)
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for WorldBounds {
+    fn default() -> Self {
+        WorldBounds::for_level(&Level::default())
+    }
+}
+
+pub fn setup_world_bounds(mut commands: Commands, level: Option<Res<Level>>) {
+    let bounds = match level {
+        Some(level) => WorldBounds::for_level(&level),
+        None => WorldBounds::default(),
+    };
+    commands.insert_resource(bounds);
+}
+
+pub fn despawn_out_of_bounds(
+    mut commands: Commands,
+    bounds: Res<WorldBounds>,
+    mut lives: ResMut<Lives>,
+    mut shield: ResMut<Shield>,
+    mut last_stand: ResMut<LastStand>,
+    enemies: Query<(Entity, &Transform, &EnemyKind), With<Enemy>>,
+) {
+    for (entity, transform, &kind) in &enemies {
+        let position = transform.translation.truncate();
+        let Some(edge) = bounds.exit_edge(position) else {
+            continue;
+        };
+        if bounds.leak_edge == edge {
+            record_leak(&mut lives, &mut shield, &mut last_stand, kind);
+        } else {
+            warn!(
+                "simulation: {} left the world through the {edge:?} edge at {position}",
+                kind.name()
+            );
+        }
+        commands.entity(entity).despawn();
+    }
+}
+
+const ENRAGE_INTERVAL: f32 = 5.0;
+const ENRAGE_SPEED_PER_STACK: f32 = 0.2;
+
+#[derive(Resource, Default)]
+pub struct Enrage {
+    pub wave: u32,
+    pub elapsed: f32,
+    pub stacks: u32,
+}
+
+impl Enrage {
+    fn speed_multiplier(&self) -> f32 {
+        1.0 + self.stacks as f32 * ENRAGE_SPEED_PER_STACK
+    }
+}
+
+fn tick_enrage(
+    mut commands: Commands,
+    time: Res<Time>,
+    level: Option<Res<Level>>,
+    waves: Res<Waves>,
+    mut enrage: ResMut<Enrage>,
+) {
+    let Some(limit) = level.and_then(|level| level.enrage_after) else {
+        return;
+    };
+    if enrage.wave != waves.number {
+        *enrage = Enrage {
+            wave: waves.number,
+            ..default()
+        };
+    }
+    enrage.elapsed += time.delta_secs();
+    let stacks = ((enrage.elapsed - limit) / ENRAGE_INTERVAL).ceil().max(0.0) as u32;
+    if stacks > enrage.stacks {
+        if enrage.stacks == 0 {
+            spawn_toast(&mut commands, "Enemies are enraged!");
+        }
+        enrage.stacks = stacks;
+    }
+}
+
+fn update_corpse_position(
+    time: Res<Time>,
+    mut query: Query<(&mut Transform, &Direction, &Velocity), With<Corpse>>,
+) {
+    for (mut transform, direction, velocity) in &mut query {
+        transform.translation += direction.0 * velocity.0 * time.delta_secs();
+    }
+}
+
+const ABILITY_COOLDOWN: f32 = 4.0;
+const ABILITY_CHARGE_TIME: f32 = 1.0;
+const TELEPORT_DISTANCE: f32 = 80.0;
+const SUMMON_COUNT: u32 = 2;
+const EMP_RADIUS: f32 = 150.0;
+const INTERRUPT_DAMAGE: u32 = 300;
+
+#[derive(Clone, Copy)]
+enum AbilityKind {
+    Teleport,
+    Summon,
+    Emp,
+}
+
+impl AbilityKind {
+    fn name(self) -> &'static str {
+        match self {
+            AbilityKind::Teleport => "Teleport",
+            AbilityKind::Summon => "Summon",
+            AbilityKind::Emp => "EMP",
+        }
+    }
+}
+
+#[derive(Event)]
+pub struct AbilityCast {
+    pub ability: &'static str,
+    pub interrupted: bool,
+}
+
+const BOSS_ABILITIES: [AbilityKind; 3] =
+    [AbilityKind::Teleport, AbilityKind::Summon, AbilityKind::Emp];
+
+#[derive(Component)]
+struct Abilities {
+    rotation: &'static [AbilityKind],
+    next: usize,
+    cooldown: Timer,
+    charge: Option<Timer>,
+    charge_start_hp: u32,
+}
+
+impl Abilities {
+    pub fn new(rotation: &'static [AbilityKind]) -> Self {
+        Abilities {
+            rotation,
+            next: 0,
+            cooldown: Timer::from_seconds(ABILITY_COOLDOWN, TimerMode::Once),
+            charge: None,
+            charge_start_hp: 0,
+        }
+    }
+}
+
+#[derive(Component)]
+struct Telegraph(Entity);
+
+fn tick_enemy_abilities(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut cast: EventWriter<AbilityCast>,
+    assets: Res<GameAssets>,
+    mut enemies: Query<
+        (
+            &mut Transform,
+            &Direction,
+            &Hp,
+            &PathProgress,
+            &StatusEffects,
+            &mut Abilities,
+        ),
+        With<Enemy>,
+    >,
+    mut towers: Query<(&Transform, &mut Cooldown), (With<Tower>, Without<Enemy>)>,
+) {
+    for (mut transform, direction, hp, progress, effects, mut abilities) in &mut enemies {
+        if abilities.charge.is_none() {
+            if abilities.cooldown.tick(time.delta()).finished() {
+                abilities.charge = Some(Timer::from_seconds(ABILITY_CHARGE_TIME, TimerMode::Once));
+                abilities.charge_start_hp = hp.0;
+            }
+            continue;
+        }
+
+        if effects.is_stunned()
+            || abilities.charge_start_hp.saturating_sub(hp.0) >= INTERRUPT_DAMAGE
+        {
+            abilities.cooldown.reset();
+            abilities.charge = None;
+            spawn_toast(&mut commands, "Ability interrupted");
+            cast.send(AbilityCast {
+                ability: abilities.rotation[abilities.next].name(),
+                interrupted: true,
+            });
+            continue;
+        }
+        if !abilities
+            .charge
+            .as_mut()
+            .is_some_and(|charge| charge.tick(time.delta()).finished())
+        {
+            continue;
+        }
+
+        let ability = abilities.rotation[abilities.next];
+        cast.send(AbilityCast {
+            ability: ability.name(),
+            interrupted: false,
+        });
+        match ability {
+            AbilityKind::Teleport => {
+                transform.translation += direction.0.normalize() * TELEPORT_DISTANCE;
+            }
+            AbilityKind::Summon => {
+                for i in 0..SUMMON_COUNT {
+                    let offset = Vec2::from_angle(i as f32 * std::f32::consts::PI) * 15.0;
+                    let minion = spawn_enemy_at(
+                        &mut commands,
+                        &assets,
+                        EnemyKind::Grunt,
+                        transform.translation + offset.extend(0.0),
+                        direction.0,
+                        EnemyKind::Grunt.max_hp(),
+                    );
+                    commands.entity(minion).insert(*progress);
+                }
+            }
+            AbilityKind::Emp => {
+                for (tower_transform, mut cooldown) in &mut towers {
+                    if tower_transform.translation.distance(transform.translation) <= EMP_RADIUS {
+                        cooldown.0.reset();
+                    }
+                }
+            }
+        }
+
+        abilities.next = (abilities.next + 1) % abilities.rotation.len();
+        abilities.cooldown.reset();
+        abilities.charge = None;
+    }
+}
+
+fn update_telegraphs(
+    mut telegraphs: Query<(&Telegraph, &mut Transform, &mut Visibility)>,
+    enemies: Query<(&Transform, &Abilities), Without<Telegraph>>,
+) {
+    for (telegraph, mut transform, mut visibility) in &mut telegraphs {
+        let Ok((enemy_transform, abilities)) = enemies.get(telegraph.0) else {
+            continue;
+        };
+        match &abilities.charge {
+            Some(charge) => {
+                *visibility = Visibility::Inherited;
+                transform.translation = enemy_transform.translation + Vec3::new(0.0, 20.0, 1.0);
+                transform.scale = Vec3::splat(charge.fraction());
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+}
+
+fn despawn_dependents(
+    trigger: Trigger<OnRemove, Dependents>,
+    mut commands: Commands,
+    query: Query<&Dependents>,
+) {
+    if let Ok(dependents) = query.get(trigger.entity()) {
+        for &dependent in &dependents.0 {
+            if let Some(mut entity) = commands.get_entity(dependent) {
+                entity.despawn();
+            }
+        }
+    }
+}
+
+pub struct EnemyPlugin;
+
+impl Plugin for EnemyPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(EnemySpawnTimer(Timer::from_seconds(
+            ENEMY_SPAWN_INTERVAL,
+            TimerMode::Repeating,
+        )));
+        app.insert_resource(Waves::for_run());
+        app.init_resource::<WorldBounds>();
+        app.init_resource::<NextSpawn>();
+        app.init_resource::<Path>();
+        app.init_resource::<AirPath>();
+        app.init_resource::<Enrage>();
+        app.add_event::<WaveStarted>();
+        app.add_event::<WaveCleared>();
+        app.add_event::<AbilityCast>();
+        app.add_observer(despawn_dependents);
+        app.add_systems(Startup, (configure_waves, setup_world_bounds));
+        app.add_systems(
+            FixedUpdate,
+            (
+                spawn_enemy,
+                (update_enemy_position, follow_path).chain(),
+                tick_enemy_abilities,
+                tick_enrage,
+                despawn_out_of_bounds.after(resolve_base_hits),
+                split_dead_enemies.before(despawn_dead_enemies),
+                emit_carrier_minions.after(follow_path),
+                heal_nearby_enemies.before(despawn_dead_enemies),
+                raise_corpses.after(follow_path),
+            )
+                .in_set(SimulationSet::Step),
+        );
+        app.add_systems(Update, (update_telegraphs, update_corpse_position));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn budget_waves_stay_within_budget() {
+        for seed in 0..20 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            for number in 1..=60 {
+                let context = WaveContext {
+                    number,
+                    lives: STARTING_LIVES,
+                    mutators: Mutators::default(),
+                };
+                let wave = BudgetWaves.generate(&context, &mut rng);
+                let mut spent = 0;
+                for entry in &BUDGET_TABLE {
+                    let spawned = wave.iter().filter(|&&kind| kind == entry.kind).count() as u32;
+                    let groups = spawned.div_ceil(entry.burst);
+                    assert!(groups <= entry.max_per_wave);
+                    assert!(groups == 0 || entry.min_wave <= number);
+                    spent += groups * entry.cost;
+                }
+                let budget = WAVE_BASE_BUDGET + number * WAVE_BUDGET_GROWTH;
+                assert!(spent <= budget, "wave {number} spent {spent} of {budget}");
+                assert!(!wave.is_empty());
+            }
+        }
+    }
+}
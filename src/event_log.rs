@@ -0,0 +1,135 @@
+use crate::combat::{BossKilled, EnemyReachedBase};
+use crate::common::{FrameSet, Lives, RunClock};
+use crate::enemy::{AbilityCast, WaveStarted};
+use crate::theme::{ThemedPanel, ThemedText};
+use crate::ui::format_run_time;
+use bevy::prelude::*;
+use std::collections::VecDeque;
+
+const EVENT_LOG_CAPACITY: usize = 100;
+const EVENT_LOG_LINES: usize = 8;
+const EVENT_LOG_PANEL: Color = Color::srgba(0.0, 0.0, 0.0, 0.5);
+
+#[derive(Resource, Default)]
+pub struct EventLog {
+    entries: VecDeque<(f32, String)>,
+    scroll: usize,
+    expanded: bool,
+}
+
+impl EventLog {
+    fn push(&mut self, time: f32, entry: String) {
+        if self.entries.len() == EVENT_LOG_CAPACITY {
+            self.entries.pop_front();
+        }
+        self.entries.push_back((time, entry));
+    }
+
+    fn text(&self) -> String {
+        if !self.expanded {
+            return format!("Event log ({}) [L]", self.entries.len());
+        }
+        let end = self.entries.len().saturating_sub(self.scroll);
+        let start = end.saturating_sub(EVENT_LOG_LINES);
+        let mut text = String::from("Event log [L]  PgUp/PgDn to scroll");
+        for (time, entry) in self.entries.range(start..end) {
+            text.push_str(&format!("\n{}  {entry}", format_run_time(*time)));
+        }
+        text
+    }
+}
+
+fn record_events(
+    clock: Res<RunClock>,
+    lives: Res<Lives>,
+    mut log: ResMut<EventLog>,
+    mut wave_started: EventReader<WaveStarted>,
+    mut reached: EventReader<EnemyReachedBase>,
+    mut boss_killed: EventReader<BossKilled>,
+    mut cast: EventReader<AbilityCast>,
+) {
+    if log.entries.back().is_some_and(|&(time, _)| time > clock.0) {
+        log.entries.retain(|&(time, _)| time <= clock.0);
+        log.scroll = 0;
+    }
+    for WaveStarted(wave) in wave_started.read() {
+        log.push(clock.0, format!("Wave {wave} started"));
+    }
+    for hit in reached.read() {
+        log.push(
+            clock.0,
+            format!("{} leaked, {} lives left", hit.kind.name(), lives.0),
+        );
+    }
+    for _ in boss_killed.read() {
+        log.push(clock.0, "Boss killed".to_string());
+    }
+    for ability in cast.read() {
+        let entry = if ability.interrupted {
+            format!("{} interrupted", ability.ability)
+        } else {
+            format!("Boss cast {}", ability.ability)
+        };
+        log.push(clock.0, entry);
+    }
+}
+
+fn navigate_event_log(keys: Res<ButtonInput<KeyCode>>, mut log: ResMut<EventLog>) {
+    if keys.just_pressed(KeyCode::KeyL) {
+        log.expanded = !log.expanded;
+        log.scroll = 0;
+    }
+    let max_scroll = log.entries.len().saturating_sub(EVENT_LOG_LINES);
+    if keys.just_pressed(KeyCode::PageUp) {
+        log.scroll = (log.scroll + EVENT_LOG_LINES).min(max_scroll);
+    }
+    if keys.just_pressed(KeyCode::PageDown) {
+        log.scroll = log.scroll.saturating_sub(EVENT_LOG_LINES);
+    }
+}
+
+#[derive(Component)]
+struct EventLogText;
+
+fn setup_event_log(mut commands: Commands) {
+    commands.spawn((
+        Text::default(),
+        TextFont::from_font_size(14.0),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(68.0),
+            right: Val::Px(12.0),
+            padding: UiRect::all(Val::Px(6.0)),
+            ..default()
+        },
+        BackgroundColor(EVENT_LOG_PANEL),
+        ThemedPanel(EVENT_LOG_PANEL),
+        ThemedText,
+        Interaction::default(),
+        EventLogText,
+    ));
+}
+
+fn update_event_log(log: Res<EventLog>, mut text: Single<&mut Text, With<EventLogText>>) {
+    if log.is_changed() {
+        text.0 = log.text();
+    }
+}
+
+pub struct EventLogPlugin;
+
+impl Plugin for EventLogPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EventLog>();
+        app.add_systems(Startup, setup_event_log);
+        app.add_systems(
+            Update,
+            (
+                record_events,
+                navigate_event_log,
+                update_event_log.in_set(FrameSet::Hud),
+            )
+                .chain(),
+        );
+    }
+}
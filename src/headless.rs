@@ -0,0 +1,182 @@
+use crate::common::{GameState, Lives};
+use crate::enemy::Waves;
+use crate::save::{modified_time, RunRestore, RunSnapshot, RunState, AUTOSAVE_PATH};
+use crate::ui::KillCam;
+use crate::HelloPlugin;
+use bevy::ecs::system::RunSystemOnce;
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use std::fs;
+use std::time::{Duration, SystemTime};
+
+pub const SOAK_DEFAULT_WAVES: u32 = 2000;
+
+pub fn soak_waves() -> Option<u32> {
+    let mut args = std::env::args().skip_while(|arg| arg != "--soak");
+    args.next()?;
+    Some(
+        args.next()
+            .and_then(|arg| arg.parse().ok())
+            .unwrap_or(SOAK_DEFAULT_WAVES),
+    )
+}
+
+#[derive(Resource)]
+pub struct IdleSimulation;
+
+const SOAK_SPEED: f32 = 16.0;
+const SOAK_REPORT_PATH: &str = "saves/soak_report.txt";
+const SOAK_MAX_ENTITIES: u32 = 10_000;
+const SOAK_MAX_MEMORY_GROWTH_KB: u64 = 256 * 1024;
+
+fn resident_memory_kb() -> u64 {
+    fs::read_to_string("/proc/self/statm")
+        .ok()
+        .and_then(|statm| statm.split_whitespace().nth(1)?.parse::<u64>().ok())
+        .map_or(0, |pages| pages * 4)
+}
+
+fn headless_app(speed: f32) -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        AssetPlugin::default(),
+        bevy::input::InputPlugin,
+        bevy::state::app::StatesPlugin,
+    ));
+    app.init_resource::<GizmoConfigStore>();
+    app.init_gizmo_group::<DefaultGizmoConfigGroup>();
+    app.init_asset::<Mesh>();
+    app.init_asset::<ColorMaterial>();
+    app.init_asset::<bevy::gizmos::LineGizmo>();
+    app.world_mut().spawn(Window::default());
+    app.add_plugins(HelloPlugin);
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+        1.0 / 60.0,
+    )));
+    app.insert_resource(KillCam {
+        enabled: false,
+        timer: None,
+    });
+    let mut virtual_time = app.world_mut().resource_mut::<Time<Virtual>>();
+    virtual_time.set_max_delta(Duration::from_secs(1));
+    virtual_time.set_relative_speed(speed);
+    app
+}
+
+pub fn run_soak(waves: u32) {
+    let mut app = headless_app(SOAK_SPEED);
+    let mut baseline = None;
+    let mut last_wave = 0;
+    let mut waves_seen = 0;
+    let mut report = Vec::new();
+    while waves_seen < waves {
+        app.update();
+        let world = app.world();
+        let wave = world.resource::<Waves>().number;
+        if wave == last_wave {
+            continue;
+        }
+        last_wave = wave;
+        if wave == 0 {
+            continue;
+        }
+        waves_seen += 1;
+
+        let entities = world.entities().len();
+        let meshes = world.resource::<Assets<Mesh>>().len();
+        let materials = world.resource::<Assets<ColorMaterial>>().len();
+        let memory = resident_memory_kb();
+        let (base_meshes, base_materials, base_memory) =
+            *baseline.get_or_insert((meshes, materials, memory));
+        assert!(
+            entities <= SOAK_MAX_ENTITIES,
+            "{entities} entities alive at wave {waves_seen}"
+        );
+        assert!(
+            meshes <= base_meshes && materials <= base_materials,
+            "asset leak at wave {waves_seen}: {meshes} meshes, {materials} materials"
+        );
+        assert!(
+            memory <= base_memory + SOAK_MAX_MEMORY_GROWTH_KB,
+            "resident memory grew from {base_memory} KiB to {memory} KiB by wave {waves_seen}"
+        );
+        if waves_seen.is_multiple_of(100) {
+            report.push(format!(
+                "{waves_seen} waves, {entities} entities, {memory} KiB resident"
+            ));
+        }
+    }
+    report.push(format!("{waves_seen} waves completed"));
+    let _ = fs::create_dir_all("saves");
+    if let Err(error) = fs::write(SOAK_REPORT_PATH, report.join("\n") + "\n") {
+        warn!("failed to write {SOAK_REPORT_PATH}: {error}");
+    }
+}
+
+const IDLE_SECONDS_PER_WAVE: u64 = 120;
+const IDLE_MAX_WAVES: u32 = 10;
+const IDLE_SPEED: f32 = 16.0;
+const IDLE_MAX_FRAMES: u32 = 50_000;
+
+#[derive(Resource)]
+pub struct IdleReport {
+    pub waves: u32,
+    pub gold: i64,
+    pub lives_lost: u32,
+}
+
+pub fn run_idle_progress() -> Option<IdleReport> {
+    let away = SystemTime::now()
+        .duration_since(modified_time(AUTOSAVE_PATH)?)
+        .ok()?;
+    let waves = ((away.as_secs() / IDLE_SECONDS_PER_WAVE) as u32).min(IDLE_MAX_WAVES);
+    if waves == 0 {
+        return None;
+    }
+    let start = RunSnapshot::read(AUTOSAVE_PATH).ok()?;
+    let (start_wave, start_gold, start_lives) = (start.wave, start.gold, start.lives);
+
+    let mut app = headless_app(IDLE_SPEED);
+    app.insert_resource(IdleSimulation);
+    app.update();
+    let world = app.world_mut();
+    world
+        .run_system_once_with(
+            start,
+            |In(snapshot): In<RunSnapshot>, mut restore: RunRestore| snapshot.restore(&mut restore),
+        )
+        .ok()?;
+
+    let capture = |world: &mut World| {
+        world
+            .run_system_once(|state: RunState| RunSnapshot::capture(&state))
+            .ok()
+    };
+    let mut result = capture(app.world_mut())?;
+    let mut last_wave = result.wave;
+    for _ in 0..IDLE_MAX_FRAMES {
+        app.update();
+        let world = app.world_mut();
+        if world.resource::<Lives>().0 == 0
+            || *world.resource::<State<GameState>>().get() == GameState::GameOver
+        {
+            break;
+        }
+        let wave = world.resource::<Waves>().number;
+        if wave != last_wave {
+            last_wave = wave;
+            result = capture(world)?;
+            if wave >= start_wave + waves {
+                break;
+            }
+        }
+    }
+
+    result.write(AUTOSAVE_PATH);
+    Some(IdleReport {
+        waves: result.wave - start_wave,
+        gold: result.gold as i64 - start_gold as i64,
+        lives_lost: start_lives.saturating_sub(result.lives),
+    })
+}
@@ -0,0 +1,90 @@
+use crate::combat::{check_enemy_base_collision, resolve_base_hits, EnemyReachedBase};
+use crate::common::{GameState, SimulationSet};
+use crate::ui::{ConfirmAction, Confirmed, HeatmapAssets, HEATMAP_SHADES, HEATMAP_TILE_SIZE};
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use std::fs;
+
+const LEAK_MAP_LAYER: f32 = -1.5;
+const LEAK_MAP_PATH: &str = "saves/leak_map.txt";
+
+#[derive(Resource, Default)]
+struct LeakMap(HashMap<IVec2, u32>);
+
+impl LeakMap {
+    fn to_text(&self) -> String {
+        let mut cells: Vec<_> = self.0.iter().collect();
+        cells.sort_by_key(|(cell, count)| (std::cmp::Reverse(**count), cell.x, cell.y));
+        cells
+            .into_iter()
+            .map(|(cell, count)| {
+                let center = cell.as_vec2() * HEATMAP_TILE_SIZE;
+                format!("leak {} {} {count}\n", center.x, center.y)
+            })
+            .collect()
+    }
+}
+
+#[derive(Component)]
+struct LeakMapTile;
+
+fn record_leaks(mut reached: EventReader<EnemyReachedBase>, mut leak_map: ResMut<LeakMap>) {
+    for leak in reached.read() {
+        let cell = (leak.position / HEATMAP_TILE_SIZE).round().as_ivec2();
+        *leak_map.0.entry(cell).or_default() += 1;
+    }
+}
+
+fn reset_leak_map(mut confirmed: EventReader<Confirmed>, mut leak_map: ResMut<LeakMap>) {
+    if confirmed
+        .read()
+        .any(|Confirmed(action)| matches!(action, ConfirmAction::RestartRun))
+    {
+        leak_map.0.clear();
+    }
+}
+
+fn show_leak_map(mut commands: Commands, leak_map: Res<LeakMap>, heatmap: Res<HeatmapAssets>) {
+    let Some(&most) = leak_map.0.values().max() else {
+        return;
+    };
+    for (cell, &count) in &leak_map.0 {
+        let shade = (count as f32 / most as f32 * HEATMAP_SHADES as f32).ceil() as usize;
+        let center = cell.as_vec2() * HEATMAP_TILE_SIZE;
+        commands.spawn((
+            Mesh2d(heatmap.tile_mesh.clone()),
+            MeshMaterial2d(heatmap.materials[shade.clamp(1, HEATMAP_SHADES) - 1].clone()),
+            Transform::from_translation(center.extend(LEAK_MAP_LAYER)),
+            LeakMapTile,
+        ));
+    }
+    let _ = fs::create_dir_all("saves");
+    match fs::write(LEAK_MAP_PATH, leak_map.to_text()) {
+        Ok(()) => info!("leak map written to {LEAK_MAP_PATH}"),
+        Err(error) => warn!("failed to write {LEAK_MAP_PATH}: {error}"),
+    }
+}
+
+fn hide_leak_map(mut commands: Commands, tiles: Query<Entity, With<LeakMapTile>>) {
+    for tile in &tiles {
+        commands.entity(tile).despawn();
+    }
+}
+
+pub struct LeakMapPlugin;
+
+impl Plugin for LeakMapPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LeakMap>();
+        app.add_systems(OnEnter(GameState::GameOver), show_leak_map);
+        app.add_systems(OnExit(GameState::GameOver), hide_leak_map);
+        app.add_systems(Update, reset_leak_map);
+        app.add_systems(
+            FixedUpdate,
+            record_leaks
+                .after(check_enemy_base_collision)
+                .before(resolve_base_hits)
+                .in_set(SimulationSet::Step),
+        );
+    }
+}
@@ -0,0 +1,336 @@
+use crate::common::{Base, GameAssets, LoadErrors};
+use crate::enemy::{
+    configure_waves, setup_world_bounds, MapEdge, ENEMY_SPAWN_INTERVAL, WAVE_BREAK_SECONDS,
+};
+use crate::tower::{setup_grid_map, spawn_level_towers, TowerKind};
+use crate::ui::{CameraFraming, ConfirmAction, Confirmed};
+use bevy::prelude::*;
+use std::fs;
+
+pub const LEVEL_PATH: &str = "assets/levels/default.level";
+
+#[derive(PartialEq)]
+pub struct ScoreFormula {
+    pub lives: u32,
+    pub gold: u32,
+    pub time: u32,
+    pub par_time: f32,
+    pub no_sell: u32,
+}
+
+impl ScoreFormula {
+    pub fn score(&self, lives: u32, gold: u32, time: f32, towers_sold: u32) -> u32 {
+        let time_bonus = (self.par_time - time).max(0.0) as u32 * self.time;
+        let no_sell_bonus = if towers_sold == 0 { self.no_sell } else { 0 };
+        lives * self.lives + gold * self.gold + time_bonus + no_sell_bonus
+    }
+}
+
+#[derive(Resource, PartialEq)]
+pub struct Level {
+    pub waves: u32,
+    pub formula: ScoreFormula,
+    pub stars: [u32; 3],
+    pub enrage_after: Option<f32>,
+    pub base: Vec2,
+    pub starter_towers: Vec<(Vec2, TowerKind)>,
+    pub waypoints: Vec<Vec2>,
+    pub air_waypoints: Vec<Vec2>,
+    pub camera: Option<Vec2>,
+    pub zoom: f32,
+    pub camera_bounds: Option<Rect>,
+    pub world_bounds: Rect,
+    pub leak_edge: Option<MapEdge>,
+    pub spawn_interval: f32,
+    pub wave_break: f32,
+}
+
+impl Default for Level {
+    fn default() -> Self {
+        Level {
+            waves: 15,
+            formula: ScoreFormula {
+                lives: 100,
+                gold: 2,
+                time: 5,
+                par_time: 600.0,
+                no_sell: 500,
+            },
+            stars: [1500, 3000, 4500],
+            enrage_after: None,
+            base: Vec2::ZERO,
+            starter_towers: vec![(Vec2::new(0.0, 60.0), TowerKind::Arrow)],
+            waypoints: Vec::new(),
+            air_waypoints: Vec::new(),
+            camera: None,
+            zoom: 1.0,
+            camera_bounds: None,
+            world_bounds: Rect::new(-2560.0, -1440.0, 2560.0, 1440.0),
+            leak_edge: None,
+            spawn_interval: ENEMY_SPAWN_INTERVAL,
+            wave_break: WAVE_BREAK_SECONDS,
+        }
+    }
+}
+
+impl Level {
+    pub fn load(path: &str, errors: &mut LoadErrors) -> Self {
+        let Ok(text) = fs::read_to_string(path) else {
+            errors.report(format!("missing level {path}, using the default layout"));
+            return Level::default();
+        };
+        Level::parse(&text, |line| {
+            errors.report(format!("skipping bad level line in {path}: {line}"))
+        })
+    }
+
+    pub fn parse(text: &str, mut reject: impl FnMut(&str)) -> Self {
+        let mut level = Level::default();
+        let mut starter_towers = Vec::new();
+        for line in text.lines() {
+            let mut fields = line.split_whitespace();
+            let key = fields.next();
+            let values: Vec<f32> = fields.filter_map(|field| field.parse().ok()).collect();
+            match (key, values.as_slice()) {
+                (Some("waves"), &[waves]) => level.waves = waves as u32,
+                (Some("par_time"), &[seconds]) => level.formula.par_time = seconds,
+                (Some("enrage_after"), &[seconds]) => level.enrage_after = Some(seconds),
+                (Some("score_lives"), &[weight]) => level.formula.lives = weight as u32,
+                (Some("score_gold"), &[weight]) => level.formula.gold = weight as u32,
+                (Some("score_time"), &[weight]) => level.formula.time = weight as u32,
+                (Some("score_no_sell"), &[bonus]) => level.formula.no_sell = bonus as u32,
+                (Some("base"), &[x, y]) => level.base = Vec2::new(x, y),
+                (Some("waypoint"), &[x, y]) => level.waypoints.push(Vec2::new(x, y)),
+                (Some("air_waypoint"), &[x, y]) => level.air_waypoints.push(Vec2::new(x, y)),
+                (Some("camera"), &[x, y]) => level.camera = Some(Vec2::new(x, y)),
+                (Some("zoom"), &[zoom]) if zoom > 0.0 => level.zoom = zoom,
+                (Some("spawn_interval"), &[seconds]) if seconds > 0.0 => {
+                    level.spawn_interval = seconds
+                }
+                (Some("wave_break"), &[seconds]) => level.wave_break = seconds.max(0.0),
+                (Some("camera_bounds"), &[x0, y0, x1, y1]) => {
+                    level.camera_bounds = Some(Rect::new(x0, y0, x1, y1))
+                }
+                (Some("world_bounds"), &[x0, y0, x1, y1]) if x0 != x1 && y0 != y1 => {
+                    level.world_bounds = Rect::new(x0, y0, x1, y1)
+                }
+                (Some("leak_edge"), &[]) => {
+                    match line.split_whitespace().nth(1).and_then(MapEdge::from_name) {
+                        Some(edge) => level.leak_edge = Some(edge),
+                        None => reject(line),
+                    }
+                }
+                (Some("tower"), &[x, y]) => {
+                    let kind = match line.split_whitespace().nth(3) {
+                        Some(name) => TowerKind::from_name(name).unwrap_or_else(|| {
+                            reject(line);
+                            TowerKind::Cannon
+                        }),
+                        None => TowerKind::Cannon,
+                    };
+                    starter_towers.push((Vec2::new(x, y), kind));
+                }
+                (Some("stars"), &[one, two, three]) => {
+                    level.stars = [one as u32, two as u32, three as u32]
+                }
+                (None, _) => {}
+                _ => reject(line),
+            }
+        }
+        if !starter_towers.is_empty() {
+            level.starter_towers = starter_towers;
+        }
+        level
+    }
+
+    pub fn to_text(&self) -> String {
+        let defaults = Level::default();
+        let mut lines = vec![format!("waves {}", self.waves)];
+        let formula = [
+            ("score_lives", self.formula.lives, defaults.formula.lives),
+            ("score_gold", self.formula.gold, defaults.formula.gold),
+            ("score_time", self.formula.time, defaults.formula.time),
+            (
+                "score_no_sell",
+                self.formula.no_sell,
+                defaults.formula.no_sell,
+            ),
+        ];
+        for (key, value, default) in formula {
+            if value != default {
+                lines.push(format!("{key} {value}"));
+            }
+        }
+        if self.formula.par_time != defaults.formula.par_time {
+            lines.push(format!("par_time {}", self.formula.par_time));
+        }
+        if self.stars != defaults.stars {
+            let [one, two, three] = self.stars;
+            lines.push(format!("stars {one} {two} {three}"));
+        }
+        if let Some(seconds) = self.enrage_after {
+            lines.push(format!("enrage_after {seconds}"));
+        }
+        if self.base != defaults.base {
+            lines.push(format!("base {} {}", self.base.x, self.base.y));
+        }
+        for &(position, kind) in &self.starter_towers {
+            lines.push(format!(
+                "tower {} {} {}",
+                position.x,
+                position.y,
+                kind.name()
+            ));
+        }
+        if let Some(camera) = self.camera {
+            lines.push(format!("camera {} {}", camera.x, camera.y));
+        }
+        if self.zoom != defaults.zoom {
+            lines.push(format!("zoom {}", self.zoom));
+        }
+        if let Some(bounds) = self.camera_bounds {
+            let (min, max) = (bounds.min, bounds.max);
+            lines.push(format!(
+                "camera_bounds {} {} {} {}",
+                min.x, min.y, max.x, max.y
+            ));
+        }
+        if self.world_bounds != defaults.world_bounds {
+            let (min, max) = (self.world_bounds.min, self.world_bounds.max);
+            lines.push(format!(
+                "world_bounds {} {} {} {}",
+                min.x, min.y, max.x, max.y
+            ));
+        }
+        if let Some(edge) = self.leak_edge {
+            lines.push(format!("leak_edge {}", edge.name()));
+        }
+        if self.spawn_interval != defaults.spawn_interval {
+            lines.push(format!("spawn_interval {}", self.spawn_interval));
+        }
+        if self.wave_break != defaults.wave_break {
+            lines.push(format!("wave_break {}", self.wave_break));
+        }
+        for waypoint in &self.waypoints {
+            lines.push(format!("waypoint {} {}", waypoint.x, waypoint.y));
+        }
+        for waypoint in &self.air_waypoints {
+            lines.push(format!("air_waypoint {} {}", waypoint.x, waypoint.y));
+        }
+        lines.join("\n")
+    }
+
+    pub fn stars(&self, score: u32) -> usize {
+        self.stars
+            .iter()
+            .filter(|&&threshold| score >= threshold)
+            .count()
+            .max(1)
+    }
+}
+
+pub fn setup_base(mut commands: Commands, assets: Res<GameAssets>, level: Option<Res<Level>>) {
+    let default_level = Level::default();
+    let level = level.as_deref().unwrap_or(&default_level);
+
+    let framing = CameraFraming {
+        home: level.camera.unwrap_or(level.base),
+        zoom: level.zoom,
+        bounds: level.camera_bounds,
+    };
+    commands.spawn((
+        Camera2d,
+        Transform::from_translation(framing.home.extend(0.0)),
+        OrthographicProjection {
+            scale: framing.zoom,
+            ..OrthographicProjection::default_2d()
+        },
+    ));
+    commands.insert_resource(framing);
+
+    commands.spawn((
+        Mesh2d(assets.base_mesh.clone()),
+        MeshMaterial2d(assets.base_material.clone()),
+        Transform::from_translation(level.base.extend(0.0)),
+        Base,
+    ));
+    spawn_level_towers(&mut commands, &assets, level);
+}
+
+pub fn apply_level_layout(
+    mut commands: Commands,
+    level: Res<Level>,
+    mut base: Single<&mut Transform, With<Base>>,
+    camera: Single<(&mut Transform, &mut OrthographicProjection), (With<Camera2d>, Without<Base>)>,
+) {
+    let framing = CameraFraming {
+        home: level.camera.unwrap_or(level.base),
+        zoom: level.zoom,
+        bounds: level.camera_bounds,
+    };
+    base.translation = level.base.extend(0.0);
+    let (mut transform, mut projection) = camera.into_inner();
+    transform.translation = framing.home.extend(0.0);
+    projection.scale = framing.zoom;
+    commands.insert_resource(framing);
+}
+
+pub fn play_level(commands: &mut Commands, level: Level) {
+    commands.insert_resource(level);
+    commands.run_system_cached(configure_waves);
+    commands.run_system_cached(setup_grid_map);
+    commands.run_system_cached(setup_world_bounds);
+    commands.run_system_cached(apply_level_layout);
+    commands.send_event(Confirmed(ConfirmAction::RestartRun));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn score_formula_totals() {
+        let formula = Level::default().formula;
+        assert_eq!(formula.score(10, 50, 500.0, 0), 1000 + 100 + 500 + 500);
+        assert_eq!(formula.score(10, 50, 700.0, 1), 1000 + 100);
+        assert_eq!(formula.score(0, 0, 599.5, 3), 0);
+
+        let custom = ScoreFormula {
+            lives: 50,
+            gold: 1,
+            time: 10,
+            par_time: 300.0,
+            no_sell: 250,
+        };
+        assert_eq!(custom.score(4, 120, 280.0, 0), 200 + 120 + 200 + 250);
+    }
+
+    #[test]
+    fn leak_edge_defaults_to_the_base_side() {
+        let bounds = Level::default().world_bounds;
+        assert_eq!(
+            MapEdge::nearest(bounds, Vec2::new(2000.0, 100.0)),
+            MapEdge::Right
+        );
+        assert_eq!(
+            MapEdge::nearest(bounds, Vec2::new(-300.0, -1200.0)),
+            MapEdge::Bottom
+        );
+
+        let level = Level::parse("world_bounds -800 -600 800 600\nleak_edge top\n", |line| {
+            panic!("rejected {line}")
+        });
+        assert_eq!(level.world_bounds, Rect::new(-800.0, -600.0, 800.0, 600.0));
+        assert_eq!(level.leak_edge, Some(MapEdge::Top));
+        assert!(Level::parse(&level.to_text(), |_| {}) == level);
+    }
+
+    #[test]
+    fn stars_follow_thresholds() {
+        let level = Level::default();
+        assert_eq!(level.stars(0), 1);
+        assert_eq!(level.stars(2999), 1);
+        assert_eq!(level.stars(3000), 2);
+        assert_eq!(level.stars(9000), 3);
+    }
+}
@@ -0,0 +1,139 @@
+use crate::common::GameState;
+use bevy::asset::LoadState;
+use bevy::prelude::*;
+
+const LOADING_BACKGROUND: Color = Color::srgb(0.05, 0.05, 0.08);
+
+#[derive(States, Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum LoadingState {
+    #[default]
+    Loading,
+    Ready,
+}
+
+#[derive(Resource, Default)]
+pub struct Preload(pub Vec<UntypedHandle>);
+
+/// The game state to enter once loading finishes; requests made while
+/// loading land here instead of switching state behind the loading screen.
+#[derive(Resource)]
+struct AfterLoading(GameState);
+
+impl Default for AfterLoading {
+    fn default() -> Self {
+        Self(GameState::Playing)
+    }
+}
+
+#[derive(Component)]
+struct LoadingScreen;
+
+#[derive(Component)]
+struct LoadingBar;
+
+fn spawn_loading_screen(mut commands: Commands) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(12.0),
+                ..default()
+            },
+            BackgroundColor(LOADING_BACKGROUND),
+            GlobalZIndex(i32::MAX),
+            LoadingScreen,
+        ))
+        .with_children(|screen| {
+            screen.spawn(Text::new("Loading"));
+            screen
+                .spawn((
+                    Node {
+                        width: Val::Percent(40.0),
+                        height: Val::Px(12.0),
+                        ..default()
+                    },
+                    BackgroundColor(Color::hsla(0., 0., 0.2, 0.8)),
+                ))
+                .with_child((
+                    Node {
+                        width: Val::Percent(0.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    BackgroundColor(Color::hsl(200., 0.7, 0.6)),
+                    LoadingBar,
+                ));
+        });
+}
+
+fn update_loading_progress(
+    asset_server: Res<AssetServer>,
+    preload: Res<Preload>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    mut bar: Query<&mut Node, With<LoadingBar>>,
+    mut next_state: ResMut<NextState<LoadingState>>,
+) {
+    let settled = preload
+        .0
+        .iter()
+        .filter(|handle| {
+            matches!(
+                asset_server.get_load_state(handle.id()),
+                None | Some(LoadState::Loaded | LoadState::Failed(_))
+            )
+        })
+        .count();
+    let total = preload.0.len();
+    for mut node in &mut bar {
+        node.width = Val::Percent(100.0 * settled as f32 / total.max(1) as f32);
+    }
+    if settled == total {
+        next_state.set(LoadingState::Ready);
+    } else {
+        virtual_time.pause();
+    }
+}
+
+fn hold_game_state(mut next_state: ResMut<NextState<GameState>>, mut after: ResMut<AfterLoading>) {
+    if let NextState::Pending(state) = *next_state {
+        after.0 = state;
+        next_state.reset();
+    }
+}
+
+fn finish_loading(
+    mut commands: Commands,
+    after: Res<AfterLoading>,
+    mut next_state: ResMut<NextState<GameState>>,
+    screens: Query<Entity, With<LoadingScreen>>,
+) {
+    next_state.set(after.0);
+    for screen in &screens {
+        commands.entity(screen).despawn_recursive();
+    }
+}
+
+pub struct LoadingPlugin;
+
+impl Plugin for LoadingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<LoadingState>();
+        app.init_resource::<Preload>();
+        app.init_resource::<AfterLoading>();
+        app.add_systems(OnEnter(LoadingState::Loading), spawn_loading_screen);
+        app.add_systems(OnExit(LoadingState::Loading), finish_loading);
+        app.add_systems(
+            PreUpdate,
+            hold_game_state.run_if(in_state(LoadingState::Loading)),
+        );
+        app.add_systems(
+            Update,
+            update_loading_progress.run_if(in_state(LoadingState::Loading)),
+        );
+    }
+}
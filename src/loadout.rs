@@ -0,0 +1,156 @@
+use crate::common::{FrameSet, GameState};
+use crate::profile::Profile;
+use crate::run_modes::{custom_requested, daily_requested, weekly_requested};
+use crate::theme::{ThemedPanel, ThemedText};
+use crate::tower::{BuildKind, TowerKind};
+use crate::ui::spawn_toast;
+use bevy::prelude::*;
+
+pub const LOADOUT_SIZE: usize = 4;
+const LOADOUT_PANEL: Color = Color::srgba(0.0, 0.0, 0.0, 0.8);
+const LOADOUT_KEYS: [KeyCode; 8] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+];
+
+#[derive(Resource, Clone, PartialEq, Eq)]
+pub struct Loadout(pub Vec<TowerKind>);
+
+impl Default for Loadout {
+    fn default() -> Self {
+        Loadout(TowerKind::ALL.to_vec())
+    }
+}
+
+impl Loadout {
+    pub fn from_names<'a>(names: impl Iterator<Item = &'a str>) -> Self {
+        let mut kinds: Vec<TowerKind> = Vec::new();
+        for kind in names.filter_map(TowerKind::from_name) {
+            if !kinds.contains(&kind) {
+                kinds.push(kind);
+            }
+        }
+        if kinds.is_empty() {
+            return Loadout::default();
+        }
+        Loadout(kinds)
+    }
+
+    pub fn names(&self) -> String {
+        self.0
+            .iter()
+            .map(|kind| kind.name())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn toggle(&mut self, kind: TowerKind) {
+        if self.0.contains(&kind) {
+            self.0.retain(|&chosen| chosen != kind);
+        } else if self.0.len() < LOADOUT_SIZE {
+            self.0.push(kind);
+        }
+        self.0
+            .sort_by_key(|chosen| TowerKind::ALL.iter().position(|kind| kind == chosen));
+    }
+
+    pub fn next_after(&self, kind: TowerKind) -> TowerKind {
+        let index = self.0.iter().position(|&chosen| chosen == kind);
+        self.0[index.map_or(0, |index| (index + 1) % self.0.len())]
+    }
+}
+
+#[derive(Component)]
+struct LoadoutPanel;
+
+fn loadout_requested() -> bool {
+    !daily_requested()
+        && !weekly_requested()
+        && !custom_requested()
+        && std::env::args().any(|arg| arg == "--loadout")
+}
+
+fn loadout_text(loadout: &Loadout) -> String {
+    let mut text = format!("Tower loadout, pick up to {LOADOUT_SIZE}\n");
+    for (index, kind) in TowerKind::ALL.into_iter().enumerate() {
+        let mark = if loadout.0.contains(&kind) { "x" } else { " " };
+        text.push_str(&format!("[{}] [{mark}] {}\n", index + 1, kind.name()));
+    }
+    text.push_str("[Enter] start");
+    text
+}
+
+fn open_loadout_screen(mut commands: Commands, mut loadout: ResMut<Loadout>) {
+    if !loadout_requested() {
+        return;
+    }
+    loadout.0.truncate(LOADOUT_SIZE);
+    commands.set_state(GameState::Menu);
+    commands.spawn((
+        Text::new(loadout_text(&loadout)),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(30.0),
+            left: Val::Percent(40.0),
+            ..default()
+        },
+        BackgroundColor(LOADOUT_PANEL),
+        ThemedPanel(LOADOUT_PANEL),
+        ThemedText,
+        Interaction::default(),
+        LoadoutPanel,
+    ));
+}
+
+fn update_loadout_screen(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    panel: Single<(Entity, &mut Text), With<LoadoutPanel>>,
+    mut loadout: ResMut<Loadout>,
+    mut build_kind: ResMut<BuildKind>,
+) {
+    let (entity, mut text) = panel.into_inner();
+    for (key, kind) in LOADOUT_KEYS.into_iter().zip(TowerKind::ALL) {
+        if keys.just_pressed(key) {
+            loadout.toggle(kind);
+        }
+    }
+    text.0 = loadout_text(&loadout);
+
+    if keys.just_pressed(KeyCode::Enter) {
+        if loadout.0.is_empty() {
+            spawn_toast(&mut commands, "Pick at least one tower");
+            return;
+        }
+        let mut profile = Profile::load();
+        profile.loadout = loadout.clone();
+        profile.save();
+        build_kind.0 = loadout.0[0];
+        commands.set_state(GameState::Playing);
+        commands.entity(entity).despawn();
+        spawn_toast(&mut commands, &format!("Loadout: {}", loadout.names()));
+    }
+}
+
+pub struct LoadoutPlugin;
+
+impl Plugin for LoadoutPlugin {
+    fn build(&self, app: &mut App) {
+        let loadout = Profile::load().loadout;
+        app.insert_resource(BuildKind(loadout.0[0]));
+        app.insert_resource(loadout);
+        app.add_systems(Startup, open_loadout_screen);
+        app.add_systems(
+            Update,
+            update_loadout_screen
+                .run_if(in_state(GameState::Menu))
+                .in_set(FrameSet::Hud),
+        );
+    }
+}
@@ -1,274 +1,301 @@
-use bevy::math::bounding::{Aabb2d, BoundingCircle, IntersectsVolume};
+#![allow(clippy::too_many_arguments, clippy::type_complexity)]
+
+mod accessibility;
+mod audit;
+mod barks;
+mod cinematic;
+mod combat;
+mod common;
+mod cosmetics;
+mod decals;
+mod economy;
+mod enemy;
+mod event_log;
+mod headless;
+mod leak_map;
+mod level;
+mod loading;
+mod loadout;
+mod map_code;
+mod music;
+mod ping;
+mod practice;
+mod profile;
+mod projectile;
+mod rewind;
+mod run_end;
+mod run_modes;
+mod save;
+mod scouting;
+mod status;
+mod theme;
+mod tower;
+mod turn_based;
+mod ui;
+
+use accessibility::AccessibilityPlugin;
+use audit::AuditPlugin;
+use barks::BarksPlugin;
 use bevy::prelude::*;
-use bevy::sprite::{Wireframe2dConfig, Wireframe2dPlugin};
-use rand::prelude::*;
-
-#[derive(Resource)]
-struct EnemySpawnTimer(Timer);
-
-#[derive(Component)]
-struct Enemy;
-
-#[derive(Component)]
-struct Velocity(f32);
-
-#[derive(Component)]
-struct Direction(Vec3);
-
-#[derive(Component)]
-struct Player;
-
-#[derive(Component)]
-struct Collided(bool);
-
-#[derive(Component)]
-struct Range(f32);
-
-#[derive(Component)]
-struct Projectile;
-
-#[derive(Component)]
-struct Target(Option<Entity>);
-
-#[derive(Component)]
-struct FireRate(f32);
-
-#[derive(Component)]
-struct Hp(u32);
-
-#[derive(Component)]
-struct ProjectedHp(u32);
-
-#[derive(Component)]
-struct Cooldown(Timer);
-
-fn setup_tower(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-) {
-    commands.spawn(Camera2d);
-    let shape = meshes.add(Rectangle::new(40., 40.));
-    let color = Color::hsl(360., 0.95, 0.7);
-
-    commands.spawn((
-        Mesh2d(shape),
-        MeshMaterial2d(materials.add(color)),
-        Transform::from_xyz(0.0, 0.0, 0.0),
-        Range(200.0),
-        FireRate(1.0),
-        Cooldown(Timer::from_seconds(0.2, TimerMode::Repeating)),
-        Player,
-        Target(None),
-    ));
+use bevy::sprite::Wireframe2dPlugin;
+use cinematic::CinematicPlugin;
+use combat::CombatPlugin;
+use common::{
+    in_run, simulating, FrameSet, GameAssets, GameRng, GameState, Gold, Lives, LoadErrors,
+    Mutators, Score, Shield, SimulationSet, STARTING_GOLD, STARTING_LIVES,
+};
+use cosmetics::CosmeticsPlugin;
+use decals::DecalsPlugin;
+use economy::EconomyPlugin;
+use enemy::EnemyPlugin;
+use event_log::EventLogPlugin;
+use headless::{run_idle_progress, run_soak, soak_waves};
+use leak_map::LeakMapPlugin;
+use level::{setup_base, Level, LEVEL_PATH};
+use loading::{LoadingPlugin, LoadingState};
+use loadout::LoadoutPlugin;
+use map_code::MapCodePlugin;
+use music::MusicPlugin;
+use ping::PingPlugin;
+use practice::PracticePlugin;
+use profile::Profile;
+use projectile::ProjectilePlugin;
+use rewind::RewindPlugin;
+use run_end::RunEndPlugin;
+use run_modes::{
+    custom_requested, daily_requested, demo_requested, endless_requested, weekly_requested,
+    DemoMode, RunModesPlugin,
+};
+use save::SavePlugin;
+use scouting::ScoutingPlugin;
+use status::StatusPlugin;
+use std::fs;
+use theme::ThemePlugin;
+use tower::TowerPlugin;
+use turn_based::TurnBasedPlugin;
+use ui::{navigate_confirm_dialog, ConfirmDialog, UiPlugin};
+
+const CRASH_LOG_PATH: &str = "saves/crash.log";
+
+fn pause_virtual_time(mut virtual_time: ResMut<Time<Virtual>>) {
+    virtual_time.pause();
 }
 
-fn spawn_enemy(
-    mut commands: Commands,
-    time: Res<Time>,
-    mut spawn_timer: ResMut<EnemySpawnTimer>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    query: Query<&Transform, With<Player>>,
-    window: Single<&Window>,
-) {
-    let mut rng = rand::thread_rng();
-    if spawn_timer.0.tick(time.delta()).just_finished() {
-        for player_transform in &query {
-            let shape = meshes.add(Rectangle::new(10.0, 10.0));
-            let color = Color::hsl(360., 0.95, 0.7);
-            let dir =
-                Vec2::new(rng.gen_range(-100.0..100.0), rng.gen_range(-100.0..100.0)).normalize();
-            let enemy_center = Vec2::new(window.width() / 2., window.height() / 2.)
-                + Vec2::new(window.width(), window.height()) * dir;
-            let enemy_transform = Transform::from_xyz(enemy_center.x, enemy_center.y, 0.0);
-
-            commands.spawn((
-                Mesh2d(shape),
-                MeshMaterial2d(materials.add(color)),
-                enemy_transform,
-                Enemy,
-                Velocity(100.0),
-                Direction(player_transform.translation - enemy_transform.translation),
-                Collided(false),
-                Hp(100),
-                ProjectedHp(100),
-            ));
-        }
-    }
+fn resume_virtual_time(mut virtual_time: ResMut<Time<Virtual>>) {
+    virtual_time.unpause();
 }
 
-fn update_enemy_position(
-    time: Res<Time>,
-    mut query: Query<(&mut Transform, &Direction, &Velocity), With<Enemy>>,
+fn toggle_pause(
+    keys: Res<ButtonInput<KeyCode>>,
+    dialog: Option<Res<ConfirmDialog>>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
 ) {
-    for (mut transform, direction, velocity) in &mut query {
-        transform.translation += direction.0.normalize() * velocity.0 * time.delta_secs();
+    if !keys.any_just_pressed([KeyCode::Escape, KeyCode::KeyP]) || dialog.is_some() {
+        return;
     }
+    next_state.set(match state.get() {
+        GameState::Paused => GameState::Playing,
+        _ => GameState::Paused,
+    });
 }
 
-fn check_enemy_player_collision(
-    player_transform: Single<&Transform, With<Player>>,
-    mut query: Query<(&Transform, &mut Collided), With<Enemy>>,
-) {
-    for (enemy_transform, mut collided) in &mut query {
-        let enemy_bounding = Aabb2d::new(
-            enemy_transform.translation.truncate(),
-            enemy_transform.scale.truncate() / 2.,
+pub struct HelloPlugin;
+impl Plugin for HelloPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            EnemyPlugin,
+            TowerPlugin,
+            ProjectilePlugin,
+            CombatPlugin,
+            UiPlugin,
+            EconomyPlugin,
+            AccessibilityPlugin,
+            EventLogPlugin,
+            PingPlugin,
+            TurnBasedPlugin,
+            ThemePlugin,
+        ));
+        app.add_plugins((
+            MapCodePlugin,
+            LoadingPlugin,
+            AuditPlugin,
+            LeakMapPlugin,
+            BarksPlugin,
+            MusicPlugin,
+            PracticePlugin,
+            LoadoutPlugin,
+            CosmeticsPlugin,
+            DecalsPlugin,
+            CinematicPlugin,
+            ScoutingPlugin,
+            StatusPlugin,
+        ));
+        app.add_plugins((RunEndPlugin, SavePlugin, RewindPlugin, RunModesPlugin));
+        app.init_state::<GameState>();
+        app.add_systems(OnEnter(GameState::Playing), resume_virtual_time);
+        app.add_systems(OnExit(GameState::Playing), pause_virtual_time);
+        app.configure_sets(
+            FixedUpdate,
+            (
+                SimulationSet::Step,
+                SimulationSet::Limit,
+                SimulationSet::Record,
+            )
+                .chain()
+                .run_if(simulating),
         );
-
-        let player_bounding = Aabb2d::new(
-            player_transform.translation.truncate(),
-            player_transform.scale.truncate() / 2.,
+        app.configure_sets(
+            Update,
+            (
+                FrameSet::Input
+                    .run_if(in_run)
+                    .run_if(in_state(LoadingState::Ready)),
+                FrameSet::Hud,
+            )
+                .chain(),
         );
-
-        if enemy_bounding.intersects(&player_bounding) {
-            collided.0 = true;
+        app.init_resource::<GameAssets>();
+        app.insert_resource(Gold(STARTING_GOLD));
+        app.insert_resource(Lives(STARTING_LIVES));
+        app.init_resource::<Shield>();
+        let mut load_errors = LoadErrors::from_args();
+        if demo_requested() {
+            app.insert_resource(DemoMode);
+        } else if !daily_requested()
+            && !weekly_requested()
+            && !custom_requested()
+            && !endless_requested()
+        {
+            let level = map_code::requested_level(&mut load_errors)
+                .unwrap_or_else(|| Level::load(LEVEL_PATH, &mut load_errors));
+            app.insert_resource(level);
         }
+        app.insert_resource(load_errors);
+        app.init_resource::<Score>();
+        app.init_resource::<GameRng>();
+        app.init_resource::<Mutators>();
+        app.insert_resource(Profile::load().commander);
+        app.add_systems(Startup, setup_base);
+        app.add_systems(
+            Update,
+            toggle_pause
+                .after(navigate_confirm_dialog)
+                .in_set(FrameSet::Input),
+        );
     }
 }
 
-fn despawn_collided_enemies(
-    mut commands: Commands,
-    mut query: Query<(Entity, &Collided), With<Enemy>>,
-) {
-    for (entity, collided) in &mut query {
-        if collided.0 {
-            commands.entity(entity).despawn();
-        }
-    }
+fn install_crash_log() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = fs::create_dir_all("saves");
+        let backtrace = std::backtrace::Backtrace::force_capture();
+        let _ = fs::write(CRASH_LOG_PATH, format!("{info}\n{backtrace}"));
+        default_hook(info);
+    }));
 }
 
-fn tower_choose_target(
-    query: Query<(Entity, &Transform, &ProjectedHp), With<Enemy>>,
-    player: Single<(&Transform, &mut Target), With<Player>>,
-) {
-    let (player_transform, mut target) = player.into_inner();
-
-    let mut closest_enemy: Option<Entity> = None;
-    let mut distance_to_player = f32::MAX;
-    for (entity, enemy_transform, enemy_projected_hp) in &query {
-        let curr_distance_to_player = enemy_transform
-            .translation
-            .distance(player_transform.translation);
-        if curr_distance_to_player < distance_to_player && enemy_projected_hp.0 > 0 {
-            closest_enemy = Some(entity);
-            distance_to_player = curr_distance_to_player;
-        }
+fn main() {
+    install_crash_log();
+    if let Some(waves) = soak_waves() {
+        run_soak(waves);
+        return;
     }
-
-    target.0 = closest_enemy;
+    let idle_report = endless_requested().then(run_idle_progress).flatten();
+    let mut app = App::new();
+    app.add_plugins(DefaultPlugins)
+        .add_plugins(Wireframe2dPlugin)
+        .add_plugins(HelloPlugin);
+    if let Some(report) = idle_report {
+        app.insert_resource(report);
+    }
+    app.run();
 }
 
-fn tower_shoot_target(
-    time: Res<Time>,
-    mut commands: Commands,
-    mut query: Query<(Entity, &Transform, &mut ProjectedHp), With<Enemy>>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    player: Single<(&Range, &mut Cooldown, &Transform, &mut Target), With<Player>>,
-) {
-    let (player_range, mut cooldown, player_transform, target) = player.into_inner();
-
-    cooldown.0.tick(time.delta());
-    if let Some(enemy) = target.0 {
-        let color = Color::hsl(360., 0.95, 0.7);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::combat::tower_choose_target;
+    use crate::common::{Bounty, Enemy, Hp, MaxHp, ProjectedHp, Target, Tower};
+    use crate::enemy::{update_enemy_position, Direction, Enrage, PathProgress, Velocity};
+    use crate::tower::Range;
+    use crate::ui::{update_health_bars, HealthBar};
+    use bevy::ecs::schedule::ExecutorKind;
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+
+    thread_local! {
+        static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    }
 
-        if let Ok((_, enemy_transform, mut enemy_projected_hp)) = query.get_mut(enemy) {
-            let distance_to_player = enemy_transform
-                .translation
-                .distance(player_transform.translation);
+    struct CountingAllocator;
 
-            if distance_to_player < player_range.0
-                && cooldown.0.just_finished()
-                && enemy_projected_hp.0 > 0
-            {
-                commands.spawn((
-                    Mesh2d(meshes.add(Circle::new(5.0))),
-                    MeshMaterial2d(materials.add(color)),
-                    player_transform.clone(),
-                    Velocity(100.0),
-                    Projectile,
-                    Target(Some(enemy)),
-                    Direction(enemy_transform.translation - player_transform.translation),
-                ));
-                enemy_projected_hp.0 -= 50;
-            }
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            ALLOCATIONS.with(|count| count.set(count.get() + 1));
+            System.alloc(layout)
         }
-    }
-}
 
-fn update_projectiles_position(
-    time: Res<Time>,
-    mut query: Query<(&mut Transform, &Direction, &Velocity), With<Projectile>>,
-) {
-    for (mut transform, direction, velocity) in &mut query {
-        transform.translation += direction.0.normalize() * velocity.0 * time.delta_secs();
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
     }
-}
-
-fn check_projectile_collision(
-    mut commands: Commands,
-    mut query: Query<(Entity, &Transform, &Direction, &Velocity, &Target), With<Projectile>>,
-    mut enemies: Query<(&Transform, &mut Hp), With<Enemy>>,
-) {
-    for (projectile_entity, transform, _direction, _velocity, &Target(maybe_enemy_entity)) in
-        &mut query
-    {
-        let enemy_entity =
-            maybe_enemy_entity.expect("Projectiles are alawys expected to have a target?");
 
-        if let Ok((enemy_transform, mut enemy_hp)) = enemies.get_mut(enemy_entity) {
-            let bounding_circle = BoundingCircle::new(transform.translation.truncate(), 5.0 / 2.);
-            let bounding_box = Aabb2d::new(
-                enemy_transform.translation.truncate(),
-                enemy_transform.scale.truncate() / 2.,
-            );
+    #[global_allocator]
+    static GLOBAL: CountingAllocator = CountingAllocator;
 
-            if bounding_circle.intersects(&bounding_box) {
-                enemy_hp.0 -= 50;
-                commands.entity(projectile_entity).despawn();
-            }
-        } else {
-            commands.entity(projectile_entity).despawn();
-        }
+    fn allocations() -> usize {
+        ALLOCATIONS.with(Cell::get)
     }
-}
 
-fn despawn_dead_enemies(mut commands: Commands, query: Query<(Entity, &Hp), With<Enemy>>) {
-    for (entity, hp) in &query {
-        if hp.0 <= 0 {
-            commands.entity(entity).despawn();
+    #[test]
+    fn hot_systems_do_not_allocate_per_frame() {
+        let mut world = World::new();
+        world.insert_resource(Time::<()>::default());
+        world.insert_resource(Mutators::default());
+        world.init_resource::<Enrage>();
+        let tower = world
+            .spawn((Transform::default(), Tower, Range(1000.0), Target(None)))
+            .id();
+        for i in 0..50 {
+            let enemy = world
+                .spawn((
+                    Transform::from_xyz(i as f32 * 20.0, 300.0, 0.0),
+                    Enemy,
+                    Velocity(100.0),
+                    Direction(Vec3::NEG_Y),
+                    Hp(100),
+                    ProjectedHp(100),
+                    MaxHp(100),
+                    PathProgress::new(Vec3::new(i as f32 * 20.0, 300.0, 0.0)),
+                    Bounty(5),
+                ))
+                .id();
+            world.spawn((Transform::default(), HealthBar(enemy)));
         }
-    }
-}
 
-pub struct HelloPlugin;
-impl Plugin for HelloPlugin {
-    fn build(&self, app: &mut App) {
-        app.insert_resource(EnemySpawnTimer(Timer::from_seconds(
-            0.5,
-            TimerMode::Repeating,
-        )));
-        app.add_systems(Startup, setup_tower);
-        app.add_systems(
-            Update,
+        let mut schedule = Schedule::default();
+        schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+        schedule.add_systems(
             (
-                spawn_enemy,
                 update_enemy_position,
-                despawn_dead_enemies,
-                (tower_choose_target, tower_shoot_target).chain(),
-                (update_projectiles_position, check_projectile_collision).chain(),
-                (check_enemy_player_collision, despawn_collided_enemies).chain(),
-            ),
+                tower_choose_target,
+                update_health_bars,
+            )
+                .chain(),
         );
-    }
-}
+        for _ in 0..3 {
+            schedule.run(&mut world);
+        }
 
-fn main() {
-    App::new()
-        .add_plugins(DefaultPlugins)
-        .add_plugins(Wireframe2dPlugin)
-        .add_plugins(HelloPlugin)
-        .run();
+        let before = allocations();
+        for _ in 0..100 {
+            schedule.run(&mut world);
+        }
+        assert_eq!(allocations() - before, 0);
+        assert!(world
+            .get::<Target>(tower)
+            .is_some_and(|target| target.0.is_some()));
+    }
 }
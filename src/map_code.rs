@@ -0,0 +1,285 @@
+use crate::common::{FrameSet, LoadErrors};
+use crate::level::{play_level, Level};
+use crate::theme::{ThemedPanel, ThemedText};
+use crate::ui::spawn_toast;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::input::ButtonState;
+use bevy::prelude::*;
+use std::fs;
+
+const MAP_CODE_PREFIX: &str = "TD1.";
+const MAP_CODE_PATH: &str = "saves/map_code.txt";
+const MAX_MAP_CODE_LEN: usize = 4096;
+const MAX_WAYPOINTS: usize = 64;
+const MAX_STARTER_TOWERS: usize = 32;
+const IMPORT_DIALOG_PANEL: Color = Color::srgba(0.1, 0.1, 0.15, 0.95);
+const IMPORT_PREVIEW_CHARS: usize = 40;
+
+pub fn encode(level: &Level) -> String {
+    format!(
+        "{MAP_CODE_PREFIX}{}",
+        URL_SAFE_NO_PAD.encode(level.to_text())
+    )
+}
+
+pub fn decode(code: &str) -> Result<Level, String> {
+    let code = code.trim();
+    if code.len() > MAX_MAP_CODE_LEN {
+        return Err("map code is too long".to_string());
+    }
+    let payload = code.strip_prefix(MAP_CODE_PREFIX).ok_or("not a map code")?;
+    let bytes = URL_SAFE_NO_PAD
+        .decode(payload)
+        .map_err(|_| "map code is corrupted")?;
+    let text = String::from_utf8(bytes).map_err(|_| "map code is corrupted")?;
+    let mut rejected = None;
+    let level = Level::parse(&text, |line| {
+        rejected.get_or_insert_with(|| line.to_string());
+    });
+    if let Some(line) = rejected {
+        return Err(format!("unknown map line: {line}"));
+    }
+    if level.waves == 0 {
+        return Err("map has no waves".to_string());
+    }
+    if level.waypoints.len() > MAX_WAYPOINTS
+        || level.air_waypoints.len() > MAX_WAYPOINTS
+        || level.starter_towers.len() > MAX_STARTER_TOWERS
+    {
+        return Err("map is too large".to_string());
+    }
+    let bounds = level.camera_bounds;
+    let outside = |point: &Vec2| bounds.is_some_and(|bounds| !bounds.contains(*point));
+    if level
+        .waypoints
+        .iter()
+        .chain(&level.air_waypoints)
+        .chain(level.starter_towers.iter().map(|(position, _)| position))
+        .chain([&level.base])
+        .any(|point| !point.is_finite() || outside(point))
+    {
+        return Err("map has points outside its bounds".to_string());
+    }
+    Ok(level)
+}
+
+pub fn requested_level(errors: &mut LoadErrors) -> Option<Level> {
+    let args: Vec<String> = std::env::args().collect();
+    let pair = args.windows(2).find(|pair| pair[0] == "--map-code")?;
+    decode(&pair[1])
+        .inspect_err(|error| errors.report(format!("ignoring --map-code: {error}")))
+        .ok()
+}
+
+fn export_map_code(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    level: Option<Res<Level>>,
+) {
+    if !keys.just_pressed(KeyCode::F7) {
+        return;
+    }
+    let Some(level) = level else {
+        spawn_toast(&mut commands, "This run has no map to share");
+        return;
+    };
+    let code = encode(&level);
+    info!("map code: {code}");
+    let _ = fs::create_dir_all("saves");
+    match fs::write(MAP_CODE_PATH, &code) {
+        Ok(()) => {
+            spawn_toast(&mut commands, &format!("Map code saved to {MAP_CODE_PATH}"));
+        }
+        Err(error) => warn!("failed to write {MAP_CODE_PATH}: {error}"),
+    }
+}
+
+#[derive(Resource, Default)]
+struct MapImportDialog {
+    code: String,
+    error: Option<String>,
+}
+
+impl MapImportDialog {
+    fn text(&self) -> String {
+        let preview: String = if self.code.len() > IMPORT_PREVIEW_CHARS {
+            format!(
+                "...{}",
+                &self.code[self.code.len() - IMPORT_PREVIEW_CHARS..]
+            )
+        } else {
+            self.code.clone()
+        };
+        let hint = match &self.error {
+            Some(error) => error.clone(),
+            None if self.code.is_empty() => format!("Enter with no code reads {MAP_CODE_PATH}"),
+            None => "Enter to play, Esc to cancel".to_string(),
+        };
+        format!("Import map code\n{preview}_\n{hint}")
+    }
+}
+
+#[derive(Component)]
+struct MapImportText;
+
+fn open_import_dialog(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    dialog: Option<Res<MapImportDialog>>,
+) {
+    if dialog.is_some() || !keys.just_pressed(KeyCode::F8) {
+        return;
+    }
+    let dialog = MapImportDialog::default();
+    commands.spawn((
+        Text::new(dialog.text()),
+        TextLayout::new_with_justify(JustifyText::Center),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(40.0),
+            left: Val::Percent(25.0),
+            width: Val::Percent(50.0),
+            padding: UiRect::all(Val::Px(16.0)),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        BackgroundColor(IMPORT_DIALOG_PANEL),
+        ThemedPanel(IMPORT_DIALOG_PANEL),
+        ThemedText,
+        MapImportText,
+    ));
+    commands.insert_resource(dialog);
+}
+
+fn type_map_code(
+    mut commands: Commands,
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    mut input: EventReader<KeyboardInput>,
+    mut dialog: ResMut<MapImportDialog>,
+    mut text: Query<(Entity, &mut Text), With<MapImportText>>,
+) {
+    let mut submit = false;
+    let mut close = false;
+    let mut typed = false;
+    for event in input.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+        match &event.logical_key {
+            Key::Character(characters) => {
+                let characters = characters.chars().filter(char::is_ascii_graphic);
+                dialog.code.extend(characters);
+                typed = true;
+            }
+            Key::Backspace => {
+                dialog.code.pop();
+                typed = true;
+            }
+            Key::Enter => submit = true,
+            Key::Escape => close = true,
+            _ => {}
+        }
+    }
+    keys.reset_all();
+
+    if submit {
+        let code = if dialog.code.is_empty() {
+            fs::read_to_string(MAP_CODE_PATH).unwrap_or_default()
+        } else {
+            dialog.code.clone()
+        };
+        match decode(&code) {
+            Ok(level) => {
+                play_level(&mut commands, level);
+                spawn_toast(&mut commands, "Playing imported map");
+                close = true;
+            }
+            Err(error) => dialog.error = Some(error),
+        }
+    } else if typed {
+        dialog.error = None;
+    }
+    if close {
+        commands.remove_resource::<MapImportDialog>();
+        for (entity, _) in &text {
+            commands.entity(entity).despawn();
+        }
+        return;
+    }
+    for (_, mut text) in &mut text {
+        text.0 = dialog.text();
+    }
+}
+
+pub struct MapCodePlugin;
+
+impl Plugin for MapCodePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                type_map_code
+                    .run_if(resource_exists::<MapImportDialog>)
+                    .before(FrameSet::Input),
+                (export_map_code, open_import_dialog).in_set(FrameSet::Input),
+            ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tower::TowerKind;
+
+    #[test]
+    fn map_codes_round_trip() {
+        let mut level = Level {
+            waves: 25,
+            stars: [1000, 2500, 4000],
+            enrage_after: Some(240.0),
+            base: Vec2::new(-120.0, 40.0),
+            starter_towers: vec![
+                (Vec2::new(0.0, 60.0), TowerKind::Frost),
+                (Vec2::new(90.5, -30.0), TowerKind::Tesla),
+            ],
+            waypoints: vec![Vec2::new(-400.0, 0.0), Vec2::new(-200.0, 150.0)],
+            air_waypoints: vec![Vec2::new(300.0, 300.0)],
+            camera: Some(Vec2::new(10.0, -10.0)),
+            zoom: 1.5,
+            camera_bounds: Some(Rect::new(-800.0, -600.0, 800.0, 600.0)),
+            spawn_interval: 0.75,
+            wave_break: 3.0,
+            ..Level::default()
+        };
+        level.formula.lives = 80;
+        level.formula.par_time = 420.0;
+        level.formula.no_sell = 0;
+
+        let decoded = decode(&encode(&level)).expect("encoded level should decode");
+        assert!(decoded == level);
+        assert!(decode(&encode(&Level::default())).is_ok_and(|decoded| decoded == Level::default()));
+    }
+
+    #[test]
+    fn malformed_map_codes_are_rejected() {
+        for code in [
+            "",
+            "TD1.!!!not base64!!!",
+            "TD1.dmFsaWQ=",
+            "TD2.d2F2ZXMgMw",
+            "waves 3",
+        ] {
+            assert!(decode(code).is_err(), "{code:?} should not decode");
+        }
+        let not_utf8 = format!("{MAP_CODE_PREFIX}{}", URL_SAFE_NO_PAD.encode([0xff, 0xfe]));
+        assert!(decode(&not_utf8).is_err());
+        let unknown_line = format!(
+            "{MAP_CODE_PREFIX}{}",
+            URL_SAFE_NO_PAD.encode("waves 3\nlava 1")
+        );
+        assert!(decode(&unknown_line).is_err());
+    }
+}
@@ -0,0 +1,85 @@
+use crate::common::{Enemy, Shield};
+use crate::loading::Preload;
+use crate::tower::{shield_cap, ShieldGenerator};
+use bevy::audio::Volume;
+use bevy::prelude::*;
+use std::path::Path;
+
+const DANGER_CROWD: f32 = 30.0;
+const MUSIC_VOLUME: f32 = 0.5;
+const STEM_FADE_WIDTH: f32 = 0.25;
+const STEM_FADE_RATE: f32 = 0.5;
+const STEMS: [(&str, f32); 3] = [
+    ("music/base.ogg", 0.0),
+    ("music/percussion.ogg", 0.4),
+    ("music/lead.ogg", 0.75),
+];
+
+#[derive(Resource, Default)]
+pub struct Danger(pub f32);
+
+#[derive(Component)]
+struct MusicStem {
+    threshold: f32,
+}
+
+fn setup_music(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut preload: ResMut<Preload>,
+) {
+    for (path, threshold) in STEMS {
+        if !Path::new("assets").join(path).exists() {
+            continue;
+        }
+        let stem = asset_server.load(path);
+        preload.0.push(stem.clone().untyped());
+        commands.spawn((
+            AudioPlayer::<AudioSource>(stem),
+            PlaybackSettings::LOOP.with_volume(Volume::new(0.0)),
+            MusicStem { threshold },
+        ));
+    }
+}
+
+fn update_danger(
+    enemies: Query<(), With<Enemy>>,
+    generators: Query<(), With<ShieldGenerator>>,
+    shield: Res<Shield>,
+    mut danger: ResMut<Danger>,
+) {
+    let crowd = enemies.iter().count() as f32 / DANGER_CROWD;
+    let cap = shield_cap(generators.iter().count());
+    let exposure = if cap > 0 {
+        1.0 - shield.0 as f32 / cap as f32
+    } else {
+        0.0
+    };
+    danger.0 = crowd.max(exposure).clamp(0.0, 1.0);
+}
+
+fn fade_music_stems(
+    time: Res<Time<Real>>,
+    danger: Res<Danger>,
+    stems: Query<(&MusicStem, &AudioSink)>,
+) {
+    let step = STEM_FADE_RATE * time.delta_secs();
+    for (stem, sink) in &stems {
+        let mix = ((danger.0 - stem.threshold) / STEM_FADE_WIDTH + 1.0).clamp(0.0, 1.0);
+        let volume = sink.volume();
+        sink.set_volume(volume + (mix * MUSIC_VOLUME - volume).clamp(-step, step));
+    }
+}
+
+pub struct MusicPlugin;
+
+impl Plugin for MusicPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Danger>();
+        app.add_systems(Update, update_danger);
+        if app.world().contains_resource::<Assets<AudioSource>>() {
+            app.add_systems(Startup, setup_music);
+            app.add_systems(Update, fade_music_stems.after(update_danger));
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use crate::common::FrameSet;
+use bevy::audio::Pitch;
+use bevy::prelude::*;
+use std::time::Duration;
+
+const PING_LIFETIME: f32 = 3.0;
+const PING_RADIUS: f32 = 30.0;
+const PING_RINGS: usize = 3;
+const PING_PITCH: f32 = 880.0;
+const PING_TONE: Duration = Duration::from_millis(150);
+
+#[derive(Resource, Default)]
+pub struct Pings(Vec<(Vec2, f32)>);
+
+#[derive(Resource)]
+struct PingTone(Option<Handle<Pitch>>);
+
+impl FromWorld for PingTone {
+    fn from_world(world: &mut World) -> Self {
+        PingTone(
+            world
+                .get_resource_mut::<Assets<Pitch>>()
+                .map(|mut pitches| pitches.add(Pitch::new(PING_PITCH, PING_TONE))),
+        )
+    }
+}
+
+fn place_ping(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut mouse: ResMut<ButtonInput<MouseButton>>,
+    time: Res<Time<Real>>,
+    window: Single<&Window>,
+    camera: Single<(&Camera, &GlobalTransform)>,
+    tone: Res<PingTone>,
+    mut pings: ResMut<Pings>,
+) {
+    if !keys.any_pressed([KeyCode::AltLeft, KeyCode::AltRight])
+        || !mouse.just_pressed(MouseButton::Left)
+    {
+        return;
+    }
+    mouse.clear_just_pressed(MouseButton::Left);
+    let (camera, camera_transform) = camera.into_inner();
+    let Some(position) = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor).ok())
+    else {
+        return;
+    };
+
+    pings.0.push((position, time.elapsed_secs()));
+    if let Some(tone) = &tone.0 {
+        commands.spawn((AudioPlayer(tone.clone()), PlaybackSettings::DESPAWN));
+    }
+}
+
+fn expire_pings(time: Res<Time<Real>>, mut pings: ResMut<Pings>) {
+    let now = time.elapsed_secs();
+    if pings.0.iter().any(|&(_, at)| now - at > PING_LIFETIME) {
+        pings.0.retain(|&(_, at)| now - at <= PING_LIFETIME);
+    }
+}
+
+fn draw_pings(mut gizmos: Gizmos, time: Res<Time<Real>>, pings: Res<Pings>) {
+    let now = time.elapsed_secs();
+    for &(position, at) in &pings.0 {
+        let age = now - at;
+        let fade = 1.0 - age / PING_LIFETIME;
+        for ring in 0..PING_RINGS {
+            let phase = (age + ring as f32 / PING_RINGS as f32).fract();
+            gizmos.circle_2d(
+                position,
+                PING_RADIUS * phase,
+                Color::hsla(50., 1.0, 0.6, fade * (1.0 - phase)),
+            );
+        }
+        gizmos.circle_2d(position, 3.0, Color::hsla(50., 1.0, 0.6, fade));
+    }
+}
+
+pub struct PingPlugin;
+
+impl Plugin for PingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Pings>();
+        app.init_resource::<PingTone>();
+        app.add_systems(
+            Update,
+            (
+                place_ping.before(FrameSet::Input),
+                (expire_pings, draw_pings).chain(),
+            ),
+        );
+    }
+}
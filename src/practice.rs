@@ -0,0 +1,275 @@
+use crate::combat::EnemyReachedBase;
+use crate::common::{Enemy, FrameSet, GameRng};
+use crate::enemy::{describe_wave, EnemyKind};
+use crate::projectile::Projectile;
+use crate::save::{RunRestore, RunSnapshot, RunState};
+use crate::theme::{ThemedPanel, ThemedText};
+use crate::ui::spawn_toast;
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+
+const PRACTICE_LIVES: u32 = 999;
+const PICKER_PANEL: Color = Color::srgba(0.1, 0.1, 0.15, 0.95);
+
+#[derive(Resource)]
+pub struct Practice {
+    snapshot: RunSnapshot,
+    history: Vec<(u32, Vec<EnemyKind>)>,
+    rng: StdRng,
+    wave: u32,
+    leaked: usize,
+}
+
+impl Practice {
+    fn begin(state: &RunState, rng: &GameRng, wave: u32) -> Self {
+        Practice {
+            snapshot: RunSnapshot::capture(state),
+            history: state.waves.history.clone(),
+            rng: rng.0.clone(),
+            wave,
+            leaked: 0,
+        }
+    }
+
+    fn end(&self, restore: &mut RunRestore, rng: &mut GameRng) {
+        self.snapshot.restore(restore);
+        restore.waves.history.clone_from(&self.history);
+        rng.0 = self.rng.clone();
+    }
+}
+
+#[derive(Resource)]
+struct PracticePicker {
+    index: usize,
+}
+
+#[derive(Component)]
+struct PracticePickerText;
+
+fn picker_text(history: &[(u32, Vec<EnemyKind>)], index: usize) -> String {
+    let (wave, composition) = &history[index];
+    format!(
+        "Practice wave {wave}\n{}\nLeft/Right to choose, Enter to start, Esc to cancel",
+        describe_wave(composition)
+    )
+}
+
+fn open_practice_picker(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    state: RunState,
+    picker: Option<Res<PracticePicker>>,
+    practice: Option<Res<Practice>>,
+) {
+    if !keys.just_pressed(KeyCode::F11) || picker.is_some() || practice.is_some() {
+        return;
+    }
+    let history = &state.waves.history;
+    if history.is_empty() {
+        spawn_toast(&mut commands, "No waves to practice yet");
+        return;
+    }
+    let index = history.len() - 1;
+    commands.spawn((
+        Text::new(picker_text(history, index)),
+        TextLayout::new_with_justify(JustifyText::Center),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(40.0),
+            left: Val::Percent(30.0),
+            width: Val::Percent(40.0),
+            padding: UiRect::all(Val::Px(16.0)),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        BackgroundColor(PICKER_PANEL),
+        ThemedPanel(PICKER_PANEL),
+        ThemedText,
+        PracticePickerText,
+    ));
+    commands.insert_resource(PracticePicker { index });
+}
+
+fn navigate_practice_picker(
+    mut commands: Commands,
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    mut picker: ResMut<PracticePicker>,
+    mut text: Query<(Entity, &mut Text), With<PracticePickerText>>,
+    doomed: Query<Entity, Or<(With<Enemy>, With<Projectile>)>>,
+    rng: Res<GameRng>,
+    mut run: ParamSet<(RunState, RunRestore)>,
+) {
+    let last = run.p0().waves.history.len().saturating_sub(1);
+    let start = keys.just_pressed(KeyCode::Enter);
+    let close = start || keys.just_pressed(KeyCode::Escape);
+    if keys.just_pressed(KeyCode::ArrowLeft) {
+        picker.index = picker.index.saturating_sub(1);
+    }
+    if keys.just_pressed(KeyCode::ArrowRight) {
+        picker.index = (picker.index + 1).min(last);
+    }
+    keys.reset_all();
+
+    if start {
+        let (wave, composition) = run.p0().waves.history[picker.index].clone();
+        let practice = Practice::begin(&run.p0(), &rng, wave);
+        let mut restore = run.p1();
+        for entity in &doomed {
+            restore.commands.entity(entity).despawn();
+        }
+        restore.waves.number = wave;
+        restore.waves.pending = composition.into();
+        restore.waves.upcoming = None;
+        restore.waves.break_timer.reset();
+        restore.gold.0 = 0;
+        restore.lives.0 = PRACTICE_LIVES;
+        commands.insert_resource(practice);
+        spawn_toast(
+            &mut commands,
+            &format!("Practicing wave {wave}, F11 to stop"),
+        );
+    }
+    if close {
+        commands.remove_resource::<PracticePicker>();
+        for (entity, _) in &text {
+            commands.entity(entity).despawn();
+        }
+        return;
+    }
+    let history = &run.p0().waves.history;
+    for (_, mut text) in &mut text {
+        text.0 = picker_text(history, picker.index);
+    }
+}
+
+fn finish_practice(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut reached: EventReader<EnemyReachedBase>,
+    mut practice: ResMut<Practice>,
+    enemies: Query<(), With<Enemy>>,
+    mut rng: ResMut<GameRng>,
+    mut restore: RunRestore,
+) {
+    practice.leaked += reached.read().count();
+    let cleared = restore.waves.pending.is_empty() && enemies.is_empty();
+    if !cleared && !keys.just_pressed(KeyCode::F11) {
+        return;
+    }
+    practice.end(&mut restore, &mut rng);
+    restore.commands.remove_resource::<Practice>();
+    let summary = if cleared {
+        format!(
+            "Practice wave {} cleared, {} leaked",
+            practice.wave, practice.leaked
+        )
+    } else {
+        format!("Practice wave {} stopped", practice.wave)
+    };
+    spawn_toast(&mut restore.commands, &summary);
+}
+
+pub struct PracticePlugin;
+
+impl Plugin for PracticePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                navigate_practice_picker
+                    .run_if(resource_exists::<PracticePicker>)
+                    .before(FrameSet::Input),
+                (
+                    open_practice_picker,
+                    finish_practice.run_if(resource_exists::<Practice>),
+                )
+                    .in_set(FrameSet::Input),
+            ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{GameAssets, Gold, Hp, Kills, Lives, Mutators, RunClock, Score, Shield};
+    use crate::enemy::Waves;
+    use crate::run_end::LastStand;
+    use crate::scouting::ScoutRerolls;
+    use crate::tower::{spawn_tower_at, BaseUpgrades, TowerKind, TowersSold, UltimateCharge};
+    use bevy::ecs::system::RunSystemOnce;
+    use rand::prelude::*;
+
+    fn capture(world: &mut World) -> String {
+        world
+            .run_system_once(|state: RunState| RunSnapshot::capture(&state).serialize())
+            .expect("capture should run")
+    }
+
+    #[test]
+    fn practice_round_trip_leaves_the_run_unchanged() {
+        let mut world = World::new();
+        world.init_resource::<Assets<Mesh>>();
+        world.init_resource::<Assets<ColorMaterial>>();
+        world.init_resource::<GameAssets>();
+        world.insert_resource(Gold(90));
+        world.insert_resource(Lives(14));
+        world.init_resource::<Shield>();
+        world.init_resource::<BaseUpgrades>();
+        world.init_resource::<RunClock>();
+        world.init_resource::<Mutators>();
+        world.insert_resource(Score(300));
+        world.init_resource::<TowersSold>();
+        world.init_resource::<LastStand>();
+        world.insert_resource(ScoutRerolls(2));
+        world.insert_resource(GameRng(StdRng::seed_from_u64(7)));
+        let mut waves = Waves::for_run();
+        waves.number = 4;
+        waves.upcoming = Some(vec![EnemyKind::Tank, EnemyKind::Runner]);
+        waves.history = vec![(3, vec![EnemyKind::Grunt]), (4, vec![EnemyKind::Flyer])];
+        world.insert_resource(waves);
+        world
+            .run_system_once(|mut commands: Commands, assets: Res<GameAssets>| {
+                let tower = spawn_tower_at(&mut commands, &assets, Vec2::ZERO, TowerKind::Sniper);
+                commands
+                    .entity(tower)
+                    .insert((Kills(60), UltimateCharge(700), Hp(120)));
+            })
+            .expect("tower should spawn");
+
+        let before = capture(&mut world);
+        let expected_roll = world.resource::<GameRng>().0.clone().gen::<u64>();
+        let practice = world
+            .run_system_once(|state: RunState, rng: Res<GameRng>| Practice::begin(&state, &rng, 3))
+            .expect("practice should begin");
+
+        world.resource_mut::<Gold>().0 = 0;
+        world.resource_mut::<Score>().0 += 45;
+        world.resource_mut::<ScoutRerolls>().0 = 0;
+        world.resource_mut::<GameRng>().0.gen::<u64>();
+        let mut waves = world.resource_mut::<Waves>();
+        waves.number = 3;
+        waves.upcoming = None;
+        waves.history.retain(|&(wave, _)| wave < 3);
+        for (mut kills, mut charge) in world
+            .query::<(&mut Kills, &mut UltimateCharge)>()
+            .iter_mut(&mut world)
+        {
+            kills.0 += 5;
+            charge.0 = 0;
+        }
+
+        world
+            .run_system_once(move |mut restore: RunRestore, mut rng: ResMut<GameRng>| {
+                practice.end(&mut restore, &mut rng)
+            })
+            .expect("practice should end");
+
+        assert_eq!(capture(&mut world), before);
+        let waves = world.resource::<Waves>();
+        assert!(waves.history == [(3, vec![EnemyKind::Grunt]), (4, vec![EnemyKind::Flyer])]);
+        assert_eq!(
+            world.resource_mut::<GameRng>().0.gen::<u64>(),
+            expected_roll
+        );
+    }
+}
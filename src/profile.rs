@@ -0,0 +1,110 @@
+use crate::cosmetics::{Achievement, Cosmetics, TowerSkin};
+use crate::loadout::Loadout;
+use crate::tower::{Commander, TowerKind};
+use bevy::prelude::*;
+use std::collections::BTreeMap;
+use std::fs;
+
+const PROFILE_PATH: &str = "saves/profile.txt";
+
+#[derive(Default)]
+pub struct Profile {
+    pub commander: Commander,
+    pub daily_results: Vec<(u64, u32)>,
+    pub weekly_results: Vec<(u64, u32)>,
+    pub loadout: Loadout,
+    pub cosmetics: Cosmetics,
+    pub best_times: BTreeMap<String, f32>,
+}
+
+impl Profile {
+    pub fn load() -> Self {
+        let mut profile = Profile::default();
+        for line in fs::read_to_string(PROFILE_PATH).unwrap_or_default().lines() {
+            let mut fields = line.split_whitespace();
+            match fields.next() {
+                Some("commander") => {
+                    if let Some(commander) = fields.next().and_then(Commander::from_name) {
+                        profile.commander = commander;
+                    }
+                }
+                Some("daily") => {
+                    let day = fields.next().and_then(|field| field.parse().ok());
+                    let score = fields.next().and_then(|field| field.parse().ok());
+                    if let (Some(day), Some(score)) = (day, score) {
+                        profile.daily_results.push((day, score));
+                    }
+                }
+                Some("loadout") => profile.loadout = Loadout::from_names(fields),
+                Some("achievement") => {
+                    if let Some(achievement) = fields.next().and_then(Achievement::from_name) {
+                        profile.cosmetics.achievements.push(achievement);
+                    }
+                }
+                Some("skin") => {
+                    let kind = fields.next().and_then(TowerKind::from_name);
+                    let skin = fields.next().and_then(TowerSkin::from_name);
+                    if let (Some(kind), Some(skin)) = (kind, skin) {
+                        profile.cosmetics.skins.push((kind, skin));
+                    }
+                }
+                Some("weekly") => {
+                    let week = fields.next().and_then(|field| field.parse().ok());
+                    let score = fields.next().and_then(|field| field.parse().ok());
+                    if let (Some(week), Some(score)) = (week, score) {
+                        profile.weekly_results.push((week, score));
+                    }
+                }
+                Some("best_time") => {
+                    let level = fields.next();
+                    let seconds = fields.next().and_then(|field| field.parse().ok());
+                    if let (Some(level), Some(seconds)) = (level, seconds) {
+                        profile.best_times.insert(level.to_string(), seconds);
+                    }
+                }
+                _ => {}
+            }
+        }
+        profile
+    }
+
+    pub fn weekly_best(&self, week: u64) -> Option<u32> {
+        self.weekly_results
+            .iter()
+            .find(|&&(played, _)| played == week)
+            .map(|&(_, score)| score)
+    }
+
+    pub fn record_time(&mut self, level: String, seconds: f32) -> bool {
+        let best = self.best_times.entry(level).or_insert(f32::INFINITY);
+        let improved = seconds < *best;
+        *best = best.min(seconds);
+        improved
+    }
+
+    pub fn save(&self) {
+        let mut contents = format!("commander {}\n", self.commander.name());
+        for (day, score) in &self.daily_results {
+            contents.push_str(&format!("daily {day} {score}\n"));
+        }
+        for (week, score) in &self.weekly_results {
+            contents.push_str(&format!("weekly {week} {score}\n"));
+        }
+        if self.loadout != Loadout::default() {
+            contents.push_str(&format!("loadout {}\n", self.loadout.names()));
+        }
+        for achievement in &self.cosmetics.achievements {
+            contents.push_str(&format!("achievement {}\n", achievement.name()));
+        }
+        for (kind, skin) in &self.cosmetics.skins {
+            contents.push_str(&format!("skin {} {}\n", kind.name(), skin.name()));
+        }
+        for (level, seconds) in &self.best_times {
+            contents.push_str(&format!("best_time {level} {seconds}\n"));
+        }
+        let _ = fs::create_dir_all("saves");
+        if let Err(err) = fs::write(PROFILE_PATH, contents) {
+            warn!("failed to write profile {PROFILE_PATH}: {err}");
+        }
+    }
+}
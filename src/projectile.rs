@@ -0,0 +1,289 @@
+use crate::combat::release_projected_hp;
+use crate::common::{Damage, Enemy, GameAssets, Hp, ProjectedHp, SimulationSet, Target};
+use crate::enemy::{Corpse, Direction, Velocity};
+use bevy::prelude::*;
+
+#[derive(Component)]
+pub struct FiredBy(pub Entity);
+
+#[derive(Component)]
+pub struct Projectile;
+
+#[derive(Component)]
+pub struct Lobbed {
+    pub origin: Vec2,
+    pub landing: Vec2,
+    pub ground: Vec2,
+    pub flight: f32,
+}
+
+#[derive(Component)]
+pub struct Homing {
+    pub turn_rate: f32,
+}
+
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub enum ProjectileKind {
+    Shell,
+    Bolt,
+    Acid,
+    Frost,
+    Bomb,
+    Slug,
+    Missile,
+    Lightning,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum DamageType {
+    Kinetic,
+    Explosive,
+    Elemental,
+}
+
+impl DamageType {
+    pub fn name(self) -> &'static str {
+        match self {
+            DamageType::Kinetic => "kinetic",
+            DamageType::Explosive => "explosive",
+            DamageType::Elemental => "elemental",
+        }
+    }
+}
+
+impl ProjectileKind {
+    pub fn speed(self) -> f32 {
+        match self {
+            ProjectileKind::Shell => 100.0,
+            ProjectileKind::Bolt => 300.0,
+            ProjectileKind::Acid => 120.0,
+            ProjectileKind::Frost => 140.0,
+            ProjectileKind::Bomb => 180.0,
+            ProjectileKind::Slug | ProjectileKind::Lightning => 1000.0,
+            ProjectileKind::Missile => 200.0,
+        }
+    }
+
+    pub fn material(self, assets: &GameAssets) -> Handle<ColorMaterial> {
+        match self {
+            ProjectileKind::Shell
+            | ProjectileKind::Bolt
+            | ProjectileKind::Bomb
+            | ProjectileKind::Slug
+            | ProjectileKind::Missile
+            | ProjectileKind::Lightning => assets.projectile_material.clone(),
+            ProjectileKind::Acid => assets.acid_projectile_material.clone(),
+            ProjectileKind::Frost => assets.frost_projectile_material.clone(),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ProjectileKind::Shell => "shell",
+            ProjectileKind::Bolt => "bolt",
+            ProjectileKind::Acid => "acid",
+            ProjectileKind::Frost => "frost",
+            ProjectileKind::Bomb => "bomb",
+            ProjectileKind::Slug => "slug",
+            ProjectileKind::Missile => "missile",
+            ProjectileKind::Lightning => "lightning",
+        }
+    }
+
+    pub fn damage_type(self) -> DamageType {
+        match self {
+            ProjectileKind::Shell | ProjectileKind::Bolt | ProjectileKind::Slug => {
+                DamageType::Kinetic
+            }
+            ProjectileKind::Bomb | ProjectileKind::Missile => DamageType::Explosive,
+            ProjectileKind::Acid | ProjectileKind::Frost | ProjectileKind::Lightning => {
+                DamageType::Elemental
+            }
+        }
+    }
+
+    pub fn is_hitscan(self) -> bool {
+        matches!(self, ProjectileKind::Slug | ProjectileKind::Lightning)
+    }
+
+    pub fn splash_radius(self) -> Option<f32> {
+        (self == ProjectileKind::Bomb).then_some(SPLASH_RADIUS)
+    }
+
+    pub fn hits_first_enemy(self) -> bool {
+        self == ProjectileKind::Bolt
+    }
+
+    pub fn chain(self) -> Option<(usize, f32)> {
+        (self == ProjectileKind::Lightning).then_some((CHAIN_JUMPS, CHAIN_RADIUS))
+    }
+
+    pub fn flight_time(self) -> Option<f32> {
+        (self == ProjectileKind::Bomb).then_some(MORTAR_FLIGHT_SECONDS)
+    }
+
+    pub fn turn_rate(self) -> Option<f32> {
+        (self == ProjectileKind::Missile).then_some(MISSILE_TURN_RATE)
+    }
+}
+
+pub const PROJECTILE_DAMAGE: u32 = 50;
+pub const SPLASH_RADIUS: f32 = 50.0;
+pub const MISSILE_TURN_RATE: f32 = 4.0;
+pub const MORTAR_FLIGHT_SECONDS: f32 = 1.2;
+const LOB_HEIGHT: f32 = 60.0;
+const LOB_SCALE: f32 = 0.6;
+pub const CHAIN_JUMPS: usize = 3;
+pub const CHAIN_RADIUS: f32 = 90.0;
+pub const PROJECTILE_RADIUS: f32 = 2.5;
+pub const PROJECTILE_LIFETIME: f32 = 4.0;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    DespawnOldest,
+    SkipSpawn,
+}
+
+#[derive(Resource)]
+pub struct EntityCaps {
+    pub projectiles: usize,
+    pub corpses: usize,
+    pub policy: OverflowPolicy,
+}
+
+impl EntityCaps {
+    pub fn from_args() -> Self {
+        let mut caps = EntityCaps {
+            projectiles: 2000,
+            corpses: 500,
+            policy: OverflowPolicy::DespawnOldest,
+        };
+        let args: Vec<String> = std::env::args().collect();
+        for pair in args.windows(2) {
+            match (pair[0].as_str(), pair[1].parse()) {
+                ("--projectile-cap", Ok(cap)) => caps.projectiles = cap,
+                ("--corpse-cap", Ok(cap)) => caps.corpses = cap,
+                _ => {}
+            }
+        }
+        if args.iter().any(|arg| arg == "--skip-spawn-on-cap") {
+            caps.policy = OverflowPolicy::SkipSpawn;
+        }
+        caps
+    }
+
+    pub fn allows_spawn(&self, count: usize, cap: usize) -> bool {
+        self.policy == OverflowPolicy::DespawnOldest || count < cap
+    }
+}
+
+#[derive(Component)]
+pub struct SpawnedAt(pub f32);
+
+fn enforce_entity_caps(
+    mut commands: Commands,
+    caps: Res<EntityCaps>,
+    projectiles: Query<(Entity, &SpawnedAt, &Target, &Damage), With<Projectile>>,
+    corpses: Query<(Entity, &SpawnedAt), With<Corpse>>,
+    mut enemies: Query<(&Transform, &mut Hp, &mut ProjectedHp), With<Enemy>>,
+) {
+    let excess = projectiles.iter().count().saturating_sub(caps.projectiles);
+    if excess > 0 {
+        let mut oldest: Vec<_> = projectiles.iter().collect();
+        oldest.sort_by(|(_, a, _, _), (_, b, _, _)| a.0.total_cmp(&b.0));
+        for (projectile, _, target, &damage) in oldest.into_iter().take(excess) {
+            release_projected_hp(&mut enemies, target.0, damage);
+            commands.entity(projectile).despawn();
+        }
+    }
+
+    let excess = corpses.iter().count().saturating_sub(caps.corpses);
+    if excess > 0 {
+        let mut oldest: Vec<_> = corpses.iter().collect();
+        oldest.sort_by(|(_, a), (_, b)| a.0.total_cmp(&b.0));
+        for (corpse, _) in oldest.into_iter().take(excess) {
+            commands.entity(corpse).despawn();
+        }
+    }
+}
+
+pub fn spawn_projectile(
+    commands: &mut Commands,
+    assets: &GameAssets,
+    spawned_at: f32,
+    kind: ProjectileKind,
+    damage: Damage,
+    tower: Entity,
+    from: Vec3,
+    enemy: Entity,
+    enemy_translation: Vec3,
+) {
+    let speed = match kind.flight_time() {
+        Some(flight) => from.distance(enemy_translation) / flight,
+        None => kind.speed(),
+    };
+    let mut projectile = commands.spawn((
+        Mesh2d(assets.projectile_mesh.clone()),
+        MeshMaterial2d(kind.material(assets)),
+        Transform::from_translation(from),
+        Velocity(speed),
+        Projectile,
+        kind,
+        damage,
+        SpawnedAt(spawned_at),
+        Target(Some(enemy)),
+        FiredBy(tower),
+        Direction(enemy_translation - from),
+    ));
+    if let Some(turn_rate) = kind.turn_rate() {
+        projectile.insert(Homing { turn_rate });
+    }
+    if let Some(flight) = kind.flight_time() {
+        projectile.insert(Lobbed {
+            origin: from.truncate(),
+            landing: enemy_translation.truncate(),
+            ground: from.truncate(),
+            flight,
+        });
+    }
+}
+
+pub fn lift_lobbed_projectiles(
+    time: Res<Time>,
+    mut projectiles: Query<(&mut Transform, &mut Lobbed, &SpawnedAt), With<Projectile>>,
+) {
+    for (mut transform, mut lobbed, spawned_at) in &mut projectiles {
+        let progress = ((time.elapsed_secs() - spawned_at.0) / lobbed.flight).clamp(0.0, 1.0);
+        let height = (progress * std::f32::consts::PI).sin();
+        lobbed.ground = lobbed.origin.lerp(lobbed.landing, progress);
+        let position = lobbed.ground + Vec2::Y * LOB_HEIGHT * height;
+        transform.translation = position.extend(transform.translation.z);
+        transform.scale = Vec3::splat(1.0 + LOB_SCALE * height);
+    }
+}
+
+pub fn update_projectiles_position(
+    time: Res<Time>,
+    mut query: Query<(&mut Transform, &Direction, &Velocity), (With<Projectile>, Without<Lobbed>)>,
+) {
+    for (mut transform, direction, velocity) in &mut query {
+        transform.translation += direction.0.normalize() * velocity.0 * time.delta_secs();
+    }
+}
+
+pub struct ProjectilePlugin;
+
+impl Plugin for ProjectilePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(EntityCaps::from_args());
+        app.add_systems(
+            FixedUpdate,
+            (
+                (update_projectiles_position, lift_lobbed_projectiles)
+                    .chain()
+                    .in_set(SimulationSet::Step),
+                enforce_entity_caps.in_set(SimulationSet::Limit),
+            ),
+        );
+    }
+}
@@ -0,0 +1,72 @@
+use crate::common::{in_run, SimulationSet};
+use crate::practice::Practice;
+use crate::save::{RunRestore, RunSnapshot, RunState};
+use bevy::prelude::*;
+use std::collections::VecDeque;
+
+const REWIND_SECONDS: f32 = 5.0;
+const REWIND_SNAPSHOT_INTERVAL: f32 = 0.5;
+const REWIND_CHARGES: u32 = 3;
+const REWIND_CAPACITY: usize = (REWIND_SECONDS / REWIND_SNAPSHOT_INTERVAL) as usize;
+
+#[derive(Resource)]
+pub struct RewindBuffer {
+    pub snapshots: VecDeque<RunSnapshot>,
+    pub timer: Timer,
+    pub charges: u32,
+}
+
+impl Default for RewindBuffer {
+    fn default() -> Self {
+        RewindBuffer {
+            snapshots: VecDeque::with_capacity(REWIND_CAPACITY),
+            timer: Timer::from_seconds(REWIND_SNAPSHOT_INTERVAL, TimerMode::Repeating),
+            charges: REWIND_CHARGES,
+        }
+    }
+}
+
+fn record_rewind_snapshot(time: Res<Time>, mut rewind: ResMut<RewindBuffer>, state: RunState) {
+    if rewind.timer.tick(time.delta()).just_finished() {
+        let mut snapshot = if rewind.snapshots.len() == REWIND_CAPACITY {
+            rewind.snapshots.pop_front().unwrap_or_default()
+        } else {
+            RunSnapshot::default()
+        };
+        snapshot.capture_into(&state);
+        rewind.snapshots.push_back(snapshot);
+    }
+}
+
+fn rewind(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut rewind: ResMut<RewindBuffer>,
+    mut restore: RunRestore,
+) {
+    if !keys.just_pressed(KeyCode::Backspace) || rewind.charges == 0 {
+        return;
+    }
+    if let Some(snapshot) = rewind.snapshots.pop_front() {
+        snapshot.restore(&mut restore);
+        rewind.snapshots.clear();
+        rewind.timer.reset();
+        rewind.charges -= 1;
+    }
+}
+
+pub struct RewindPlugin;
+impl Plugin for RewindPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RewindBuffer>();
+        app.add_systems(
+            FixedUpdate,
+            record_rewind_snapshot
+                .run_if(not(resource_exists::<Practice>))
+                .in_set(SimulationSet::Record),
+        );
+        app.add_systems(
+            Update,
+            rewind.run_if(in_run.and(not(resource_exists::<Practice>))),
+        );
+    }
+}
@@ -0,0 +1,151 @@
+use crate::common::{Enemy, GameState, Gold, Lives, RunClock, Score, SimulationSet};
+use crate::enemy::{despawn_out_of_bounds, Waves};
+use crate::level::Level;
+use crate::map_code;
+use crate::practice::Practice;
+use crate::profile::Profile;
+use crate::tower::TowersSold;
+use crate::ui::{format_run_time, spawn_toast};
+use bevy::prelude::*;
+
+pub const LAST_STAND_SECONDS: f32 = 10.0;
+const LAST_STAND_FIRE_RATE: f32 = 2.0;
+
+#[derive(Resource, Default)]
+pub struct LastStand {
+    pub timer: Option<Timer>,
+    pub used: bool,
+    pub breached: bool,
+}
+
+impl LastStand {
+    pub fn fire_rate_multiplier(&self) -> f32 {
+        if self.timer.is_some() {
+            LAST_STAND_FIRE_RATE
+        } else {
+            1.0
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct RunEndText;
+
+fn tick_run_clock(time: Res<Time>, mut clock: ResMut<RunClock>) {
+    clock.0 += time.delta_secs();
+}
+
+fn update_last_stand(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut lives: ResMut<Lives>,
+    mut last_stand: ResMut<LastStand>,
+    score: Res<Score>,
+    enemies: Query<(), With<Enemy>>,
+) {
+    let LastStand {
+        timer,
+        used,
+        breached,
+    } = &mut *last_stand;
+    let lost = match timer.as_mut() {
+        None if lives.0 > 0 => false,
+        None if !*used => {
+            *used = true;
+            *timer = Some(Timer::from_seconds(LAST_STAND_SECONDS, TimerMode::Once));
+            spawn_toast(&mut commands, "Last stand! Clear the field");
+            false
+        }
+        None => true,
+        Some(last_stand_timer) => {
+            if *breached || last_stand_timer.tick(time.delta()).finished() {
+                true
+            } else {
+                if enemies.is_empty() {
+                    *timer = None;
+                    lives.0 = 1;
+                    spawn_toast(&mut commands, "Last stand survived");
+                }
+                false
+            }
+        }
+    };
+    if lost {
+        *timer = None;
+        end_run(&mut commands, format!("Game over\nScore: {}", score.0));
+    }
+}
+
+fn end_run(commands: &mut Commands, message: String) {
+    commands.set_state(GameState::GameOver);
+    commands.spawn((
+        RunEndText,
+        Text::new(message),
+        TextLayout::new_with_justify(JustifyText::Center),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(40.0),
+            width: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+    ));
+}
+
+fn check_victory(
+    mut commands: Commands,
+    level: Option<Res<Level>>,
+    waves: Res<Waves>,
+    lives: Res<Lives>,
+    gold: Res<Gold>,
+    clock: Res<RunClock>,
+    towers_sold: Res<TowersSold>,
+    enemies: Query<(), With<Enemy>>,
+) {
+    let Some(level) = level else {
+        return;
+    };
+    if waves.number < level.waves || !waves.pending.is_empty() || !enemies.is_empty() {
+        return;
+    }
+    let score = level.formula.score(lives.0, gold.0, clock.0, towers_sold.0);
+    let stars = level.stars(score);
+    let level_code = map_code::encode(&level);
+    let mut profile = Profile::load();
+    let new_best = profile.record_time(level_code.clone(), clock.0);
+    if new_best {
+        profile.save();
+    }
+    end_run(
+        &mut commands,
+        format!(
+            "Victory!\nScore: {score}\n{}{}\nTime: {}{}\nBest: {}",
+            "*".repeat(stars),
+            "-".repeat(3 - stars),
+            format_run_time(clock.0),
+            if new_best { "  New best!" } else { "" },
+            format_run_time(profile.best_times[&level_code])
+        ),
+    );
+}
+
+pub struct RunEndPlugin;
+impl Plugin for RunEndPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LastStand>();
+        app.init_resource::<RunClock>();
+        app.add_systems(
+            FixedUpdate,
+            (
+                tick_run_clock,
+                (
+                    update_last_stand,
+                    check_victory.run_if(not(resource_exists::<Practice>)),
+                )
+                    .chain()
+                    .after(despawn_out_of_bounds),
+            )
+                .in_set(SimulationSet::Step),
+        );
+    }
+}
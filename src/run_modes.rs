@@ -0,0 +1,498 @@
+use crate::common::{
+    Base, FrameSet, GameAssets, GameRng, GameState, Gold, LoadErrors, Mutators, Score, Tower,
+};
+use crate::enemy::{Path, Waves};
+use crate::headless::soak_waves;
+use crate::level::{play_level, Level, LEVEL_PATH};
+use crate::profile::Profile;
+use crate::rewind::RewindBuffer;
+use crate::run_end::RunEndText;
+use crate::save::{fresh_run, RunRestore};
+use crate::theme::{ThemedPanel, ThemedText};
+use crate::tower::{spawn_starting_towers, spawn_tower_at, Commander, Occupancy, TowerKind};
+use crate::ui::{clamp_camera, spawn_toast, CameraFraming, KillCam};
+use bevy::prelude::*;
+use rand::prelude::*;
+use std::fs;
+use std::time::SystemTime;
+
+#[derive(Resource)]
+pub struct DemoMode;
+
+pub fn daily_requested() -> bool {
+    std::env::args().any(|arg| arg == "--daily")
+}
+
+pub fn weekly_requested() -> bool {
+    !daily_requested() && std::env::args().any(|arg| arg == "--weekly")
+}
+
+pub fn custom_requested() -> bool {
+    !daily_requested() && !weekly_requested() && std::env::args().any(|arg| arg == "--custom")
+}
+
+pub fn endless_requested() -> bool {
+    std::env::args().any(|arg| arg == "--endless")
+}
+
+pub fn demo_requested() -> bool {
+    std::env::args().any(|arg| arg == "--demo") || soak_waves().is_some()
+}
+
+const MENU_PANEL: Color = Color::srgba(0.0, 0.0, 0.0, 0.8);
+
+#[derive(Resource)]
+struct DailyChallenge {
+    day: u64,
+}
+
+#[derive(Component)]
+struct DailyChallengeText;
+
+fn today() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |since_epoch| since_epoch.as_secs() / 86_400)
+}
+
+fn record_daily_result(day: u64, score: u32) {
+    let mut profile = Profile::load();
+    profile
+        .daily_results
+        .retain(|&(recorded_day, _)| recorded_day != day);
+    profile.daily_results.push((day, score));
+    profile.save();
+}
+
+fn daily_share_code(day: u64, score: u32) -> String {
+    format!("D{day:X}-{score:X}")
+}
+
+fn start_daily_challenge(mut commands: Commands) {
+    if !daily_requested() {
+        return;
+    }
+    let day = today();
+    if Profile::load()
+        .daily_results
+        .iter()
+        .any(|&(played, _)| played == day)
+    {
+        spawn_toast(&mut commands, "Today's daily challenge was already played");
+        return;
+    }
+
+    let seed = day.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    let mutators = Mutators::from_seed(seed >> 32);
+    record_daily_result(day, 0);
+
+    commands.insert_resource(GameRng(StdRng::seed_from_u64(seed)));
+    commands.insert_resource(mutators);
+    commands.insert_resource(DailyChallenge { day });
+    commands.insert_resource(RewindBuffer {
+        charges: 0,
+        ..default()
+    });
+    commands.spawn((
+        Text::new(format!("Daily challenge: {}", mutators.describe())),
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(12.0),
+            left: Val::Px(12.0),
+            ..default()
+        },
+        Interaction::default(),
+        DailyChallengeText,
+    ));
+}
+
+fn update_daily_challenge_text(
+    daily: Option<Res<DailyChallenge>>,
+    score: Res<Score>,
+    mutators: Res<Mutators>,
+    mut text: Single<&mut Text, With<DailyChallengeText>>,
+) {
+    if let Some(daily) = daily {
+        text.0 = format!(
+            "Daily challenge: {}  Code: {}",
+            mutators.describe(),
+            daily_share_code(daily.day, score.0)
+        );
+    }
+}
+
+fn record_daily_on_exit(
+    mut exit: EventReader<AppExit>,
+    daily: Option<Res<DailyChallenge>>,
+    score: Res<Score>,
+) {
+    if let Some(daily) = daily {
+        if exit.read().count() > 0 {
+            record_daily_result(daily.day, score.0);
+        }
+    }
+}
+
+const WEEKLY_PATH: &str = "assets/weekly.ladder";
+const WEEKLY_ARCHIVE_WEEKS: usize = 8;
+
+#[derive(Clone, Copy)]
+struct WeeklyRules {
+    week: u64,
+    seed: u64,
+    mutators: Mutators,
+}
+
+impl WeeklyRules {
+    fn load_all(errors: &mut LoadErrors) -> Vec<WeeklyRules> {
+        let mut weeks = Vec::new();
+        for line in fs::read_to_string(WEEKLY_PATH).unwrap_or_default().lines() {
+            let mut fields = line.split_whitespace();
+            if fields.next() != Some("week") {
+                continue;
+            }
+            let mut number = || fields.next().and_then(|field| field.parse::<u64>().ok());
+            if let (Some(week), Some(seed), Some(mutators)) = (number(), number(), number()) {
+                weeks.push(WeeklyRules {
+                    week,
+                    seed,
+                    mutators: Mutators(mutators as u32),
+                });
+            } else {
+                errors.report(format!(
+                    "skipping bad weekly entry in {WEEKLY_PATH}: {line}"
+                ));
+            }
+        }
+        weeks
+    }
+
+    fn for_week(week: u64, defined: &[WeeklyRules]) -> Self {
+        defined
+            .iter()
+            .copied()
+            .find(|rules| rules.week == week)
+            .unwrap_or_else(|| {
+                let seed = week.wrapping_mul(0xD1B5_4A32_D192_ED03);
+                WeeklyRules {
+                    week,
+                    seed,
+                    mutators: Mutators::from_seed(seed >> 32),
+                }
+            })
+    }
+}
+
+#[derive(Resource)]
+struct WeeklyLadder {
+    week: u64,
+}
+
+#[derive(Component)]
+struct WeeklyMenu;
+
+fn this_week() -> u64 {
+    today() / 7
+}
+
+fn record_weekly_result(week: u64, score: u32) {
+    let mut profile = Profile::load();
+    if profile.weekly_best(week).is_some_and(|best| best >= score) {
+        return;
+    }
+    profile
+        .weekly_results
+        .retain(|&(recorded_week, _)| recorded_week != week);
+    profile.weekly_results.push((week, score));
+    profile.save();
+}
+
+fn weekly_menu_text(rules: WeeklyRules, defined: &[WeeklyRules], profile: &Profile) -> String {
+    let best = |week| {
+        profile
+            .weekly_best(week)
+            .map_or("-".to_string(), |score| score.to_string())
+    };
+    let mut text = format!(
+        "Weekly ladder, week {}\n{}\nYour best: {}\n\nPast weeks:\n",
+        rules.week,
+        rules.mutators.describe(),
+        best(rules.week)
+    );
+    let mut past: Vec<u64> = defined
+        .iter()
+        .map(|rules| rules.week)
+        .chain(profile.weekly_results.iter().map(|&(week, _)| week))
+        .filter(|&week| week < rules.week)
+        .collect();
+    past.sort_unstable_by(|a, b| b.cmp(a));
+    past.dedup();
+    if past.is_empty() {
+        text.push_str("none yet\n");
+    }
+    for week in past.into_iter().take(WEEKLY_ARCHIVE_WEEKS) {
+        let rules = WeeklyRules::for_week(week, defined);
+        text.push_str(&format!(
+            "Week {week}: {}  best {}\n",
+            rules.mutators.describe(),
+            best(week)
+        ));
+    }
+    text.push_str("\nEnter to start");
+    text
+}
+
+fn start_weekly_ladder(mut commands: Commands, mut load_errors: ResMut<LoadErrors>) {
+    if !weekly_requested() {
+        return;
+    }
+    let defined = WeeklyRules::load_all(&mut load_errors);
+    let rules = WeeklyRules::for_week(this_week(), &defined);
+
+    commands.insert_resource(GameRng(StdRng::seed_from_u64(rules.seed)));
+    commands.insert_resource(rules.mutators);
+    commands.insert_resource(WeeklyLadder { week: rules.week });
+    commands.insert_resource(RewindBuffer {
+        charges: 0,
+        ..default()
+    });
+    commands.set_state(GameState::Menu);
+    commands.spawn((
+        Text::new(weekly_menu_text(rules, &defined, &Profile::load())),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(25.0),
+            left: Val::Percent(35.0),
+            ..default()
+        },
+        BackgroundColor(MENU_PANEL),
+        ThemedPanel(MENU_PANEL),
+        ThemedText,
+        Interaction::default(),
+        WeeklyMenu,
+    ));
+}
+
+fn update_weekly_menu(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    menu: Single<Entity, With<WeeklyMenu>>,
+    mutators: Res<Mutators>,
+    ladder: Res<WeeklyLadder>,
+) {
+    if !keys.just_pressed(KeyCode::Enter) {
+        return;
+    }
+    commands.entity(*menu).despawn();
+    commands.set_state(GameState::Playing);
+    spawn_toast(
+        &mut commands,
+        &format!("Week {}: {}", ladder.week, mutators.describe()),
+    );
+}
+
+fn record_weekly_on_exit(
+    mut exit: EventReader<AppExit>,
+    ladder: Option<Res<WeeklyLadder>>,
+    score: Res<Score>,
+) {
+    if let Some(ladder) = ladder {
+        if exit.read().count() > 0 {
+            record_weekly_result(ladder.week, score.0);
+        }
+    }
+}
+
+#[derive(Component)]
+struct CustomRulesPanel;
+
+fn custom_rules_text(mutators: Mutators, commander: Commander) -> String {
+    let mut text = String::from("Custom game\n");
+    text.push_str(&format!("[C] commander: {}\n", commander.describe()));
+    for (index, &rule) in Mutators::CUSTOM_RULES.iter().enumerate() {
+        let state = if mutators.has(rule) { "on" } else { "off" };
+        text.push_str(&format!(
+            "[{}] {}: {state}\n",
+            index + 1,
+            Mutators::name(rule)
+        ));
+    }
+    text.push_str("[Enter] start");
+    text
+}
+
+fn open_custom_rules(mut commands: Commands, commander: Res<Commander>) {
+    if !custom_requested() {
+        return;
+    }
+
+    commands.set_state(GameState::Menu);
+    commands.spawn((
+        Text::new(custom_rules_text(Mutators::default(), *commander)),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(30.0),
+            left: Val::Percent(40.0),
+            ..default()
+        },
+        BackgroundColor(MENU_PANEL),
+        ThemedPanel(MENU_PANEL),
+        ThemedText,
+        Interaction::default(),
+        CustomRulesPanel,
+    ));
+}
+
+fn update_custom_rules(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    panel: Single<(Entity, &mut Text), With<CustomRulesPanel>>,
+    mut mutators: ResMut<Mutators>,
+    mut commander: ResMut<Commander>,
+    mut gold: ResMut<Gold>,
+    assets: Res<GameAssets>,
+) {
+    let (entity, mut text) = panel.into_inner();
+    if keys.just_pressed(KeyCode::KeyC) {
+        *commander = commander.next();
+    }
+    let toggle_keys = [
+        KeyCode::Digit1,
+        KeyCode::Digit2,
+        KeyCode::Digit3,
+        KeyCode::Digit4,
+    ];
+    for (key, rule) in toggle_keys.into_iter().zip(Mutators::CUSTOM_RULES) {
+        if keys.just_pressed(key) {
+            mutators.0 ^= rule;
+        }
+    }
+    text.0 = custom_rules_text(*mutators, *commander);
+
+    if keys.just_pressed(KeyCode::Enter) {
+        let mut profile = Profile::load();
+        profile.commander = *commander;
+        profile.save();
+        spawn_starting_towers(&mut commands, &assets, *commander);
+        gold.0 = mutators.starting_gold();
+        commands.set_state(GameState::Playing);
+        commands.entity(entity).despawn();
+    }
+}
+
+const DEMO_PAN_RADIUS: f32 = 80.0;
+const DEMO_PAN_SPEED: f32 = 0.1;
+const DEMO_MAX_TOWERS: usize = 40;
+const DEMO_BUILD_ORDER: [TowerKind; 4] = [
+    TowerKind::Cannon,
+    TowerKind::Frost,
+    TowerKind::Arrow,
+    TowerKind::Mortar,
+];
+
+fn demo_auto_build(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    commander: Res<Commander>,
+    mut gold: ResMut<Gold>,
+    towers: Query<(), With<Tower>>,
+    path: Res<Path>,
+    base: Single<&Transform, With<Base>>,
+    occupancy: Occupancy,
+) {
+    let built = towers.iter().count();
+    let kind = DEMO_BUILD_ORDER[built % DEMO_BUILD_ORDER.len()];
+    let cost = kind.cost(*commander);
+    if gold.0 < cost || built >= DEMO_MAX_TOWERS {
+        return;
+    }
+    let route: Vec<Vec2> = path
+        .0
+        .iter()
+        .copied()
+        .chain([base.translation.truncate()])
+        .collect();
+    let Some(cell) = occupancy.cell_covering(&route, kind) else {
+        return;
+    };
+    gold.0 -= cost;
+    spawn_tower_at(&mut commands, &assets, cell, kind);
+}
+
+fn demo_pan_camera(
+    real_time: Res<Time<Real>>,
+    framing: Res<CameraFraming>,
+    kill_cam: Res<KillCam>,
+    mut camera: Single<&mut Transform, With<Camera2d>>,
+) {
+    if kill_cam.timer.is_some() {
+        return;
+    }
+    let offset = Vec2::from_angle(real_time.elapsed_secs() * DEMO_PAN_SPEED) * DEMO_PAN_RADIUS;
+    camera.translation = (framing.home + offset).extend(camera.translation.z);
+}
+
+fn restart_demo(
+    mut commands: Commands,
+    state: Res<State<GameState>>,
+    run_end_text: Query<Entity, With<RunEndText>>,
+    mut restore: RunRestore,
+) {
+    if *state.get() != GameState::GameOver {
+        return;
+    }
+    commands.set_state(GameState::Playing);
+    for entity in &run_end_text {
+        commands.entity(entity).despawn();
+    }
+    fresh_run(*restore.mutators).restore(&mut restore);
+}
+
+fn exit_demo(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut load_errors: ResMut<LoadErrors>,
+) {
+    if keys.get_just_pressed().next().is_none() && mouse.get_just_pressed().next().is_none() {
+        return;
+    }
+    commands.remove_resource::<DemoMode>();
+    commands.insert_resource(Waves::campaign());
+    play_level(&mut commands, Level::load(LEVEL_PATH, &mut load_errors));
+    spawn_toast(&mut commands, "Demo ended");
+}
+
+pub struct RunModesPlugin;
+impl Plugin for RunModesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Startup,
+            (
+                start_daily_challenge,
+                start_weekly_ladder,
+                open_custom_rules,
+            ),
+        );
+        app.add_systems(
+            Update,
+            (
+                (
+                    update_daily_challenge_text,
+                    update_custom_rules.run_if(in_state(GameState::Menu)),
+                    update_weekly_menu.run_if(in_state(GameState::Menu)),
+                )
+                    .in_set(FrameSet::Hud),
+                (
+                    demo_auto_build,
+                    demo_pan_camera.before(clamp_camera),
+                    restart_demo,
+                    exit_demo,
+                )
+                    .chain()
+                    .run_if(resource_exists::<DemoMode>),
+            ),
+        );
+        app.add_systems(Last, (record_daily_on_exit, record_weekly_on_exit));
+    }
+}
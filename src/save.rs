@@ -0,0 +1,913 @@
+use crate::common::{
+    Enemy, GameAssets, GameState, Gold, Hp, Kills, Lives, Mutators, RunClock, Score, Shield, Tower,
+    STARTING_LIVES,
+};
+use crate::enemy::{spawn_enemy_at, Direction, EnemyKind, PathProgress, WaveCleared, Waves};
+use crate::headless::{IdleReport, IdleSimulation};
+use crate::level::Level;
+use crate::practice::Practice;
+use crate::projectile::Projectile;
+use crate::run_end::{LastStand, RunEndText};
+use crate::run_modes::{daily_requested, demo_requested, weekly_requested, DemoMode};
+use crate::scouting::{ScoutRerolls, SCOUT_REROLLS};
+use crate::tower::{
+    spawn_level_towers, spawn_rubble, spawn_shield_generator, spawn_starting_towers,
+    spawn_tower_at, BaseUpgrades, Commander, Rubble, ShieldGenerator, TargetingStrategy, TowerKind,
+    TowersSold, UltimateCharge, TOWER_HP,
+};
+use crate::ui::{
+    navigate_confirm_dialog, open_confirm_dialog, spawn_toast, ConfirmAction, Confirmed,
+};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
+
+type RunEntityFilter = Or<(
+    With<Enemy>,
+    With<Projectile>,
+    With<Rubble>,
+    With<ShieldGenerator>,
+    With<Tower>,
+)>;
+
+pub struct EnemySnapshot {
+    kind: EnemyKind,
+    translation: Vec3,
+    direction: Vec3,
+    hp: u32,
+    origin: Vec3,
+    waypoint: usize,
+}
+
+#[derive(PartialEq)]
+pub struct TowerSnapshot {
+    position: Vec2,
+    kind: TowerKind,
+    strategy: TargetingStrategy,
+    kills: u32,
+    charge: u32,
+    hp: u32,
+}
+
+#[derive(SystemParam)]
+pub struct RunState<'w, 's> {
+    pub enemies: Query<
+        'w,
+        's,
+        (
+            &'static Transform,
+            &'static Direction,
+            &'static Hp,
+            &'static EnemyKind,
+            &'static PathProgress,
+        ),
+        With<Enemy>,
+    >,
+    pub towers: Query<
+        'w,
+        's,
+        (
+            &'static Transform,
+            &'static TowerKind,
+            &'static TargetingStrategy,
+            &'static Kills,
+            &'static UltimateCharge,
+            &'static Hp,
+        ),
+        With<Tower>,
+    >,
+    pub generators: Query<'w, 's, &'static Transform, With<ShieldGenerator>>,
+    pub rubble: Query<'w, 's, (&'static Transform, &'static Rubble)>,
+    pub gold: Res<'w, Gold>,
+    pub lives: Res<'w, Lives>,
+    pub shield: Res<'w, Shield>,
+    pub upgrades: Res<'w, BaseUpgrades>,
+    pub waves: Res<'w, Waves>,
+    pub clock: Res<'w, RunClock>,
+    pub mutators: Res<'w, Mutators>,
+    pub score: Res<'w, Score>,
+    pub towers_sold: Res<'w, TowersSold>,
+    pub last_stand: Res<'w, LastStand>,
+    pub scout_rerolls: Res<'w, ScoutRerolls>,
+}
+
+#[derive(SystemParam)]
+pub struct RunRestore<'w, 's> {
+    pub commands: Commands<'w, 's>,
+    pub run_entities: Query<'w, 's, Entity, RunEntityFilter>,
+    pub assets: Res<'w, GameAssets>,
+    pub gold: ResMut<'w, Gold>,
+    pub lives: ResMut<'w, Lives>,
+    pub shield: ResMut<'w, Shield>,
+    pub upgrades: ResMut<'w, BaseUpgrades>,
+    pub waves: ResMut<'w, Waves>,
+    pub clock: ResMut<'w, RunClock>,
+    pub mutators: ResMut<'w, Mutators>,
+    pub score: ResMut<'w, Score>,
+    pub towers_sold: ResMut<'w, TowersSold>,
+    pub last_stand: ResMut<'w, LastStand>,
+    pub scout_rerolls: ResMut<'w, ScoutRerolls>,
+}
+
+const SAVE_VERSION: u32 = 6;
+
+pub enum SaveError {
+    Missing,
+    BadVersion,
+    TooNew(u32),
+    Corrupt(String),
+}
+
+impl SaveError {
+    pub fn reason(&self) -> String {
+        match self {
+            SaveError::Missing => "no save found".to_string(),
+            SaveError::BadVersion => "unreadable save version".to_string(),
+            SaveError::TooNew(version) => {
+                format!("save format v{version} is newer than this build reads (v{SAVE_VERSION})")
+            }
+            SaveError::Corrupt(line) => format!("unreadable save line: {line}"),
+        }
+    }
+}
+
+fn save_version(input: &str) -> Result<u32, SaveError> {
+    let Some(header) = input
+        .lines()
+        .next()
+        .and_then(|line| line.strip_prefix("version "))
+    else {
+        return Ok(1);
+    };
+    header.trim().parse().map_err(|_| SaveError::BadVersion)
+}
+
+fn migrate_save(input: &str) -> Result<Vec<String>, SaveError> {
+    let version = save_version(input)?;
+    if version > SAVE_VERSION {
+        return Err(SaveError::TooNew(version));
+    }
+    let mut lines: Vec<String> = input
+        .lines()
+        .filter(|line| !line.starts_with("version "))
+        .map(str::to_string)
+        .collect();
+    for from in version..SAVE_VERSION {
+        match from {
+            1 => migrate_save_v1(&mut lines),
+            2 => migrate_save_v2(&mut lines),
+            3 => migrate_save_v3(&mut lines),
+            4 => migrate_save_v4(&mut lines),
+            5 => migrate_save_v5(&mut lines),
+            _ => {}
+        }
+    }
+    Ok(lines)
+}
+
+fn migrate_save_v1(lines: &mut [String]) {
+    let cannon = TowerKind::Cannon.name();
+    let closest = TargetingStrategy::default().name();
+    for line in lines {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let migrated = match fields.as_slice() {
+            ["tower", x, y] => format!("tower {x} {y} {cannon} {closest}"),
+            ["tower", x, y, kind] => format!("tower {x} {y} {kind} {closest}"),
+            ["enemy", kind, x, y, z, dx, dy, dz, hp] => {
+                format!("enemy {kind} {x} {y} {z} {dx} {dy} {dz} {hp} {x} {y} 0")
+            }
+            ["enemy", _, _, _, _, _, _, _, _, _, _] => format!("{line} 0"),
+            _ => continue,
+        };
+        *line = migrated;
+    }
+}
+
+fn migrate_save_v2(lines: &mut Vec<String>) {
+    lines.extend(["score 0", "towers_sold 0", "last_stand false false -"].map(str::to_string));
+}
+
+fn migrate_save_v3(lines: &mut [String]) {
+    for line in lines.iter_mut().filter(|line| line.starts_with("tower ")) {
+        line.push_str(" 0 0");
+    }
+}
+
+fn migrate_save_v4(lines: &mut Vec<String>) {
+    lines.push(format!("scout_rerolls {SCOUT_REROLLS}"));
+}
+
+fn migrate_save_v5(lines: &mut [String]) {
+    for line in lines.iter_mut().filter(|line| line.starts_with("tower ")) {
+        line.push_str(&format!(" {TOWER_HP}"));
+    }
+}
+
+#[derive(Default)]
+pub struct RunSnapshot {
+    pub mutators: u32,
+    pub clock: f32,
+    pub gold: u32,
+    pub lives: u32,
+    pub shield: u32,
+    pub base_upgrades: (u32, u32),
+    pub wave: u32,
+    pub pending: Vec<EnemyKind>,
+    pub upcoming: Option<Vec<EnemyKind>>,
+    pub scout_rerolls: u32,
+    pub towers: Vec<TowerSnapshot>,
+    pub generators: Vec<Vec2>,
+    pub rubble: Vec<(Vec2, TowerKind, u32)>,
+    pub enemies: Vec<EnemySnapshot>,
+    pub score: u32,
+    pub towers_sold: u32,
+    pub last_stand_used: bool,
+    pub last_stand_breached: bool,
+    pub last_stand_remaining: Option<f32>,
+}
+
+impl RunSnapshot {
+    pub fn capture(state: &RunState) -> Self {
+        let mut snapshot = RunSnapshot::default();
+        snapshot.capture_into(state);
+        snapshot
+    }
+
+    pub fn capture_into(&mut self, state: &RunState) {
+        self.mutators = state.mutators.0;
+        self.clock = state.clock.0;
+        self.gold = state.gold.0;
+        self.lives = state.lives.0;
+        self.shield = state.shield.0;
+        self.base_upgrades = (state.upgrades.trickle, state.upgrades.auto_attack);
+        self.wave = state.waves.number;
+        self.pending.clear();
+        self.pending.extend(state.waves.pending.iter().copied());
+        self.upcoming.clone_from(&state.waves.upcoming);
+        self.scout_rerolls = state.scout_rerolls.0;
+        self.towers.clear();
+        self.towers.extend(state.towers.iter().map(
+            |(transform, &kind, &strategy, kills, charge, hp)| TowerSnapshot {
+                position: transform.translation.truncate(),
+                kind,
+                strategy,
+                kills: kills.0,
+                charge: charge.0,
+                hp: hp.0,
+            },
+        ));
+        self.generators.clear();
+        self.generators.extend(
+            state
+                .generators
+                .iter()
+                .map(|transform| transform.translation.truncate()),
+        );
+        self.rubble.clear();
+        self.rubble
+            .extend(state.rubble.iter().map(|(transform, rubble)| {
+                (transform.translation.truncate(), rubble.kind, rubble.kills)
+            }));
+        self.score = state.score.0;
+        self.towers_sold = state.towers_sold.0;
+        self.last_stand_used = state.last_stand.used;
+        self.last_stand_breached = state.last_stand.breached;
+        self.last_stand_remaining = state.last_stand.timer.as_ref().map(Timer::remaining_secs);
+        self.enemies.clear();
+        self.enemies.extend(state.enemies.iter().map(
+            |(transform, direction, hp, &kind, progress)| EnemySnapshot {
+                kind,
+                translation: transform.translation,
+                direction: direction.0,
+                hp: hp.0,
+                origin: progress.origin,
+                waypoint: progress.waypoint,
+            },
+        ));
+    }
+
+    pub fn serialize(&self) -> String {
+        let mut out = format!(
+            "version {SAVE_VERSION}\nmutators {}\nclock {}\ngold {}\nlives {}\nshield {}\nwave {}\n",
+            self.mutators, self.clock, self.gold, self.lives, self.shield, self.wave
+        );
+        out.push_str(&format!(
+            "base {} {}\n",
+            self.base_upgrades.0, self.base_upgrades.1
+        ));
+        out.push_str(&format!(
+            "score {}\ntowers_sold {}\nscout_rerolls {}\n",
+            self.score, self.towers_sold, self.scout_rerolls
+        ));
+        out.push_str(&format!(
+            "last_stand {} {} {}\n",
+            self.last_stand_used,
+            self.last_stand_breached,
+            self.last_stand_remaining
+                .map_or("-".to_string(), |remaining| remaining.to_string())
+        ));
+        for kind in &self.pending {
+            out.push_str(&format!("pending {}\n", kind.name()));
+        }
+        if let Some(upcoming) = &self.upcoming {
+            out.push_str("upcoming");
+            for kind in upcoming {
+                out.push_str(&format!(" {}", kind.name()));
+            }
+            out.push('\n');
+        }
+        for tower in &self.towers {
+            out.push_str(&format!(
+                "tower {} {} {} {} {} {} {}\n",
+                tower.position.x,
+                tower.position.y,
+                tower.kind.name(),
+                tower.strategy.name(),
+                tower.kills,
+                tower.charge,
+                tower.hp
+            ));
+        }
+        for generator in &self.generators {
+            out.push_str(&format!("generator {} {}\n", generator.x, generator.y));
+        }
+        for (rubble, kind, kills) in &self.rubble {
+            out.push_str(&format!(
+                "rubble {} {} {} {kills}\n",
+                rubble.x,
+                rubble.y,
+                kind.name()
+            ));
+        }
+        for enemy in &self.enemies {
+            out.push_str(&format!(
+                "enemy {} {} {} {} {} {} {} {} {} {} {}\n",
+                enemy.kind.name(),
+                enemy.translation.x,
+                enemy.translation.y,
+                enemy.translation.z,
+                enemy.direction.x,
+                enemy.direction.y,
+                enemy.direction.z,
+                enemy.hp,
+                enemy.origin.x,
+                enemy.origin.y,
+                enemy.waypoint,
+            ));
+        }
+        out
+    }
+
+    pub fn parse(input: &str) -> Result<Self, SaveError> {
+        let mut snapshot = RunSnapshot {
+            lives: STARTING_LIVES,
+            ..default()
+        };
+        for line in migrate_save(input)? {
+            snapshot
+                .parse_line(&line)
+                .ok_or_else(|| SaveError::Corrupt(line.clone()))?;
+        }
+        Ok(snapshot)
+    }
+
+    fn parse_line(&mut self, line: &str) -> Option<()> {
+        let mut fields = line.split_whitespace();
+        match fields.next() {
+            Some("mutators") => self.mutators = fields.next()?.parse().ok()?,
+            Some("clock") => self.clock = fields.next()?.parse().ok()?,
+            Some("gold") => self.gold = fields.next()?.parse().ok()?,
+            Some("lives") => self.lives = fields.next()?.parse().ok()?,
+            Some("shield") => self.shield = fields.next()?.parse().ok()?,
+            Some("base") => {
+                let trickle = fields.next()?.parse().ok()?;
+                let auto_attack = fields.next()?.parse().ok()?;
+                self.base_upgrades = (trickle, auto_attack);
+            }
+            Some("wave") => self.wave = fields.next()?.parse().ok()?,
+            Some("score") => self.score = fields.next()?.parse().ok()?,
+            Some("towers_sold") => self.towers_sold = fields.next()?.parse().ok()?,
+            Some("scout_rerolls") => self.scout_rerolls = fields.next()?.parse().ok()?,
+            Some("last_stand") => {
+                self.last_stand_used = fields.next()?.parse().ok()?;
+                self.last_stand_breached = fields.next()?.parse().ok()?;
+                self.last_stand_remaining = match fields.next()? {
+                    "-" => None,
+                    remaining => Some(remaining.parse().ok()?),
+                };
+            }
+            Some("pending") => self.pending.push(EnemyKind::from_name(fields.next()?)?),
+            Some("upcoming") => {
+                self.upcoming = Some(fields.map(EnemyKind::from_name).collect::<Option<_>>()?);
+            }
+            Some("tower") => {
+                let x = fields.next()?.parse().ok()?;
+                let y = fields.next()?.parse().ok()?;
+                let kind = TowerKind::from_name(fields.next()?)?;
+                let strategy = TargetingStrategy::from_name(fields.next()?)?;
+                self.towers.push(TowerSnapshot {
+                    position: Vec2::new(x, y),
+                    kind,
+                    strategy,
+                    kills: fields.next()?.parse().ok()?,
+                    charge: fields.next()?.parse().ok()?,
+                    hp: fields.next()?.parse().ok()?,
+                });
+            }
+            Some("generator") => {
+                let x = fields.next()?.parse().ok()?;
+                let y = fields.next()?.parse().ok()?;
+                self.generators.push(Vec2::new(x, y));
+            }
+            Some("rubble") => {
+                let x = fields.next()?.parse().ok()?;
+                let y = fields.next()?.parse().ok()?;
+                let kind = TowerKind::from_name(fields.next()?)?;
+                let kills = fields.next()?.parse().ok()?;
+                self.rubble.push((Vec2::new(x, y), kind, kills));
+            }
+            Some("enemy") => {
+                let kind = EnemyKind::from_name(fields.next()?)?;
+                let values: Vec<f32> = fields.map(|f| f.parse().ok()).collect::<Option<_>>()?;
+                let &[x, y, z, dx, dy, dz, hp, ox, oy, waypoint] = values.as_slice() else {
+                    return None;
+                };
+                self.enemies.push(EnemySnapshot {
+                    kind,
+                    translation: Vec3::new(x, y, z),
+                    direction: Vec3::new(dx, dy, dz),
+                    hp: hp as u32,
+                    origin: Vec3::new(ox, oy, z),
+                    waypoint: waypoint as usize,
+                });
+            }
+            None => {}
+            Some(_) => return None,
+        }
+        Some(())
+    }
+
+    pub fn write(&self, path: &str) {
+        let path = Path::new(path);
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        if let Err(err) = fs::write(path, self.serialize()) {
+            warn!("failed to write save {}: {err}", path.display());
+        }
+    }
+
+    pub fn read(path: &str) -> Result<Self, SaveError> {
+        let contents = fs::read_to_string(path).map_err(|_| SaveError::Missing)?;
+        Self::parse(&contents)
+    }
+
+    pub fn restore(&self, restore: &mut RunRestore) {
+        for entity in &restore.run_entities {
+            restore.commands.entity(entity).despawn();
+        }
+        restore.gold.0 = self.gold;
+        restore.lives.0 = self.lives;
+        restore.shield.0 = self.shield;
+        (restore.upgrades.trickle, restore.upgrades.auto_attack) = self.base_upgrades;
+        restore.waves.number = self.wave;
+        restore.waves.pending.clear();
+        restore.waves.pending.extend(self.pending.iter().copied());
+        restore.waves.upcoming.clone_from(&self.upcoming);
+        restore.waves.break_timer.reset();
+        restore.scout_rerolls.0 = self.scout_rerolls;
+        restore.clock.0 = self.clock;
+        restore.mutators.0 = self.mutators;
+        restore.score.0 = self.score;
+        restore.towers_sold.0 = self.towers_sold;
+        *restore.last_stand = LastStand {
+            timer: self
+                .last_stand_remaining
+                .map(|remaining| Timer::from_seconds(remaining, TimerMode::Once)),
+            used: self.last_stand_used,
+            breached: self.last_stand_breached,
+        };
+        for tower in &self.towers {
+            let spawned = spawn_tower_at(
+                &mut restore.commands,
+                &restore.assets,
+                tower.position,
+                tower.kind,
+            );
+            restore.commands.entity(spawned).insert((
+                tower.strategy,
+                Kills(tower.kills),
+                UltimateCharge(tower.charge),
+                Hp(tower.hp),
+            ));
+        }
+        for &generator in &self.generators {
+            spawn_shield_generator(&mut restore.commands, &restore.assets, generator);
+        }
+        for &(rubble, kind, kills) in &self.rubble {
+            spawn_rubble(&mut restore.commands, &restore.assets, rubble, kind, kills);
+        }
+        for enemy in &self.enemies {
+            let spawned = spawn_enemy_at(
+                &mut restore.commands,
+                &restore.assets,
+                enemy.kind,
+                enemy.translation,
+                enemy.direction,
+                enemy.hp,
+            );
+            restore.commands.entity(spawned).insert(PathProgress {
+                waypoint: enemy.waypoint,
+                ..PathProgress::new(enemy.origin)
+            });
+        }
+    }
+}
+
+pub const AUTOSAVE_PATH: &str = "saves/autosave.sav";
+const MANUAL_SAVE_PATH: &str = "saves/manual.sav";
+
+#[derive(Component)]
+struct RecoverPrompt;
+
+pub fn modified_time(path: &str) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+fn autosave(mut commands: Commands, mut cleared: EventReader<WaveCleared>, state: RunState) {
+    if let Some(WaveCleared(number)) = cleared.read().last() {
+        RunSnapshot::capture(&state).write(AUTOSAVE_PATH);
+        spawn_toast(&mut commands, &format!("Wave {number} cleared, autosaved"));
+    }
+}
+
+fn autosave_on_exit(mut exit: EventReader<AppExit>, state: RunState) {
+    if exit.read().any(|event| *event == AppExit::Success) {
+        RunSnapshot::capture(&state).write(AUTOSAVE_PATH);
+    }
+}
+
+fn manual_save(mut commands: Commands, keys: Res<ButtonInput<KeyCode>>, state: RunState) {
+    if !keys.just_pressed(KeyCode::F5) {
+        return;
+    }
+    if Path::new(MANUAL_SAVE_PATH).exists() {
+        open_confirm_dialog(&mut commands, ConfirmAction::OverwriteSave);
+        return;
+    }
+    RunSnapshot::capture(&state).write(MANUAL_SAVE_PATH);
+    spawn_toast(&mut commands, "Saved");
+}
+
+fn request_restart(mut commands: Commands, keys: Res<ButtonInput<KeyCode>>) {
+    if keys.just_pressed(KeyCode::F9) {
+        open_confirm_dialog(&mut commands, ConfirmAction::RestartRun);
+    }
+}
+
+fn confirm_restart_run(
+    mut commands: Commands,
+    mut confirmed: EventReader<Confirmed>,
+    commander: Res<Commander>,
+    level: Option<Res<Level>>,
+    run_end_text: Query<Entity, With<RunEndText>>,
+    mut restore: RunRestore,
+) {
+    if !confirmed
+        .read()
+        .any(|Confirmed(action)| matches!(action, ConfirmAction::RestartRun))
+    {
+        return;
+    }
+    commands.set_state(GameState::Playing);
+    for entity in &run_end_text {
+        commands.entity(entity).despawn();
+    }
+    fresh_run(*restore.mutators).restore(&mut restore);
+    let default_level = Level::default();
+    let level = level.as_deref().unwrap_or(&default_level);
+    spawn_level_towers(&mut restore.commands, &restore.assets, level);
+    spawn_starting_towers(&mut restore.commands, &restore.assets, *commander);
+    spawn_toast(&mut commands, "Run restarted");
+}
+
+fn confirm_overwrite_save(
+    mut commands: Commands,
+    mut confirmed: EventReader<Confirmed>,
+    state: RunState,
+) {
+    for Confirmed(action) in confirmed.read() {
+        if let ConfirmAction::OverwriteSave = action {
+            RunSnapshot::capture(&state).write(MANUAL_SAVE_PATH);
+            spawn_toast(&mut commands, "Saved");
+        }
+    }
+}
+
+fn setup_recover_prompt(mut commands: Commands, idle_report: Option<Res<IdleReport>>) {
+    if daily_requested() || weekly_requested() || demo_requested() {
+        return;
+    }
+    let Some(autosaved_at) = modified_time(AUTOSAVE_PATH) else {
+        return;
+    };
+    let newer_than_manual =
+        modified_time(MANUAL_SAVE_PATH).is_none_or(|saved_at| autosaved_at > saved_at);
+
+    if newer_than_manual {
+        let mut message = String::from("Recover last session? (Y/N)");
+        if let Some(report) = idle_report {
+            message.push_str(&format!(
+                "\nWhile you were away: {} waves, {:+} gold, {} lives lost",
+                report.waves, report.gold, report.lives_lost
+            ));
+        }
+        commands.spawn((
+            Text::new(message),
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(12.0),
+                left: Val::Px(12.0),
+                ..default()
+            },
+            Interaction::default(),
+            RecoverPrompt,
+        ));
+    }
+}
+
+fn handle_recover_prompt(
+    keys: Res<ButtonInput<KeyCode>>,
+    prompt: Single<Entity, With<RecoverPrompt>>,
+    mut restore: RunRestore,
+) {
+    if keys.just_pressed(KeyCode::KeyY) {
+        match RunSnapshot::read(AUTOSAVE_PATH) {
+            Ok(snapshot) => snapshot.restore(&mut restore),
+            Err(error) => {
+                warn!("cannot load {AUTOSAVE_PATH}: {}", error.reason());
+                spawn_toast(&mut restore.commands, &error.reason());
+            }
+        }
+    } else if !keys.just_pressed(KeyCode::KeyN) {
+        return;
+    }
+
+    restore.commands.entity(*prompt).despawn();
+}
+
+fn persists_run(
+    demo: Option<Res<DemoMode>>,
+    idle: Option<Res<IdleSimulation>>,
+    practice: Option<Res<Practice>>,
+) -> bool {
+    demo.is_none() && idle.is_none() && practice.is_none()
+}
+
+pub fn fresh_run(mutators: Mutators) -> RunSnapshot {
+    RunSnapshot {
+        mutators: mutators.0,
+        gold: mutators.starting_gold(),
+        lives: STARTING_LIVES,
+        scout_rerolls: SCOUT_REROLLS,
+        ..default()
+    }
+}
+
+pub struct SavePlugin;
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_recover_prompt);
+        app.add_systems(
+            Update,
+            (
+                autosave.run_if(persists_run),
+                (
+                    manual_save.run_if(not(resource_exists::<Practice>)),
+                    request_restart,
+                )
+                    .chain()
+                    .before(navigate_confirm_dialog),
+                (confirm_overwrite_save, confirm_restart_run)
+                    .chain()
+                    .after(navigate_confirm_dialog),
+                handle_recover_prompt,
+            ),
+        );
+        app.add_systems(Last, autosave_on_exit.run_if(persists_run));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+
+    const V1_SAVE: &str = "mutators 8\nclock 42.5\ngold 75\nlives 12\nshield 3\nwave 4\n\
+        base 1 0\npending grunt\npending tank\ntower 10 20\ntower 30 40 arrow\n\
+        enemy runner 5 6 1 0 -1 0 40\nenemy grunt 7 8 1 1 0 0 20 -100 0\n";
+
+    #[test]
+    fn migrates_v1_saves() {
+        assert!(matches!(save_version(V1_SAVE), Ok(1)));
+        let snapshot = RunSnapshot::parse(V1_SAVE)
+            .ok()
+            .expect("v1 save should parse");
+        assert_eq!(snapshot.mutators, 8);
+        assert_eq!(snapshot.clock, 42.5);
+        assert_eq!(
+            (snapshot.gold, snapshot.lives, snapshot.shield),
+            (75, 12, 3)
+        );
+        assert_eq!(snapshot.base_upgrades, (1, 0));
+        assert_eq!(snapshot.wave, 4);
+        assert!(snapshot.pending == [EnemyKind::Grunt, EnemyKind::Tank]);
+        assert!(
+            snapshot.towers
+                == [
+                    TowerSnapshot {
+                        position: Vec2::new(10.0, 20.0),
+                        kind: TowerKind::Cannon,
+                        strategy: TargetingStrategy::Closest,
+                        kills: 0,
+                        charge: 0,
+                        hp: TOWER_HP,
+                    },
+                    TowerSnapshot {
+                        position: Vec2::new(30.0, 40.0),
+                        kind: TowerKind::Arrow,
+                        strategy: TargetingStrategy::Closest,
+                        kills: 0,
+                        charge: 0,
+                        hp: TOWER_HP,
+                    },
+                ]
+        );
+
+        let [runner, grunt] = snapshot.enemies.as_slice() else {
+            panic!("expected two enemies");
+        };
+        assert!(runner.kind == EnemyKind::Runner);
+        assert_eq!(runner.translation, Vec3::new(5.0, 6.0, 1.0));
+        assert_eq!(runner.direction, Vec3::NEG_Y);
+        assert_eq!(runner.hp, 40);
+        assert_eq!(runner.origin, runner.translation);
+        assert_eq!(runner.waypoint, 0);
+        assert!(grunt.kind == EnemyKind::Grunt);
+        assert_eq!(grunt.origin, Vec3::new(-100.0, 0.0, 1.0));
+        assert_eq!(grunt.waypoint, 0);
+    }
+
+    #[test]
+    fn migrates_v2_saves() {
+        let v2 = "version 2\ngold 40\nwave 3\n";
+        let snapshot = RunSnapshot::parse(v2).ok().expect("v2 save should parse");
+        assert_eq!((snapshot.gold, snapshot.wave), (40, 3));
+        assert_eq!((snapshot.score, snapshot.towers_sold), (0, 0));
+        assert!(!snapshot.last_stand_used && !snapshot.last_stand_breached);
+        assert_eq!(snapshot.last_stand_remaining, None);
+        assert!(snapshot.rubble.is_empty());
+    }
+
+    #[test]
+    fn migrates_v3_saves() {
+        let v3 = "version 3
+tower 10 20 sniper strongest
+";
+        let snapshot = RunSnapshot::parse(v3).ok().expect("v3 save should parse");
+        assert!(
+            snapshot.towers
+                == [TowerSnapshot {
+                    position: Vec2::new(10.0, 20.0),
+                    kind: TowerKind::Sniper,
+                    strategy: TargetingStrategy::Strongest,
+                    kills: 0,
+                    charge: 0,
+                    hp: TOWER_HP,
+                }]
+        );
+    }
+
+    #[test]
+    fn migrates_v4_saves() {
+        let v4 = "version 4\nwave 5\n";
+        let snapshot = RunSnapshot::parse(v4).ok().expect("v4 save should parse");
+        assert!(snapshot.upcoming.is_none());
+        assert_eq!(snapshot.scout_rerolls, SCOUT_REROLLS);
+    }
+
+    #[test]
+    fn rejects_saves_from_newer_builds() {
+        let newer = format!("version {}\ngold 10\n", SAVE_VERSION + 1);
+        assert!(matches!(
+            RunSnapshot::parse(&newer),
+            Err(SaveError::TooNew(_))
+        ));
+    }
+
+    #[test]
+    fn saves_round_trip() {
+        let mut world = World::new();
+        world.insert_resource(Gold(120));
+        world.insert_resource(Lives(9));
+        world.insert_resource(Shield(2));
+        world.insert_resource(BaseUpgrades {
+            trickle: 2,
+            auto_attack: 1,
+            ..default()
+        });
+        let mut waves = Waves::for_run();
+        waves.number = 6;
+        waves.pending.extend([EnemyKind::Flyer, EnemyKind::Boss]);
+        waves.upcoming = Some(vec![EnemyKind::Runner, EnemyKind::Grunt]);
+        world.insert_resource(waves);
+        world.insert_resource(RunClock(88.25));
+        world.insert_resource(Mutators(5));
+        world.insert_resource(Score(640));
+        world.insert_resource(TowersSold(3));
+        world.insert_resource(ScoutRerolls(1));
+        world.insert_resource(LastStand {
+            timer: Some(Timer::from_seconds(7.5, TimerMode::Once)),
+            used: true,
+            breached: false,
+        });
+        world.spawn((
+            Transform::from_xyz(80.0, 40.0, 0.0),
+            Rubble {
+                kind: TowerKind::Mortar,
+                kills: 11,
+            },
+        ));
+        world.spawn((
+            Transform::from_xyz(50.0, -25.0, 0.0),
+            TowerKind::Frost,
+            TargetingStrategy::Strongest,
+            Kills(14),
+            UltimateCharge(420),
+            Hp(180),
+            Tower,
+        ));
+        world.spawn((Transform::from_xyz(-60.0, 30.0, 0.0), ShieldGenerator));
+        world.spawn((
+            Transform::from_xyz(100.0, 200.0, 1.0),
+            Direction(Vec3::X),
+            Hp(33),
+            EnemyKind::Tank,
+            PathProgress {
+                waypoint: 2,
+                ..PathProgress::new(Vec3::new(-300.0, 0.0, 1.0))
+            },
+            Enemy,
+        ));
+
+        let captured = world
+            .run_system_once(|state: RunState| RunSnapshot::capture(&state))
+            .expect("capture should run");
+        let path = std::env::temp_dir().join(format!("round-trip-{}.sav", std::process::id()));
+        let path = path.to_str().expect("temp path should be utf-8");
+        captured.write(path);
+        let contents = fs::read_to_string(path).expect("save should be written");
+        assert!(contents.starts_with(&format!("version {SAVE_VERSION}\n")));
+        let restored = RunSnapshot::read(path).ok().expect("save should read back");
+        let _ = fs::remove_file(path);
+
+        assert_eq!(restored.serialize(), captured.serialize());
+        assert_eq!(
+            (restored.gold, restored.lives, restored.shield),
+            (120, 9, 2)
+        );
+        assert_eq!(restored.clock, 88.25);
+        assert_eq!(restored.mutators, 5);
+        assert_eq!(restored.base_upgrades, (2, 1));
+        assert_eq!(restored.wave, 6);
+        assert!(restored.pending == [EnemyKind::Flyer, EnemyKind::Boss]);
+        assert!(restored.upcoming == Some(vec![EnemyKind::Runner, EnemyKind::Grunt]));
+        assert_eq!(restored.scout_rerolls, 1);
+        assert!(
+            restored.towers
+                == [TowerSnapshot {
+                    position: Vec2::new(50.0, -25.0),
+                    kind: TowerKind::Frost,
+                    strategy: TargetingStrategy::Strongest,
+                    kills: 14,
+                    charge: 420,
+                    hp: 180,
+                }]
+        );
+        assert_eq!(restored.generators, [Vec2::new(-60.0, 30.0)]);
+        assert!(restored.rubble == [(Vec2::new(80.0, 40.0), TowerKind::Mortar, 11)]);
+        assert_eq!((restored.score, restored.towers_sold), (640, 3));
+        assert!(restored.last_stand_used && !restored.last_stand_breached);
+        assert_eq!(restored.last_stand_remaining, Some(7.5));
+        let [tank] = restored.enemies.as_slice() else {
+            panic!("expected one enemy");
+        };
+        assert!(tank.kind == EnemyKind::Tank);
+        assert_eq!(tank.translation, Vec3::new(100.0, 200.0, 1.0));
+        assert_eq!(tank.hp, 33);
+        assert_eq!(tank.origin, Vec3::new(-300.0, 0.0, 1.0));
+        assert_eq!(tank.waypoint, 2);
+    }
+}
@@ -0,0 +1,101 @@
+use crate::common::{FrameSet, GameRng, Lives, Mutators};
+use crate::enemy::{describe_wave, EnemyKind, Lane, Waves};
+use crate::theme::ThemedText;
+use crate::ui::spawn_toast;
+use bevy::prelude::*;
+
+pub const SCOUT_REROLLS: u32 = 3;
+
+#[derive(Resource)]
+pub struct ScoutRerolls(pub u32);
+
+impl Default for ScoutRerolls {
+    fn default() -> Self {
+        ScoutRerolls(SCOUT_REROLLS)
+    }
+}
+
+#[derive(Component)]
+struct WavePreviewText;
+
+fn setup_wave_preview(mut commands: Commands) {
+    commands.spawn((
+        Text::default(),
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(68.0),
+            left: Val::Px(12.0),
+            ..default()
+        },
+        ThemedText,
+        Interaction::default(),
+        WavePreviewText,
+    ));
+}
+
+fn update_wave_preview(
+    waves: Res<Waves>,
+    rerolls: Res<ScoutRerolls>,
+    mut text: Single<&mut Text, With<WavePreviewText>>,
+) {
+    let Some(upcoming) = waves.upcoming.as_ref().filter(|_| waves.in_break()) else {
+        text.0.clear();
+        return;
+    };
+    text.0 = format!("Wave {}", waves.number + 1);
+    for lane in [Lane::Ground, Lane::Air] {
+        let group: Vec<EnemyKind> = upcoming
+            .iter()
+            .copied()
+            .filter(|kind| kind.lane() == lane)
+            .collect();
+        if !group.is_empty() {
+            text.0
+                .push_str(&format!("  {}: {}", lane.name(), describe_wave(&group)));
+        }
+    }
+    if waves.can_reroll() {
+        text.0
+            .push_str(&format!("  Scout reroll [V] ({} left)", rerolls.0));
+    }
+}
+
+fn reroll_upcoming_wave(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut waves: ResMut<Waves>,
+    mut rerolls: ResMut<ScoutRerolls>,
+    mut rng: ResMut<GameRng>,
+    lives: Res<Lives>,
+    mutators: Res<Mutators>,
+) {
+    if !keys.just_pressed(KeyCode::KeyV) || !waves.in_break() || !waves.can_reroll() {
+        return;
+    }
+    if rerolls.0 == 0 {
+        spawn_toast(&mut commands, "No scout rerolls left");
+        return;
+    }
+    rerolls.0 -= 1;
+    waves.reroll_next(lives.0, *mutators, &mut rng.0);
+    spawn_toast(
+        &mut commands,
+        &format!("Wave rerolled, {} scout rerolls left", rerolls.0),
+    );
+}
+
+pub struct ScoutingPlugin;
+
+impl Plugin for ScoutingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ScoutRerolls>();
+        app.add_systems(Startup, setup_wave_preview);
+        app.add_systems(
+            Update,
+            (
+                reroll_upcoming_wave.in_set(FrameSet::Input),
+                update_wave_preview.in_set(FrameSet::Hud),
+            ),
+        );
+    }
+}
@@ -0,0 +1,159 @@
+use crate::combat::despawn_dead_enemies;
+use crate::common::{Enemy, Hp, ProjectedHp, SimulationSet};
+use crate::enemy::Armor;
+use bevy::prelude::*;
+
+const MAX_SLOW_PERCENT: u32 = 60;
+pub const BURN_PERIOD: f32 = 0.5;
+const POISON_PERIOD: f32 = 1.0;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum StatusKind {
+    ArmorShred(u32),
+    Slowed(u32),
+    Burning(u32),
+    Poisoned(u32),
+    Stunned,
+}
+
+impl StatusKind {
+    fn period(self) -> Option<f32> {
+        match self {
+            StatusKind::Burning(_) => Some(BURN_PERIOD),
+            StatusKind::Poisoned(_) => Some(POISON_PERIOD),
+            StatusKind::ArmorShred(_) | StatusKind::Slowed(_) | StatusKind::Stunned => None,
+        }
+    }
+
+    fn tick_damage(self) -> u32 {
+        match self {
+            StatusKind::Burning(damage) | StatusKind::Poisoned(damage) => damage,
+            StatusKind::ArmorShred(_) | StatusKind::Slowed(_) | StatusKind::Stunned => 0,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum Stacking {
+    Refresh,
+    Stack(usize),
+}
+
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    remaining: Timer,
+    period: Option<Timer>,
+}
+
+#[derive(Component, Default)]
+pub struct StatusEffects(pub Vec<StatusEffect>);
+
+impl StatusEffects {
+    pub fn stacks(&self, kind: StatusKind) -> usize {
+        self.0.iter().filter(|effect| effect.kind == kind).count()
+    }
+
+    pub fn apply(&mut self, kind: StatusKind, seconds: f32, stacking: Stacking) {
+        let max_stacks = match stacking {
+            Stacking::Refresh => 1,
+            Stacking::Stack(max_stacks) => max_stacks,
+        };
+        if self.stacks(kind) >= max_stacks {
+            if let Some(oldest) = self.0.iter_mut().find(|effect| effect.kind == kind) {
+                oldest.remaining.reset();
+            }
+            return;
+        }
+        self.0.push(StatusEffect {
+            kind,
+            remaining: Timer::from_seconds(seconds, TimerMode::Once),
+            period: kind
+                .period()
+                .map(|period| Timer::from_seconds(period, TimerMode::Repeating)),
+        });
+    }
+
+    pub fn armor(&self, base: &Armor) -> u32 {
+        let shred: u32 = self
+            .0
+            .iter()
+            .map(|effect| match effect.kind {
+                StatusKind::ArmorShred(amount) => amount,
+                _ => 0,
+            })
+            .sum();
+        base.0.saturating_sub(shred)
+    }
+
+    pub fn is_slowed(&self) -> bool {
+        self.0
+            .iter()
+            .any(|effect| matches!(effect.kind, StatusKind::Slowed(_)))
+    }
+
+    pub fn is_burning(&self) -> bool {
+        self.0
+            .iter()
+            .any(|effect| matches!(effect.kind, StatusKind::Burning(_)))
+    }
+
+    pub fn is_poisoned(&self) -> bool {
+        self.0
+            .iter()
+            .any(|effect| matches!(effect.kind, StatusKind::Poisoned(_)))
+    }
+
+    pub fn is_stunned(&self) -> bool {
+        self.0
+            .iter()
+            .any(|effect| effect.kind == StatusKind::Stunned)
+    }
+
+    pub fn speed_multiplier(&self) -> f32 {
+        if self.is_stunned() {
+            return 0.0;
+        }
+        let slow: u32 = self
+            .0
+            .iter()
+            .map(|effect| match effect.kind {
+                StatusKind::Slowed(percent) => percent,
+                _ => 0,
+            })
+            .sum();
+        1.0 - slow.min(MAX_SLOW_PERCENT) as f32 / 100.0
+    }
+}
+
+fn tick_status_effects(
+    time: Res<Time>,
+    mut enemies: Query<(&mut StatusEffects, &mut Hp, &mut ProjectedHp), With<Enemy>>,
+) {
+    for (mut effects, mut hp, mut projected_hp) in &mut enemies {
+        let mut damage = 0;
+        effects.0.retain_mut(|effect| {
+            if let Some(period) = &mut effect.period {
+                damage += effect.kind.tick_damage()
+                    * period.tick(time.delta()).times_finished_this_tick();
+            }
+            !effect.remaining.tick(time.delta()).finished()
+        });
+        if damage > 0 {
+            hp.0 = hp.0.saturating_sub(damage);
+            projected_hp.0 = projected_hp.0.saturating_sub(damage);
+        }
+    }
+}
+
+pub struct StatusPlugin;
+
+impl Plugin for StatusPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            tick_status_effects
+                .before(despawn_dead_enemies)
+                .in_set(SimulationSet::Step),
+        );
+    }
+}
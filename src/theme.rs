@@ -0,0 +1,179 @@
+use crate::common::{FrameSet, LoadErrors};
+use crate::loading::Preload;
+use crate::ui::spawn_toast;
+use bevy::prelude::*;
+use bevy::ui::widget::NodeImageMode;
+use std::fs;
+
+const THEME_DIR: &str = "assets/themes";
+const DEFAULT_THEME: &str = "default";
+
+#[derive(Component)]
+pub struct ThemedText;
+
+#[derive(Component)]
+pub struct ThemedPanel(pub Color);
+
+#[derive(Resource)]
+pub struct UiTheme {
+    name: String,
+    text: Option<Color>,
+    panel: Option<Color>,
+    font: Handle<Font>,
+    panel_image: Option<(Handle<Image>, f32)>,
+}
+
+impl Default for UiTheme {
+    fn default() -> Self {
+        UiTheme {
+            name: DEFAULT_THEME.to_string(),
+            text: None,
+            panel: None,
+            font: Handle::default(),
+            panel_image: None,
+        }
+    }
+}
+
+impl UiTheme {
+    fn load(name: &str, asset_server: &AssetServer, errors: &mut LoadErrors) -> Self {
+        let mut theme = UiTheme {
+            name: name.to_string(),
+            ..default()
+        };
+        let path = format!("{THEME_DIR}/{name}.theme");
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(_) if name == DEFAULT_THEME => String::new(),
+            Err(error) => {
+                errors.report(format!("failed to read theme {path}: {error}"));
+                return UiTheme::default();
+            }
+        };
+        for line in text.lines() {
+            let mut fields = line.split_whitespace();
+            let key = fields.next();
+            let asset = fields.clone().next().map(str::to_string);
+            let values: Vec<f32> = fields.filter_map(|field| field.parse().ok()).collect();
+            match (key, values.as_slice(), asset) {
+                (Some("text"), &[r, g, b], _) => theme.text = Some(Color::srgb(r, g, b)),
+                (Some("panel"), &[r, g, b, a], _) => theme.panel = Some(Color::srgba(r, g, b, a)),
+                (Some("font"), [], Some(font)) => theme.font = asset_server.load(font),
+                (Some("panel_image"), &[border], Some(image)) => {
+                    theme.panel_image = Some((asset_server.load(image), border))
+                }
+                (None, _, _) => {}
+                _ => errors.report(format!("skipping bad theme line in {path}: {line}")),
+            }
+        }
+        theme
+    }
+
+    fn available() -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(THEME_DIR)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                (path.extension()? == "theme").then_some(())?;
+                Some(path.file_stem()?.to_str()?.to_string())
+            })
+            .filter(|name| name != DEFAULT_THEME)
+            .collect();
+        names.sort();
+        names.insert(0, DEFAULT_THEME.to_string());
+        names
+    }
+}
+
+fn setup_ui_theme(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut preload: ResMut<Preload>,
+    mut load_errors: ResMut<LoadErrors>,
+) {
+    for name in UiTheme::available() {
+        let theme = UiTheme::load(&name, &asset_server, &mut load_errors);
+        preload.0.push(theme.font.untyped());
+        preload
+            .0
+            .extend(theme.panel_image.map(|(image, _)| image.untyped()));
+    }
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(pair) = args.windows(2).find(|pair| pair[0] == "--theme") {
+        commands.insert_resource(UiTheme::load(&pair[1], &asset_server, &mut load_errors));
+    }
+}
+
+fn cycle_ui_theme(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    asset_server: Res<AssetServer>,
+    mut theme: ResMut<UiTheme>,
+    mut load_errors: ResMut<LoadErrors>,
+) {
+    if !keys.just_pressed(KeyCode::F6) {
+        return;
+    }
+    let names = UiTheme::available();
+    let index = names.iter().position(|name| *name == theme.name);
+    let name = &names[index.map_or(0, |index| (index + 1) % names.len())];
+    *theme = UiTheme::load(name, &asset_server, &mut load_errors);
+    spawn_toast(&mut commands, &format!("Theme: {name}"));
+}
+
+fn apply_ui_theme(
+    mut commands: Commands,
+    theme: Res<UiTheme>,
+    mut texts: Query<(Ref<ThemedText>, &mut TextColor, &mut TextFont)>,
+    mut panels: Query<(Entity, Ref<ThemedPanel>, &mut BackgroundColor)>,
+) {
+    for (marker, mut color, mut font) in &mut texts {
+        if theme.is_changed() || marker.is_added() {
+            color.0 = theme
+                .text
+                .unwrap_or(Color::WHITE)
+                .with_alpha(color.0.alpha());
+            font.font = theme.font.clone();
+        }
+    }
+    for (entity, panel, mut background) in &mut panels {
+        if !theme.is_changed() && !panel.is_added() {
+            continue;
+        }
+        let fill = theme.panel.unwrap_or(panel.0);
+        match &theme.panel_image {
+            Some((image, border)) => {
+                background.0 = Color::NONE;
+                commands.entity(entity).insert(ImageNode {
+                    color: fill,
+                    image_mode: NodeImageMode::Sliced(TextureSlicer {
+                        border: BorderRect::square(*border),
+                        ..default()
+                    }),
+                    ..ImageNode::new(image.clone())
+                });
+            }
+            None => {
+                background.0 = fill;
+                commands.entity(entity).remove::<ImageNode>();
+            }
+        }
+    }
+}
+
+pub struct ThemePlugin;
+
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<UiTheme>();
+        app.add_systems(Startup, setup_ui_theme);
+        app.add_systems(
+            Update,
+            (
+                cycle_ui_theme.in_set(FrameSet::Input),
+                apply_ui_theme.after(cycle_ui_theme),
+            ),
+        );
+    }
+}
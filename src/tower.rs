@@ -0,0 +1,1987 @@
+use crate::combat::{damage_towers_on_contact, enemy_bounds, tower_shoot_target};
+use crate::common::{
+    in_run, Base, Bounty, Damage, Dependents, Enemy, FrameSet, GameAssets, GameState, Gold, Hp,
+    Kills, Lives, MaxHp, Mutators, ProjectedHp, Selected, SimulationSet, Target, Tower,
+};
+use crate::enemy::{configure_waves, Path, PathProgress, Targetable, CORPSE_LAYER};
+use crate::level::Level;
+use crate::loadout::Loadout;
+use crate::projectile::{
+    spawn_projectile, EntityCaps, Projectile, ProjectileKind, PROJECTILE_DAMAGE,
+};
+use crate::run_modes::custom_requested;
+use crate::status::{Stacking, StatusEffects, StatusKind};
+use crate::ui::{
+    navigate_confirm_dialog, open_confirm_dialog, spawn_toast, ConfirmAction, Confirmed, HealthBar,
+};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+
+const TOWER_COST: u32 = 50;
+
+const MARSHAL_STARTING_TOWERS: [Vec2; 1] = [Vec2::new(60.0, 0.0)];
+
+#[derive(Resource, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Commander {
+    #[default]
+    Engineer,
+    Marshal,
+}
+
+impl Commander {
+    pub const ALL: [Commander; 2] = [Commander::Engineer, Commander::Marshal];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Commander::Engineer => "engineer",
+            Commander::Marshal => "marshal",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|commander| commander.name() == name)
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            Commander::Engineer => "Engineer: towers cost 20% less, [E] overclock",
+            Commander::Marshal => "Marshal: +1 starting tower, [E] airstrike",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&c| c == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    pub fn tower_cost(self) -> u32 {
+        match self {
+            Commander::Engineer => TOWER_COST * 4 / 5,
+            Commander::Marshal => TOWER_COST,
+        }
+    }
+
+    pub fn starting_towers(self) -> &'static [Vec2] {
+        match self {
+            Commander::Engineer => &[],
+            Commander::Marshal => &MARSHAL_STARTING_TOWERS,
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct TowersSold(pub u32);
+
+const BASE_SHIELD_CAP: u32 = 5;
+const SHIELD_GENERATOR_BONUS: u32 = 5;
+const SHIELD_GENERATOR_COST: u32 = 150;
+
+#[derive(Component)]
+pub struct UltimateCharge(pub u32);
+
+pub const ULTIMATE_CHARGE: u32 = 1000;
+const VOLLEY_SHOTS: u32 = 3;
+const SALVO_RANGE_MULTIPLIER: f32 = 2.0;
+const HEADSHOT_MULTIPLIER: u32 = 5;
+const CORRODE_SHRED: u32 = 30;
+const CORRODE_POISON: u32 = 6;
+const CORRODE_SECONDS: f32 = 6.0;
+const DEEP_FREEZE_SECONDS: f32 = 3.0;
+const FIRESTORM_BURN: u32 = 8;
+const FIRESTORM_SECONDS: f32 = 5.0;
+const OVERLOAD_STUN_SECONDS: f32 = 1.0;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Ultimate {
+    Barrage,
+    Volley,
+    Corrode,
+    DeepFreeze,
+    Firestorm,
+    Headshot,
+    Salvo,
+    Overload,
+}
+
+impl Ultimate {
+    pub fn name(self) -> &'static str {
+        match self {
+            Ultimate::Barrage => "Barrage",
+            Ultimate::Volley => "Volley",
+            Ultimate::Corrode => "Corrode",
+            Ultimate::DeepFreeze => "Deep freeze",
+            Ultimate::Firestorm => "Firestorm",
+            Ultimate::Headshot => "Headshot",
+            Ultimate::Salvo => "Salvo",
+            Ultimate::Overload => "Overload",
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Range(pub f32);
+
+#[derive(Component)]
+pub struct FireRate(pub f32);
+
+#[derive(Component)]
+pub struct Cooldown(pub Timer);
+
+#[derive(Component, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TowerKind {
+    Cannon,
+    Arrow,
+    Acid,
+    Frost,
+    Mortar,
+    Sniper,
+    Missile,
+    Tesla,
+}
+
+impl TowerKind {
+    pub const ALL: [TowerKind; 8] = [
+        TowerKind::Cannon,
+        TowerKind::Arrow,
+        TowerKind::Acid,
+        TowerKind::Frost,
+        TowerKind::Mortar,
+        TowerKind::Sniper,
+        TowerKind::Missile,
+        TowerKind::Tesla,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TowerKind::Cannon => "cannon",
+            TowerKind::Arrow => "arrow",
+            TowerKind::Acid => "acid",
+            TowerKind::Frost => "frost",
+            TowerKind::Mortar => "mortar",
+            TowerKind::Sniper => "sniper",
+            TowerKind::Missile => "missile",
+            TowerKind::Tesla => "tesla",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.name() == name || kind.projectile().name() == name)
+    }
+
+    pub fn projectile(self) -> ProjectileKind {
+        match self {
+            TowerKind::Cannon => ProjectileKind::Shell,
+            TowerKind::Arrow => ProjectileKind::Bolt,
+            TowerKind::Acid => ProjectileKind::Acid,
+            TowerKind::Frost => ProjectileKind::Frost,
+            TowerKind::Mortar => ProjectileKind::Bomb,
+            TowerKind::Sniper => ProjectileKind::Slug,
+            TowerKind::Missile => ProjectileKind::Missile,
+            TowerKind::Tesla => ProjectileKind::Lightning,
+        }
+    }
+
+    pub fn range(self) -> f32 {
+        match self {
+            TowerKind::Cannon | TowerKind::Acid => 200.0,
+            TowerKind::Arrow => 260.0,
+            TowerKind::Frost => 160.0,
+            TowerKind::Mortar => 240.0,
+            TowerKind::Sniper => 600.0,
+            TowerKind::Missile => 300.0,
+            TowerKind::Tesla => 180.0,
+        }
+    }
+
+    pub fn detection_range(self) -> Option<f32> {
+        (self == TowerKind::Sniper).then_some(DETECTOR_RANGE)
+    }
+
+    pub fn fire_rate(self) -> f32 {
+        match self {
+            TowerKind::Cannon | TowerKind::Acid => 1.0,
+            TowerKind::Arrow => 1.6,
+            TowerKind::Frost => 0.8,
+            TowerKind::Mortar => 0.4,
+            TowerKind::Sniper => 0.15,
+            TowerKind::Missile => 0.6,
+            TowerKind::Tesla => 0.7,
+        }
+    }
+
+    pub fn damage(self) -> u32 {
+        match self {
+            TowerKind::Cannon | TowerKind::Acid | TowerKind::Mortar | TowerKind::Missile => {
+                PROJECTILE_DAMAGE
+            }
+            TowerKind::Arrow => PROJECTILE_DAMAGE / 2,
+            TowerKind::Frost => PROJECTILE_DAMAGE / 5,
+            TowerKind::Sniper => PROJECTILE_DAMAGE * 4,
+            TowerKind::Tesla => PROJECTILE_DAMAGE * 4 / 5,
+        }
+    }
+
+    pub fn ultimate(self) -> Ultimate {
+        match self {
+            TowerKind::Cannon => Ultimate::Barrage,
+            TowerKind::Arrow => Ultimate::Volley,
+            TowerKind::Acid => Ultimate::Corrode,
+            TowerKind::Frost => Ultimate::DeepFreeze,
+            TowerKind::Mortar => Ultimate::Firestorm,
+            TowerKind::Sniper => Ultimate::Headshot,
+            TowerKind::Missile => Ultimate::Salvo,
+            TowerKind::Tesla => Ultimate::Overload,
+        }
+    }
+
+    pub fn cost(self, commander: Commander) -> u32 {
+        let percent = match self {
+            TowerKind::Cannon | TowerKind::Acid => 100,
+            TowerKind::Arrow => 80,
+            TowerKind::Frost => 120,
+            TowerKind::Mortar => 150,
+            TowerKind::Sniper => 160,
+            TowerKind::Missile => 140,
+            TowerKind::Tesla => 130,
+        };
+        commander.tower_cost() * percent / 100
+    }
+
+    pub fn material(self, assets: &GameAssets) -> Handle<ColorMaterial> {
+        match self {
+            TowerKind::Cannon => assets.tower_material.clone(),
+            TowerKind::Arrow => assets.arrow_tower_material.clone(),
+            TowerKind::Acid => assets.acid_tower_material.clone(),
+            TowerKind::Frost => assets.frost_tower_material.clone(),
+            TowerKind::Mortar => assets.mortar_tower_material.clone(),
+            TowerKind::Sniper => assets.sniper_tower_material.clone(),
+            TowerKind::Missile => assets.missile_tower_material.clone(),
+            TowerKind::Tesla => assets.tesla_tower_material.clone(),
+        }
+    }
+}
+
+pub fn spawn_level_towers(commands: &mut Commands, assets: &GameAssets, level: &Level) {
+    for &(position, kind) in &level.starter_towers {
+        spawn_tower_at(commands, assets, position, kind);
+    }
+}
+
+pub fn spawn_tower_at(
+    commands: &mut Commands,
+    assets: &GameAssets,
+    position: Vec2,
+    kind: TowerKind,
+) -> Entity {
+    let translation = position.extend(0.0);
+    let health_bar = commands
+        .spawn((
+            Mesh2d(assets.health_bar_mesh.clone()),
+            MeshMaterial2d(assets.health_bar_material.clone()),
+            Transform::from_translation(translation),
+        ))
+        .id();
+    let tower = commands
+        .spawn((
+            Mesh2d(assets.tower_mesh.clone()),
+            MeshMaterial2d(kind.material(assets)),
+            Transform::from_translation(translation),
+            (
+                Range(kind.range()),
+                FireRate(kind.fire_rate()),
+                Cooldown(Timer::from_seconds(0.2, TimerMode::Repeating)),
+                Damage(kind.damage()),
+            ),
+            Tower,
+            kind,
+            Target(None),
+            UltimateCharge(0),
+            Kills(0),
+            TargetingStrategy::default(),
+            Synergy::default(),
+            Hp(TOWER_HP),
+            MaxHp(TOWER_HP),
+            Dependents(vec![health_bar]),
+        ))
+        .id();
+    commands.entity(health_bar).insert(HealthBar(tower));
+    if let Some(range) = kind.detection_range() {
+        commands.entity(tower).insert(Detector(range));
+    }
+    tower
+}
+
+const DETECTOR_RANGE: f32 = 220.0;
+
+#[derive(Component)]
+pub struct Detector(pub f32);
+
+pub const TOWER_HP: u32 = 300;
+
+const REBUILD_COST_PERCENT: u32 = 40;
+pub const TOWER_HALF_SIZE: f32 = 12.0;
+
+#[derive(Component)]
+pub struct Rubble {
+    pub kind: TowerKind,
+    pub kills: u32,
+}
+
+fn destroy_towers(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    towers: Query<(Entity, &Transform, &Hp, &TowerKind, &Kills), With<Tower>>,
+) {
+    for (entity, transform, hp, &kind, kills) in &towers {
+        if hp.0 > 0 {
+            continue;
+        }
+        commands.entity(entity).despawn();
+        spawn_rubble(
+            &mut commands,
+            &assets,
+            transform.translation.truncate(),
+            kind,
+            kills.0,
+        );
+        spawn_toast(&mut commands, "Tower destroyed");
+    }
+}
+
+pub fn spawn_rubble(
+    commands: &mut Commands,
+    assets: &GameAssets,
+    position: Vec2,
+    kind: TowerKind,
+    kills: u32,
+) {
+    commands.spawn((
+        Mesh2d(assets.tower_mesh.clone()),
+        MeshMaterial2d(assets.rubble_material.clone()),
+        Transform::from_translation(position.extend(CORPSE_LAYER)),
+        Rubble { kind, kills },
+    ));
+}
+
+pub fn rebuild_cost(commander: Commander, kind: TowerKind) -> u32 {
+    kind.cost(commander) * REBUILD_COST_PERCENT / 100
+}
+
+fn rebuild_rubble(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    assets: Res<GameAssets>,
+    commander: Res<Commander>,
+    mut gold: ResMut<Gold>,
+    rubble: Single<(Entity, &Transform, &Rubble), With<Selected>>,
+) {
+    if !keys.just_pressed(KeyCode::KeyR) {
+        return;
+    }
+    let (entity, transform, rubble) = rubble.into_inner();
+    let cost = rebuild_cost(*commander, rubble.kind);
+    if gold.0 < cost {
+        spawn_toast(&mut commands, "Not enough gold");
+        return;
+    }
+    gold.0 -= cost;
+    let tower = spawn_tower_at(
+        &mut commands,
+        &assets,
+        transform.translation.truncate(),
+        rubble.kind,
+    );
+    commands
+        .entity(tower)
+        .insert((Kills(rubble.kills), Selected));
+    commands.entity(entity).despawn();
+}
+
+#[derive(Component)]
+pub struct ShieldGenerator;
+
+pub fn shield_cap(generators: usize) -> u32 {
+    BASE_SHIELD_CAP + generators as u32 * SHIELD_GENERATOR_BONUS
+}
+
+pub fn spawn_shield_generator(commands: &mut Commands, assets: &GameAssets, position: Vec2) {
+    commands.spawn((
+        Mesh2d(assets.tower_mesh.clone()),
+        MeshMaterial2d(assets.shield_generator_material.clone()),
+        Transform::from_translation(position.extend(0.0)),
+        ShieldGenerator,
+    ));
+}
+
+fn buy_shield_generator(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    window: Single<&Window>,
+    camera: Single<(&Camera, &GlobalTransform)>,
+    assets: Res<GameAssets>,
+    mut gold: ResMut<Gold>,
+    occupancy: Occupancy,
+) {
+    if !keys.just_pressed(KeyCode::KeyG) {
+        return;
+    }
+    let (camera, camera_transform) = camera.into_inner();
+    let Some(position) = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor).ok())
+    else {
+        return;
+    };
+
+    let cell = occupancy.snap(position);
+    if let Err(error) = occupancy.check(cell) {
+        spawn_toast(
+            &mut commands,
+            &format!("Can't build here: {}", error.reason()),
+        );
+        return;
+    }
+    if gold.0 < SHIELD_GENERATOR_COST {
+        spawn_toast(&mut commands, "Not enough gold");
+        return;
+    }
+    gold.0 -= SHIELD_GENERATOR_COST;
+    spawn_shield_generator(&mut commands, &assets, cell);
+    spawn_toast(&mut commands, "Shield generator built");
+}
+
+#[derive(Component, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum TargetingStrategy {
+    #[default]
+    Closest,
+    First,
+    Last,
+    Strongest,
+    Weakest,
+    Smart,
+}
+
+#[derive(Clone, Copy)]
+pub struct ThreatWeights {
+    pub progress: f32,
+    pub health: f32,
+    pub bounty: f32,
+}
+
+impl ThreatWeights {
+    pub fn for_kind(kind: ProjectileKind) -> Self {
+        match kind {
+            ProjectileKind::Shell => ThreatWeights {
+                progress: 100.0,
+                health: 0.1,
+                bounty: 1.0,
+            },
+            ProjectileKind::Bolt => ThreatWeights {
+                progress: 150.0,
+                health: 0.05,
+                bounty: 0.5,
+            },
+            ProjectileKind::Acid => ThreatWeights {
+                progress: 60.0,
+                health: 0.0,
+                bounty: 2.0,
+            },
+            ProjectileKind::Frost => ThreatWeights {
+                progress: 200.0,
+                health: 0.0,
+                bounty: 0.0,
+            },
+            ProjectileKind::Bomb => ThreatWeights {
+                progress: 80.0,
+                health: 0.05,
+                bounty: 1.0,
+            },
+            ProjectileKind::Slug => ThreatWeights {
+                progress: 50.0,
+                health: -0.2,
+                bounty: 0.5,
+            },
+            ProjectileKind::Missile => ThreatWeights {
+                progress: 120.0,
+                health: 0.1,
+                bounty: 1.0,
+            },
+            ProjectileKind::Lightning => ThreatWeights {
+                progress: 90.0,
+                health: 0.0,
+                bounty: 1.0,
+            },
+        }
+    }
+
+    fn threat(&self, progress: &PathProgress, projected_hp: &ProjectedHp, bounty: &Bounty) -> f32 {
+        progress.value() * self.progress - projected_hp.0 as f32 * self.health
+            + bounty.0 as f32 * self.bounty
+    }
+}
+
+impl TargetingStrategy {
+    pub const ALL: [TargetingStrategy; 6] = [
+        TargetingStrategy::Closest,
+        TargetingStrategy::First,
+        TargetingStrategy::Last,
+        TargetingStrategy::Strongest,
+        TargetingStrategy::Weakest,
+        TargetingStrategy::Smart,
+    ];
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL
+            .iter()
+            .position(|&strategy| strategy == self)
+            .unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TargetingStrategy::Closest => "closest",
+            TargetingStrategy::First => "first",
+            TargetingStrategy::Last => "last",
+            TargetingStrategy::Strongest => "strongest",
+            TargetingStrategy::Weakest => "weakest",
+            TargetingStrategy::Smart => "smart",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|strategy| strategy.name() == name)
+    }
+
+    pub fn allows_focus(self) -> bool {
+        self != TargetingStrategy::Smart
+    }
+
+    pub fn pick<'a>(
+        self,
+        tower: Vec3,
+        range: f32,
+        weights: ThreatWeights,
+        enemies: impl Iterator<
+            Item = (
+                Entity,
+                &'a Transform,
+                &'a ProjectedHp,
+                &'a PathProgress,
+                &'a Bounty,
+            ),
+        >,
+    ) -> Option<Entity> {
+        let mut best: Option<(Entity, f32)> = None;
+        for (entity, transform, projected_hp, progress, bounty) in enemies {
+            let distance = transform.translation.distance(tower);
+            if projected_hp.0 == 0 || distance >= range {
+                continue;
+            }
+            let score = match self {
+                TargetingStrategy::Closest => distance,
+                TargetingStrategy::First => -progress.value(),
+                TargetingStrategy::Last => progress.value(),
+                TargetingStrategy::Strongest => -(projected_hp.0 as f32),
+                TargetingStrategy::Weakest => projected_hp.0 as f32,
+                TargetingStrategy::Smart => -weights.threat(progress, projected_hp, bounty),
+            };
+            if best.is_none_or(|(_, best_score)| score < best_score) {
+                best = Some((entity, score));
+            }
+        }
+        best.map(|(entity, _)| entity)
+    }
+}
+
+fn cycle_targeting_strategy(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    enemies: Query<
+        (Entity, &Transform, &ProjectedHp, &PathProgress, &Bounty),
+        (With<Enemy>, Targetable),
+    >,
+    tower: Single<
+        (
+            &Transform,
+            &Range,
+            &TowerKind,
+            &mut Target,
+            &mut TargetingStrategy,
+        ),
+        With<Selected>,
+    >,
+) {
+    if !keys.just_pressed(KeyCode::KeyM) {
+        return;
+    }
+    let (transform, range, &kind, mut target, mut strategy) = tower.into_inner();
+    *strategy = strategy.next();
+    let weights = ThreatWeights::for_kind(kind.projectile());
+    target.0 = strategy.pick(transform.translation, range.0, weights, enemies.iter());
+    spawn_toast(&mut commands, &format!("Targeting {}", strategy.name()));
+}
+
+pub fn tower_dps(damage: &Damage, fire_rate: &FireRate, cooldown: &Cooldown) -> f32 {
+    damage.0 as f32 * fire_rate.0 / cooldown.0.duration().as_secs_f32()
+}
+
+#[derive(Component)]
+pub struct PlannedTower(pub TowerKind);
+
+#[derive(Resource)]
+pub struct BuildKind(pub TowerKind);
+
+impl Default for BuildKind {
+    fn default() -> Self {
+        BuildKind(TowerKind::Cannon)
+    }
+}
+
+pub fn cycle_build_kind(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    loadout: Res<Loadout>,
+    mut build_kind: ResMut<BuildKind>,
+) {
+    if !keys.just_pressed(KeyCode::Tab) {
+        return;
+    }
+    build_kind.0 = loadout.next_after(build_kind.0);
+    spawn_toast(
+        &mut commands,
+        &format!("Building {} towers", build_kind.0.name()),
+    );
+}
+
+#[derive(Resource, Default)]
+pub struct PlanningMode(pub bool);
+
+pub const PLACEMENT_CELL: f32 = 40.0;
+const DEFAULT_MAP_BOUNDS: Rect = Rect {
+    min: Vec2::new(-1600.0, -900.0),
+    max: Vec2::new(1600.0, 900.0),
+};
+const PATH_SAMPLE_STEP: f32 = PLACEMENT_CELL / 4.0;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Tile {
+    Buildable,
+    Path,
+}
+
+#[derive(Resource)]
+pub struct GridMap {
+    origin: Vec2,
+    columns: i32,
+    rows: i32,
+    tiles: Vec<Tile>,
+    occupied: Vec<bool>,
+    enemies: Vec<bool>,
+}
+
+impl GridMap {
+    pub fn new(bounds: Rect) -> Self {
+        let origin = (bounds.min / PLACEMENT_CELL).floor() * PLACEMENT_CELL;
+        let size = ((bounds.max - origin) / PLACEMENT_CELL).ceil();
+        let (columns, rows) = (size.x as i32, size.y as i32);
+        let count = (columns * rows).max(0) as usize;
+        GridMap {
+            origin,
+            columns,
+            rows,
+            tiles: vec![Tile::Buildable; count],
+            occupied: vec![false; count],
+            enemies: vec![false; count],
+        }
+    }
+
+    pub fn cell(&self, position: Vec2) -> IVec2 {
+        ((position - self.origin) / PLACEMENT_CELL)
+            .floor()
+            .as_ivec2()
+    }
+
+    pub fn center(&self, cell: IVec2) -> Vec2 {
+        self.origin + (cell.as_vec2() + 0.5) * PLACEMENT_CELL
+    }
+
+    pub fn snap(&self, position: Vec2) -> Vec2 {
+        self.center(self.cell(position))
+    }
+
+    fn index(&self, cell: IVec2) -> Option<usize> {
+        let inside = (0..self.columns).contains(&cell.x) && (0..self.rows).contains(&cell.y);
+        inside.then(|| (cell.y * self.columns + cell.x) as usize)
+    }
+
+    fn cells(&self) -> impl Iterator<Item = Vec2> + '_ {
+        (0..self.rows)
+            .flat_map(move |y| (0..self.columns).map(move |x| self.center(IVec2::new(x, y))))
+    }
+
+    pub fn path_cells(&self) -> impl Iterator<Item = IVec2> + '_ {
+        (0..self.rows)
+            .flat_map(move |y| (0..self.columns).map(move |x| IVec2::new(x, y)))
+            .filter(|&cell| {
+                self.index(cell)
+                    .is_some_and(|index| self.tiles[index] == Tile::Path)
+            })
+    }
+
+    pub fn route_cells<'a>(&'a self, route: &'a [Vec2]) -> impl Iterator<Item = IVec2> + 'a {
+        sample_route(route, PATH_SAMPLE_STEP).map(|point| self.cell(point))
+    }
+
+    fn mark_path(&mut self, route: &[Vec2]) {
+        for point in sample_route(route, PATH_SAMPLE_STEP) {
+            if let Some(index) = self.index(self.cell(point)) {
+                self.tiles[index] = Tile::Path;
+            }
+        }
+    }
+
+    fn footprint(&self, position: Vec2) -> impl Iterator<Item = usize> + '_ {
+        let cell = self.cell(position);
+        (-1..=1)
+            .flat_map(move |dx| (-1..=1).map(move |dy| cell + IVec2::new(dx, dy)))
+            .filter(move |&neighbour| self.center(neighbour).distance(position) < PLACEMENT_CELL)
+            .filter_map(|neighbour| self.index(neighbour))
+    }
+
+    fn check(&self, position: Vec2) -> Result<(), PlacementError> {
+        self.check_with(position, |index| self.occupied[index])
+    }
+
+    fn check_with(
+        &self,
+        position: Vec2,
+        occupied: impl Fn(usize) -> bool,
+    ) -> Result<(), PlacementError> {
+        let index = self
+            .index(self.cell(position))
+            .ok_or(PlacementError::OutOfBounds)?;
+        if self.tiles[index] == Tile::Path {
+            return Err(PlacementError::OnPath);
+        }
+        if occupied(index) {
+            return Err(PlacementError::Occupied);
+        }
+        if self.enemies[index] {
+            return Err(PlacementError::EnemyInTheWay);
+        }
+        Ok(())
+    }
+}
+
+fn sample_route(route: &[Vec2], step: f32) -> impl Iterator<Item = Vec2> + '_ {
+    route.windows(2).flat_map(move |segment| {
+        let steps = (segment[0].distance(segment[1]) / step).ceil() as usize;
+        (0..=steps).map(move |i| segment[0].lerp(segment[1], i as f32 / steps.max(1) as f32))
+    })
+}
+
+impl Default for GridMap {
+    fn default() -> Self {
+        GridMap::new(DEFAULT_MAP_BOUNDS)
+    }
+}
+
+pub fn setup_grid_map(mut commands: Commands, level: Option<Res<Level>>, path: Res<Path>) {
+    let bounds = level
+        .as_ref()
+        .and_then(|level| level.camera_bounds)
+        .unwrap_or(DEFAULT_MAP_BOUNDS);
+    let mut grid = GridMap::new(bounds);
+    if !path.0.is_empty() {
+        let base = level.map_or(Vec2::ZERO, |level| level.base);
+        let route: Vec<Vec2> = path.0.iter().copied().chain([base]).collect();
+        grid.mark_path(&route);
+    }
+    commands.insert_resource(grid);
+}
+
+fn update_grid_occupancy(
+    mut grid: ResMut<GridMap>,
+    structures: Query<
+        &Transform,
+        Or<(
+            With<Tower>,
+            With<Rubble>,
+            With<ShieldGenerator>,
+            With<Base>,
+            With<PlannedTower>,
+        )>,
+    >,
+    enemies: Query<&Transform, With<Enemy>>,
+) {
+    let grid = &mut *grid;
+    let mut occupied = std::mem::take(&mut grid.occupied);
+    let mut enemy_cells = std::mem::take(&mut grid.enemies);
+    occupied.fill(false);
+    enemy_cells.fill(false);
+    for transform in &structures {
+        for index in grid.footprint(transform.translation.truncate()) {
+            occupied[index] = true;
+        }
+    }
+    for transform in &enemies {
+        for index in grid.footprint(transform.translation.truncate()) {
+            enemy_cells[index] = true;
+        }
+    }
+    grid.occupied = occupied;
+    grid.enemies = enemy_cells;
+}
+
+#[derive(Clone, Copy)]
+pub enum PlacementError {
+    OutOfBounds,
+    OnPath,
+    Occupied,
+    EnemyInTheWay,
+    CantAfford,
+    TooManyKinds,
+}
+
+impl PlacementError {
+    pub fn reason(self) -> &'static str {
+        match self {
+            PlacementError::OutOfBounds => "outside the map",
+            PlacementError::OnPath => "on the enemy path",
+            PlacementError::Occupied => "occupied",
+            PlacementError::EnemyInTheWay => "enemy in the way",
+            PlacementError::CantAfford => "can't afford",
+            PlacementError::TooManyKinds => "tower kind limit reached",
+        }
+    }
+}
+
+#[derive(SystemParam)]
+pub struct Occupancy<'w, 's> {
+    towers: Query<
+        'w,
+        's,
+        &'static Transform,
+        Or<(With<Tower>, With<Rubble>, With<ShieldGenerator>, With<Base>)>,
+    >,
+    planned: Query<'w, 's, &'static PlannedTower>,
+    kinds: Query<'w, 's, &'static TowerKind, With<Tower>>,
+    grid: Res<'w, GridMap>,
+    mutators: Res<'w, Mutators>,
+}
+
+impl Occupancy<'_, '_> {
+    fn snap(&self, position: Vec2) -> Vec2 {
+        self.grid.snap(position)
+    }
+
+    fn check(&self, cell: Vec2) -> Result<(), PlacementError> {
+        self.grid.check(cell)
+    }
+
+    fn check_plan(&self, cell: Vec2) -> Result<(), PlacementError> {
+        self.grid.check_with(cell, |index| {
+            self.towers.iter().any(|transform| {
+                self.grid
+                    .footprint(transform.translation.truncate())
+                    .any(|occupied| occupied == index)
+            })
+        })
+    }
+
+    pub fn check_build(&self, cell: Vec2, kind: TowerKind) -> Result<(), PlacementError> {
+        self.check_plan(cell)?;
+        self.check_kind(kind)
+    }
+
+    pub fn cell_covering(&self, route: &[Vec2], kind: TowerKind) -> Option<Vec2> {
+        let goal = *route.last()?;
+        let samples: Vec<Vec2> = if route.len() == 1 {
+            vec![goal]
+        } else {
+            sample_route(route, PLACEMENT_CELL).collect()
+        };
+        self.grid
+            .cells()
+            .map(|cell| {
+                let covered = samples
+                    .iter()
+                    .filter(|sample| sample.distance(cell) <= kind.range())
+                    .count();
+                (cell, covered)
+            })
+            .filter(|&(cell, covered)| covered > 0 && self.check_build(cell, kind).is_ok())
+            .max_by(|(a, covered_a), (b, covered_b)| {
+                covered_a
+                    .cmp(covered_b)
+                    .then_with(|| goal.distance(*b).total_cmp(&goal.distance(*a)))
+            })
+            .map(|(cell, _)| cell)
+    }
+
+    fn check_kind(&self, kind: TowerKind) -> Result<(), PlacementError> {
+        let Some(limit) = self.mutators.tower_kind_limit() else {
+            return Ok(());
+        };
+        let mut kinds: HashSet<TowerKind> = self
+            .kinds
+            .iter()
+            .copied()
+            .chain(self.planned.iter().map(|planned| planned.0))
+            .collect();
+        kinds.insert(kind);
+        if kinds.len() > limit {
+            return Err(PlacementError::TooManyKinds);
+        }
+        Ok(())
+    }
+}
+
+#[derive(SystemParam)]
+pub struct Placement<'w, 's> {
+    pub gold: Res<'w, Gold>,
+    pub commander: Res<'w, Commander>,
+    pub occupancy: Occupancy<'w, 's>,
+}
+
+impl Placement<'_, '_> {
+    pub fn snap(&self, position: Vec2) -> Vec2 {
+        self.occupancy.snap(position)
+    }
+
+    pub fn validate(&self, cell: Vec2, kind: TowerKind) -> Result<(), PlacementError> {
+        self.occupancy.check(cell)?;
+        self.occupancy.check_kind(kind)?;
+        let queued: u32 = self
+            .occupancy
+            .planned
+            .iter()
+            .map(|planned| planned.0.cost(*self.commander))
+            .sum();
+        if self.gold.0 < queued + kind.cost(*self.commander) {
+            return Err(PlacementError::CantAfford);
+        }
+        Ok(())
+    }
+}
+
+fn toggle_planning_mode(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    state: Res<State<GameState>>,
+    mut planning: ResMut<PlanningMode>,
+) {
+    if keys.just_pressed(KeyCode::KeyB) && *state.get() == GameState::Paused {
+        planning.0 = !planning.0;
+        let message = if planning.0 {
+            "Planning mode"
+        } else {
+            "Planning mode off"
+        };
+        spawn_toast(&mut commands, message);
+    }
+}
+
+fn queue_planned_tower(
+    mut commands: Commands,
+    mouse: Res<ButtonInput<MouseButton>>,
+    planning: Res<PlanningMode>,
+    window: Single<&Window>,
+    camera: Single<(&Camera, &GlobalTransform)>,
+    assets: Res<GameAssets>,
+    build_kind: Res<BuildKind>,
+    placement: Placement,
+) {
+    if !planning.0 || !mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let (camera, camera_transform) = camera.into_inner();
+    let Some(position) = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor).ok())
+    else {
+        return;
+    };
+
+    let cell = placement.snap(position);
+    if let Err(error) = placement.validate(cell, build_kind.0) {
+        spawn_toast(
+            &mut commands,
+            &format!("Can't build here: {}", error.reason()),
+        );
+        return;
+    }
+    commands.spawn((
+        Mesh2d(assets.tower_mesh.clone()),
+        MeshMaterial2d(assets.planned_tower_material.clone()),
+        Transform::from_translation(cell.extend(0.0)),
+        PlannedTower(build_kind.0),
+    ));
+}
+
+fn place_tower(
+    mut commands: Commands,
+    mouse: Res<ButtonInput<MouseButton>>,
+    planning: Res<PlanningMode>,
+    window: Single<&Window>,
+    camera: Single<(&Camera, &GlobalTransform)>,
+    assets: Res<GameAssets>,
+    build_kind: Res<BuildKind>,
+    commander: Res<Commander>,
+    mut gold: ResMut<Gold>,
+    occupancy: Occupancy,
+) {
+    if planning.0 || !mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let (camera, camera_transform) = camera.into_inner();
+    let Some(position) = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor).ok())
+    else {
+        return;
+    };
+    if occupancy
+        .towers
+        .iter()
+        .any(|transform| transform.translation.truncate().distance(position) <= TOWER_SELECT_RADIUS)
+    {
+        return;
+    }
+
+    let cell = occupancy.snap(position);
+    if let Err(error) = occupancy
+        .check(cell)
+        .and_then(|()| occupancy.check_kind(build_kind.0))
+    {
+        spawn_toast(
+            &mut commands,
+            &format!("Can't build here: {}", error.reason()),
+        );
+        return;
+    }
+    let cost = build_kind.0.cost(*commander);
+    if gold.0 < cost {
+        spawn_toast(&mut commands, "Not enough gold");
+        return;
+    }
+    gold.0 -= cost;
+    spawn_tower_at(&mut commands, &assets, cell, build_kind.0);
+}
+
+pub fn execute_planned_towers(
+    mut commands: Commands,
+    state: Res<State<GameState>>,
+    mut planning: ResMut<PlanningMode>,
+    planned: Query<(Entity, &Transform, &PlannedTower)>,
+    assets: Res<GameAssets>,
+    commander: Res<Commander>,
+    mut gold: ResMut<Gold>,
+    occupancy: Occupancy,
+) {
+    if *state.get() != GameState::Playing {
+        return;
+    }
+    planning.0 = false;
+    for (entity, transform, planned) in &planned {
+        commands.entity(entity).despawn();
+        let cell = transform.translation.truncate();
+        let cost = planned.0.cost(*commander);
+        let checked = occupancy
+            .check_plan(cell)
+            .and_then(|()| occupancy.check_kind(planned.0))
+            .and_then(|()| {
+                if gold.0 < cost {
+                    Err(PlacementError::CantAfford)
+                } else {
+                    Ok(())
+                }
+            });
+        if let Err(error) = checked {
+            spawn_toast(
+                &mut commands,
+                &format!("Planned {} dropped: {}", planned.0.name(), error.reason()),
+            );
+            continue;
+        }
+        gold.0 -= cost;
+        spawn_tower_at(&mut commands, &assets, cell, planned.0);
+    }
+}
+
+const EXTRA_LIFE_COST: u32 = 60;
+const GOLD_TRICKLE_COST: u32 = 80;
+const AUTO_ATTACK_COST: u32 = 100;
+const MAX_BASE_UPGRADE_LEVEL: u32 = 3;
+const BASE_ATTACK_RANGE: f32 = 150.0;
+const BASE_ATTACK_INTERVAL: f32 = 1.0;
+const BASE_ATTACK_DAMAGE: u32 = 20;
+
+#[derive(Resource)]
+pub struct BaseUpgrades {
+    pub trickle: u32,
+    pub auto_attack: u32,
+    pub trickle_timer: Timer,
+    pub attack_timer: Timer,
+}
+
+impl Default for BaseUpgrades {
+    fn default() -> Self {
+        Self {
+            trickle: 0,
+            auto_attack: 0,
+            trickle_timer: Timer::from_seconds(1.0, TimerMode::Repeating),
+            attack_timer: Timer::from_seconds(BASE_ATTACK_INTERVAL, TimerMode::Repeating),
+        }
+    }
+}
+
+impl BaseUpgrades {
+    pub fn trickle_within(&self, seconds: f32) -> u32 {
+        let first = self.trickle_timer.remaining_secs();
+        if self.trickle == 0 || seconds < first {
+            return 0;
+        }
+        let payouts = 1 + ((seconds - first) / self.trickle_timer.duration().as_secs_f32()) as u32;
+        payouts * self.trickle
+    }
+
+    fn level_text(level: u32, cost: u32) -> String {
+        if level >= MAX_BASE_UPGRADE_LEVEL {
+            format!("{level}/{MAX_BASE_UPGRADE_LEVEL}")
+        } else {
+            format!("{level}/{MAX_BASE_UPGRADE_LEVEL} ({cost}g)")
+        }
+    }
+
+    pub fn panel_text(&self) -> String {
+        format!(
+            "HQ  [1] Extra life ({EXTRA_LIFE_COST}g)  [2] Gold trickle {}  [3] Auto-attack {}",
+            Self::level_text(self.trickle, GOLD_TRICKLE_COST * (self.trickle + 1)),
+            Self::level_text(self.auto_attack, AUTO_ATTACK_COST * (self.auto_attack + 1)),
+        )
+    }
+}
+
+fn buy_base_upgrade(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut gold: ResMut<Gold>,
+    mut lives: ResMut<Lives>,
+    mut upgrades: ResMut<BaseUpgrades>,
+    _base: Single<(), (With<Base>, With<Selected>)>,
+) {
+    let upgrades = &mut *upgrades;
+    let (level, cost) = if keys.just_pressed(KeyCode::Digit1) {
+        (None, EXTRA_LIFE_COST)
+    } else if keys.just_pressed(KeyCode::Digit2) {
+        let cost = GOLD_TRICKLE_COST * (upgrades.trickle + 1);
+        (Some(&mut upgrades.trickle), cost)
+    } else if keys.just_pressed(KeyCode::Digit3) {
+        let cost = AUTO_ATTACK_COST * (upgrades.auto_attack + 1);
+        (Some(&mut upgrades.auto_attack), cost)
+    } else {
+        return;
+    };
+    if level
+        .as_ref()
+        .is_some_and(|level| **level >= MAX_BASE_UPGRADE_LEVEL)
+    {
+        spawn_toast(&mut commands, "Already at max level");
+        return;
+    }
+    if gold.0 < cost {
+        spawn_toast(&mut commands, "Not enough gold");
+        return;
+    }
+    gold.0 -= cost;
+    match level {
+        Some(level) => *level += 1,
+        None => lives.0 += 1,
+    }
+}
+
+fn base_auto_attack(
+    mut commands: Commands,
+    time: Res<Time>,
+    assets: Res<GameAssets>,
+    mut upgrades: ResMut<BaseUpgrades>,
+    base: Single<(Entity, &Transform), With<Base>>,
+    mut enemies: Query<(Entity, &Transform, &mut ProjectedHp), (With<Enemy>, Targetable)>,
+) {
+    if upgrades.auto_attack == 0 {
+        return;
+    }
+    let delta = time.delta().mul_f32(upgrades.auto_attack as f32);
+    if !upgrades.attack_timer.tick(delta).just_finished() {
+        return;
+    }
+    let (base, base_transform) = base.into_inner();
+    let Some((enemy, enemy_transform, mut projected_hp)) = enemies
+        .iter_mut()
+        .filter(|(_, transform, projected_hp)| {
+            projected_hp.0 > 0
+                && transform.translation.distance(base_transform.translation) < BASE_ATTACK_RANGE
+        })
+        .min_by(|(_, a, _), (_, b, _)| {
+            let a = a.translation.distance_squared(base_transform.translation);
+            let b = b.translation.distance_squared(base_transform.translation);
+            a.total_cmp(&b)
+        })
+    else {
+        return;
+    };
+    spawn_projectile(
+        &mut commands,
+        &assets,
+        time.elapsed_secs(),
+        ProjectileKind::Bolt,
+        Damage(BASE_ATTACK_DAMAGE),
+        base,
+        base_transform.translation,
+        enemy,
+        enemy_transform.translation,
+    );
+    projected_hp.0 = projected_hp.0.saturating_sub(BASE_ATTACK_DAMAGE);
+}
+
+pub fn base_gold_trickle(
+    time: Res<Time>,
+    mut upgrades: ResMut<BaseUpgrades>,
+    mut gold: ResMut<Gold>,
+) {
+    if upgrades.trickle == 0 {
+        return;
+    }
+    if upgrades.trickle_timer.tick(time.delta()).just_finished() {
+        gold.0 += upgrades.trickle;
+    }
+}
+
+const VETERAN_KILLS: u32 = 10;
+
+fn sell_tower(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut gold: ResMut<Gold>,
+    mut towers_sold: ResMut<TowersSold>,
+    commander: Res<Commander>,
+    mutators: Res<Mutators>,
+    tower: Single<(Entity, &TowerKind, &Kills), (With<Tower>, With<Selected>)>,
+) {
+    if !keys.just_pressed(KeyCode::KeyS) {
+        return;
+    }
+    if !mutators.selling_allowed() {
+        spawn_toast(&mut commands, "Selling is disabled");
+        return;
+    }
+    let (entity, &kind, kills) = tower.into_inner();
+    if kills.0 >= VETERAN_KILLS {
+        open_confirm_dialog(&mut commands, ConfirmAction::SellTower(entity));
+        return;
+    }
+    sell(
+        &mut commands,
+        &mut gold,
+        &mut towers_sold,
+        kind.cost(*commander),
+        entity,
+    );
+}
+
+fn confirm_sell_tower(
+    mut commands: Commands,
+    mut confirmed: EventReader<Confirmed>,
+    mut gold: ResMut<Gold>,
+    mut towers_sold: ResMut<TowersSold>,
+    commander: Res<Commander>,
+    towers: Query<&TowerKind, With<Tower>>,
+) {
+    for Confirmed(action) in confirmed.read() {
+        if let &ConfirmAction::SellTower(entity) = action {
+            if let Ok(kind) = towers.get(entity) {
+                sell(
+                    &mut commands,
+                    &mut gold,
+                    &mut towers_sold,
+                    kind.cost(*commander),
+                    entity,
+                );
+            }
+        }
+    }
+}
+
+fn sell(
+    commands: &mut Commands,
+    gold: &mut Gold,
+    towers_sold: &mut TowersSold,
+    cost: u32,
+    tower: Entity,
+) {
+    let refund = cost / 2;
+    gold.0 += refund;
+    towers_sold.0 += 1;
+    commands.entity(tower).despawn();
+    spawn_toast(commands, &format!("Sold for {refund}"));
+}
+
+const TOWER_SELECT_RADIUS: f32 = 20.0;
+
+#[derive(Component)]
+pub struct FocusTarget;
+
+fn mark_focus_target(
+    mut commands: Commands,
+    mouse: Res<ButtonInput<MouseButton>>,
+    planning: Res<PlanningMode>,
+    window: Single<&Window>,
+    camera: Single<(&Camera, &GlobalTransform)>,
+    enemies: Query<(Entity, &Transform, Has<FocusTarget>), With<Enemy>>,
+) {
+    if planning.0 || !mouse.just_pressed(MouseButton::Right) {
+        return;
+    }
+    let (camera, camera_transform) = camera.into_inner();
+    let Some(position) = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor).ok())
+    else {
+        return;
+    };
+
+    let clicked = enemies
+        .iter()
+        .find(|(_, transform, _)| enemy_bounds(transform).closest_point(position) == position);
+    for (enemy, _, focused) in &enemies {
+        if focused {
+            commands.entity(enemy).remove::<FocusTarget>();
+        }
+    }
+    if let Some((enemy, _, false)) = clicked {
+        commands.entity(enemy).insert(FocusTarget);
+        spawn_toast(&mut commands, "Focus fire");
+    }
+}
+
+fn select_tower(
+    mut commands: Commands,
+    mouse: Res<ButtonInput<MouseButton>>,
+    planning: Res<PlanningMode>,
+    window: Single<&Window>,
+    camera: Single<(&Camera, &GlobalTransform)>,
+    towers: Query<(Entity, &Transform), Or<(With<Tower>, With<Rubble>, With<Base>)>>,
+    selected: Query<Entity, With<Selected>>,
+) {
+    let button = if planning.0 {
+        MouseButton::Right
+    } else {
+        MouseButton::Left
+    };
+    if !mouse.just_pressed(button) {
+        return;
+    }
+    let (camera, camera_transform) = camera.into_inner();
+    let Some(position) = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor).ok())
+    else {
+        return;
+    };
+
+    for entity in &selected {
+        commands.entity(entity).remove::<Selected>();
+    }
+    if let Some((tower, _)) = towers.iter().find(|(_, transform)| {
+        transform.translation.truncate().distance(position) <= TOWER_SELECT_RADIUS
+    }) {
+        commands.entity(tower).insert(Selected);
+    }
+}
+
+fn trigger_ultimate(
+    mut commands: Commands,
+    time: Res<Time>,
+    keys: Res<ButtonInput<KeyCode>>,
+    assets: Res<GameAssets>,
+    caps: Res<EntityCaps>,
+    projectiles: Query<(), With<Projectile>>,
+    tower: Single<
+        (
+            Entity,
+            &Transform,
+            &Range,
+            &TowerKind,
+            &Damage,
+            &mut UltimateCharge,
+        ),
+        With<Selected>,
+    >,
+    mut enemies: Query<
+        (
+            Entity,
+            &Transform,
+            &Hp,
+            &mut ProjectedHp,
+            &mut StatusEffects,
+        ),
+        With<Enemy>,
+    >,
+) {
+    let (tower, tower_transform, range, &kind, &damage, mut charge) = tower.into_inner();
+    if !keys.just_pressed(KeyCode::KeyQ) || charge.0 < ULTIMATE_CHARGE {
+        return;
+    }
+
+    charge.0 = 0;
+    let ultimate = kind.ultimate();
+    let reach = match ultimate {
+        Ultimate::Salvo => range.0 * SALVO_RANGE_MULTIPLIER,
+        Ultimate::Headshot => f32::INFINITY,
+        _ => range.0,
+    };
+    let mut targets: Vec<_> = enemies
+        .iter_mut()
+        .filter(|(_, transform, ..)| {
+            transform.translation.distance(tower_transform.translation) < reach
+        })
+        .collect();
+    if ultimate == Ultimate::Headshot {
+        targets.sort_by_key(|(_, _, hp, ..)| std::cmp::Reverse(hp.0));
+        targets.truncate(1);
+    }
+    let (shots, damage) = match ultimate {
+        Ultimate::Volley => (VOLLEY_SHOTS, damage),
+        Ultimate::Headshot => (1, Damage(damage.0 * HEADSHOT_MULTIPLIER)),
+        Ultimate::Corrode | Ultimate::DeepFreeze => (0, damage),
+        _ => (1, damage),
+    };
+
+    let mut projectile_count = projectiles.iter().count();
+    for (enemy, enemy_transform, _, mut projected_hp, mut effects) in targets {
+        match ultimate {
+            Ultimate::Corrode => {
+                effects.apply(
+                    StatusKind::ArmorShred(CORRODE_SHRED),
+                    CORRODE_SECONDS,
+                    Stacking::Refresh,
+                );
+                effects.apply(
+                    StatusKind::Poisoned(CORRODE_POISON),
+                    CORRODE_SECONDS,
+                    Stacking::Refresh,
+                );
+            }
+            Ultimate::DeepFreeze => {
+                effects.apply(StatusKind::Stunned, DEEP_FREEZE_SECONDS, Stacking::Refresh)
+            }
+            Ultimate::Firestorm => effects.apply(
+                StatusKind::Burning(FIRESTORM_BURN),
+                FIRESTORM_SECONDS,
+                Stacking::Refresh,
+            ),
+            Ultimate::Overload => effects.apply(
+                StatusKind::Stunned,
+                OVERLOAD_STUN_SECONDS,
+                Stacking::Refresh,
+            ),
+            Ultimate::Barrage | Ultimate::Volley | Ultimate::Headshot | Ultimate::Salvo => {}
+        }
+        for _ in 0..shots {
+            if !caps.allows_spawn(projectile_count, caps.projectiles) {
+                break;
+            }
+            projectile_count += 1;
+            spawn_projectile(
+                &mut commands,
+                &assets,
+                time.elapsed_secs(),
+                kind.projectile(),
+                damage,
+                tower,
+                tower_transform.translation,
+                enemy,
+                enemy_transform.translation,
+            );
+            projected_hp.0 = projected_hp.0.saturating_sub(damage.0);
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum PairingBonus {
+    FireRate(f32),
+    SplashRadius(f32),
+}
+
+const PAIRINGS: [(TowerKind, TowerKind, PairingBonus); 4] = [
+    (
+        TowerKind::Mortar,
+        TowerKind::Frost,
+        PairingBonus::SplashRadius(0.2),
+    ),
+    (
+        TowerKind::Tesla,
+        TowerKind::Acid,
+        PairingBonus::FireRate(0.2),
+    ),
+    (
+        TowerKind::Missile,
+        TowerKind::Arrow,
+        PairingBonus::FireRate(0.2),
+    ),
+    (
+        TowerKind::Arrow,
+        TowerKind::Arrow,
+        PairingBonus::FireRate(0.15),
+    ),
+];
+
+fn pairing_bonus(kind: TowerKind, neighbour: TowerKind) -> Option<PairingBonus> {
+    PAIRINGS
+        .iter()
+        .find(|&&(paired, with, _)| paired == kind && with == neighbour)
+        .map(|&(_, _, bonus)| bonus)
+}
+
+#[derive(Component, Default)]
+pub struct Synergy {
+    pub links: Vec<Entity>,
+    fire_rate_bonus: f32,
+    splash_bonus: f32,
+}
+
+impl Synergy {
+    pub fn from_neighbours(
+        kind: TowerKind,
+        neighbours: impl IntoIterator<Item = (Entity, TowerKind)>,
+    ) -> Self {
+        let mut synergy = Synergy::default();
+        for (neighbour, neighbour_kind) in neighbours {
+            let Some(bonus) = pairing_bonus(kind, neighbour_kind) else {
+                continue;
+            };
+            match bonus {
+                PairingBonus::FireRate(bonus) => synergy.fire_rate_bonus += bonus,
+                PairingBonus::SplashRadius(bonus) => synergy.splash_bonus += bonus,
+            }
+            synergy.links.push(neighbour);
+        }
+        synergy
+    }
+
+    pub fn fire_rate(&self, kind: TowerKind) -> f32 {
+        kind.fire_rate() * (1.0 + self.fire_rate_bonus)
+    }
+
+    pub fn splash_radius(&self, kind: ProjectileKind) -> Option<f32> {
+        kind.splash_radius()
+            .map(|radius| radius * (1.0 + self.splash_bonus))
+    }
+}
+
+pub fn update_tower_synergies(
+    added: Query<(), Added<Tower>>,
+    mut removed: RemovedComponents<Tower>,
+    grid: Res<GridMap>,
+    mut towers: Query<(Entity, &Transform, &TowerKind, &mut FireRate, &mut Synergy), With<Tower>>,
+) {
+    if added.is_empty() && removed.read().count() == 0 {
+        return;
+    }
+
+    let cells: Vec<(Entity, IVec2, TowerKind)> = towers
+        .iter()
+        .map(|(entity, transform, &kind, _, _)| {
+            (entity, grid.cell(transform.translation.truncate()), kind)
+        })
+        .collect();
+    for (entity, transform, &kind, mut fire_rate, mut synergy) in &mut towers {
+        let cell = grid.cell(transform.translation.truncate());
+        let neighbours = cells
+            .iter()
+            .filter(|&&(other, other_cell, _)| {
+                other != entity && (other_cell - cell).abs().element_sum() == 1
+            })
+            .map(|&(other, _, other_kind)| (other, other_kind));
+        *synergy = Synergy::from_neighbours(kind, neighbours);
+        fire_rate.0 = synergy.fire_rate(kind);
+    }
+}
+
+const OVERCLOCK_SECONDS: f32 = 5.0;
+const OVERCLOCK_FIRE_RATE: f32 = 2.0;
+const AIRSTRIKE_DAMAGE: u32 = 100;
+const COMMANDER_ABILITY_COOLDOWN: f32 = 30.0;
+
+#[derive(Resource)]
+pub struct CommanderAbility {
+    pub cooldown: Timer,
+    pub overclock: Option<Timer>,
+}
+
+impl Default for CommanderAbility {
+    fn default() -> Self {
+        let mut cooldown = Timer::from_seconds(COMMANDER_ABILITY_COOLDOWN, TimerMode::Once);
+        cooldown.tick(cooldown.duration());
+        CommanderAbility {
+            cooldown,
+            overclock: None,
+        }
+    }
+}
+
+impl CommanderAbility {
+    pub fn fire_rate_multiplier(&self) -> f32 {
+        if self.overclock.is_some() {
+            OVERCLOCK_FIRE_RATE
+        } else {
+            1.0
+        }
+    }
+}
+
+pub fn spawn_starting_towers(commands: &mut Commands, assets: &GameAssets, commander: Commander) {
+    for &position in commander.starting_towers() {
+        spawn_tower_at(commands, assets, position, TowerKind::Cannon);
+    }
+}
+
+fn setup_commander(mut commands: Commands, assets: Res<GameAssets>, commander: Res<Commander>) {
+    if !custom_requested() {
+        spawn_starting_towers(&mut commands, &assets, *commander);
+    }
+}
+
+fn tick_commander_ability(time: Res<Time>, mut ability: ResMut<CommanderAbility>) {
+    ability.cooldown.tick(time.delta());
+    if let Some(overclock) = ability.overclock.as_mut() {
+        if overclock.tick(time.delta()).finished() {
+            ability.overclock = None;
+        }
+    }
+}
+
+fn use_commander_ability(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    commander: Res<Commander>,
+    mut ability: ResMut<CommanderAbility>,
+    mut enemies: Query<(&mut Hp, &mut ProjectedHp), With<Enemy>>,
+) {
+    if !keys.just_pressed(KeyCode::KeyE) {
+        return;
+    }
+    if !ability.cooldown.finished() {
+        spawn_toast(&mut commands, "Commander ability is recharging");
+        return;
+    }
+
+    match *commander {
+        Commander::Engineer => {
+            ability.overclock = Some(Timer::from_seconds(OVERCLOCK_SECONDS, TimerMode::Once));
+        }
+        Commander::Marshal => {
+            for (mut hp, mut projected_hp) in &mut enemies {
+                hp.0 = hp.0.saturating_sub(AIRSTRIKE_DAMAGE);
+                projected_hp.0 = projected_hp.0.saturating_sub(AIRSTRIKE_DAMAGE);
+            }
+        }
+    }
+    ability.cooldown.reset();
+}
+
+pub struct TowerPlugin;
+
+impl Plugin for TowerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BaseUpgrades>();
+        app.init_resource::<TowersSold>();
+        app.init_resource::<CommanderAbility>();
+        app.init_resource::<PlanningMode>();
+        app.init_resource::<BuildKind>();
+        app.init_resource::<GridMap>();
+        app.add_systems(
+            Startup,
+            (setup_commander, setup_grid_map.after(configure_waves)),
+        );
+        app.add_systems(
+            FixedUpdate,
+            (
+                tick_commander_ability,
+                base_auto_attack.after(tower_shoot_target),
+                destroy_towers.after(damage_towers_on_contact),
+            )
+                .in_set(SimulationSet::Step),
+        );
+        app.add_systems(
+            Update,
+            (
+                (sell_tower, cycle_targeting_strategy)
+                    .chain()
+                    .run_if(in_run)
+                    .before(navigate_confirm_dialog),
+                confirm_sell_tower.after(navigate_confirm_dialog),
+                update_tower_synergies,
+                (
+                    toggle_planning_mode,
+                    queue_planned_tower,
+                    place_tower,
+                    cycle_build_kind,
+                    execute_planned_towers,
+                    select_tower,
+                    mark_focus_target,
+                    trigger_ultimate,
+                    rebuild_rubble,
+                    buy_shield_generator,
+                    buy_base_upgrade,
+                    use_commander_ability,
+                )
+                    .chain()
+                    .in_set(FrameSet::Input),
+                update_grid_occupancy.before(FrameSet::Input),
+            ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::projectile::SPLASH_RADIUS;
+
+    #[test]
+    fn plans_are_checked_against_structures_built_since() {
+        use bevy::ecs::system::RunSystemOnce;
+
+        let mut world = World::new();
+        world.init_resource::<GridMap>();
+        world.init_resource::<Mutators>();
+        let grid = world.resource::<GridMap>();
+        let (open, built) = (
+            grid.snap(Vec2::new(200.0, 0.0)),
+            grid.snap(Vec2::new(0.0, 200.0)),
+        );
+        world.spawn((
+            Transform::from_translation(open.extend(0.0)),
+            PlannedTower(TowerKind::Arrow),
+        ));
+        world.spawn((
+            Transform::from_translation(built.extend(0.0)),
+            PlannedTower(TowerKind::Frost),
+        ));
+        world.spawn((
+            Transform::from_translation(built.extend(0.0)),
+            Rubble {
+                kind: TowerKind::Cannon,
+                kills: 0,
+            },
+        ));
+        world.run_system_once(update_grid_occupancy).unwrap();
+
+        let (open, built) = world
+            .run_system_once(move |occupancy: Occupancy| {
+                (occupancy.check_plan(open), occupancy.check_plan(built))
+            })
+            .unwrap();
+        assert!(open.is_ok());
+        assert!(matches!(built, Err(PlacementError::Occupied)));
+    }
+
+    #[test]
+    fn auto_build_covers_the_path_without_blocking_it() {
+        use bevy::ecs::system::RunSystemOnce;
+
+        let route = [Vec2::new(-400.0, 0.0), Vec2::ZERO];
+        let mut world = World::new();
+        let mut grid = GridMap::default();
+        grid.mark_path(&route);
+        world.insert_resource(grid);
+        world.init_resource::<Mutators>();
+        world.spawn((Transform::default(), Base));
+
+        let cell = world
+            .run_system_once(move |occupancy: Occupancy| {
+                occupancy.cell_covering(&route, TowerKind::Cannon)
+            })
+            .unwrap()
+            .expect("a buildable cell should cover the path");
+        let grid = world.resource::<GridMap>();
+        assert!(grid.check(cell).is_ok());
+        assert_eq!(grid.snap(cell), cell);
+        assert!(cell.distance(Vec2::ZERO) >= PLACEMENT_CELL);
+        assert!(cell.y.abs() <= TowerKind::Cannon.range());
+    }
+
+    #[test]
+    fn trickle_forecast_counts_payouts_in_the_window() {
+        let mut upgrades = BaseUpgrades::default();
+        assert_eq!(upgrades.trickle_within(10.0), 0);
+        upgrades.trickle = 2;
+        upgrades
+            .trickle_timer
+            .tick(std::time::Duration::from_secs_f32(0.25));
+        assert_eq!(upgrades.trickle_within(0.5), 0);
+        assert_eq!(upgrades.trickle_within(0.75), 2);
+        assert_eq!(upgrades.trickle_within(3.0), 6);
+    }
+
+    #[test]
+    fn unlisted_pairings_give_no_bonus() {
+        for kind in TowerKind::ALL {
+            let synergy = Synergy::from_neighbours(kind, []);
+            assert_eq!(synergy.fire_rate(kind), kind.fire_rate());
+        }
+        let sniper =
+            Synergy::from_neighbours(TowerKind::Sniper, [(Entity::from_raw(1), TowerKind::Tesla)]);
+        assert_eq!(
+            sniper.fire_rate(TowerKind::Sniper),
+            TowerKind::Sniper.fire_rate()
+        );
+        assert!(sniper.links.is_empty());
+    }
+
+    fn pick_from(strategy: TargetingStrategy, enemies: &[(Vec3, u32, f32, u32)]) -> Option<Entity> {
+        let components: Vec<_> = enemies
+            .iter()
+            .map(|&(position, hp, fraction, bounty)| {
+                let progress = PathProgress {
+                    fraction,
+                    ..PathProgress::new(Vec3::ZERO)
+                };
+                (
+                    Transform::from_translation(position),
+                    ProjectedHp(hp),
+                    progress,
+                    Bounty(bounty),
+                )
+            })
+            .collect();
+        strategy.pick(
+            Vec3::ZERO,
+            100.0,
+            ThreatWeights::for_kind(ProjectileKind::Shell),
+            components
+                .iter()
+                .enumerate()
+                .map(|(index, (transform, hp, progress, bounty))| {
+                    (
+                        Entity::from_raw(index as u32),
+                        transform,
+                        hp,
+                        progress,
+                        bounty,
+                    )
+                }),
+        )
+    }
+
+    const SPREAD: [(Vec3, u32, f32, u32); 3] = [
+        (Vec3::new(20.0, 0.0, 0.0), 50, 0.5, 5),
+        (Vec3::new(60.0, 0.0, 0.0), 200, 0.8, 5),
+        (Vec3::new(0.0, 80.0, 0.0), 10, 0.2, 5),
+    ];
+
+    #[test]
+    fn closest_picks_nearest_enemy() {
+        assert_eq!(
+            pick_from(TargetingStrategy::Closest, &SPREAD),
+            Some(Entity::from_raw(0))
+        );
+    }
+
+    #[test]
+    fn first_picks_enemy_furthest_along_path() {
+        assert_eq!(
+            pick_from(TargetingStrategy::First, &SPREAD),
+            Some(Entity::from_raw(1))
+        );
+    }
+
+    #[test]
+    fn last_picks_enemy_least_along_path() {
+        assert_eq!(
+            pick_from(TargetingStrategy::Last, &SPREAD),
+            Some(Entity::from_raw(2))
+        );
+    }
+
+    #[test]
+    fn strongest_picks_highest_projected_hp() {
+        assert_eq!(
+            pick_from(TargetingStrategy::Strongest, &SPREAD),
+            Some(Entity::from_raw(1))
+        );
+    }
+
+    #[test]
+    fn weakest_picks_lowest_projected_hp() {
+        assert_eq!(
+            pick_from(TargetingStrategy::Weakest, &SPREAD),
+            Some(Entity::from_raw(2))
+        );
+    }
+
+    #[test]
+    fn smart_weighs_bounty_against_progress() {
+        let enemies = [
+            (Vec3::new(20.0, 0.0, 0.0), 50, 0.5, 5),
+            (Vec3::new(40.0, 0.0, 0.0), 50, 0.4, 400),
+        ];
+        assert_eq!(
+            pick_from(TargetingStrategy::Smart, &enemies),
+            Some(Entity::from_raw(1))
+        );
+    }
+
+    #[test]
+    fn strategies_skip_dead_and_out_of_range_enemies() {
+        let enemies = [
+            (Vec3::new(20.0, 0.0, 0.0), 10, 0.1, 5),
+            (Vec3::new(30.0, 0.0, 0.0), 0, 0.9, 5),
+            (Vec3::new(500.0, 0.0, 0.0), 900, 0.95, 5),
+        ];
+        for strategy in TargetingStrategy::ALL {
+            assert_eq!(pick_from(strategy, &enemies), Some(Entity::from_raw(0)));
+        }
+    }
+
+    #[test]
+    fn strategy_names_round_trip() {
+        for strategy in TargetingStrategy::ALL {
+            assert_eq!(
+                TargetingStrategy::from_name(strategy.name()),
+                Some(strategy)
+            );
+        }
+    }
+
+    #[test]
+    fn frost_widens_mortar_splash() {
+        let frost = (Entity::from_raw(1), TowerKind::Frost);
+        let paired = Synergy::from_neighbours(TowerKind::Mortar, [frost]);
+        let radius = paired.splash_radius(ProjectileKind::Bomb).unwrap();
+        assert!((radius - SPLASH_RADIUS * 1.2).abs() < 1e-4);
+        assert_eq!(
+            paired.fire_rate(TowerKind::Mortar),
+            TowerKind::Mortar.fire_rate()
+        );
+        assert_eq!(paired.links, [frost.0]);
+
+        let cannon = Synergy::from_neighbours(TowerKind::Cannon, [frost]);
+        assert_eq!(
+            cannon.fire_rate(TowerKind::Cannon),
+            TowerKind::Cannon.fire_rate()
+        );
+        assert!(cannon.links.is_empty());
+    }
+}
@@ -0,0 +1,88 @@
+use crate::cinematic::SlowMotion;
+use crate::common::{simulating, GameState};
+use crate::enemy::Waves;
+use crate::tower::PlanningMode;
+use crate::ui::{spawn_toast, KillCam};
+use bevy::prelude::*;
+
+const COMBAT_SPEED: f32 = 4.0;
+
+#[derive(States, Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum TurnPhase {
+    #[default]
+    Realtime,
+    Planning,
+    Combat,
+}
+
+fn turn_based_requested() -> bool {
+    std::env::args().any(|arg| arg == "--turn-based")
+}
+
+fn start_turn_based(mut next_phase: ResMut<NextState<TurnPhase>>) {
+    if turn_based_requested() {
+        next_phase.set(TurnPhase::Planning);
+    }
+}
+
+fn enter_planning_phase(
+    mut commands: Commands,
+    state: Res<State<GameState>>,
+    mut planning: ResMut<PlanningMode>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+) {
+    virtual_time.set_relative_speed(1.0);
+    if *state.get() == GameState::Playing {
+        commands.set_state(GameState::Paused);
+    }
+    planning.0 = true;
+    spawn_toast(
+        &mut commands,
+        "Planning phase: queue towers, unpause to fight",
+    );
+}
+
+fn start_combat_phase(mut next_phase: ResMut<NextState<TurnPhase>>) {
+    next_phase.set(TurnPhase::Combat);
+}
+
+fn enter_combat_phase(mut commands: Commands, mut waves: ResMut<Waves>) {
+    let remaining = waves.break_timer.remaining();
+    waves.break_timer.tick(remaining);
+    spawn_toast(&mut commands, "Combat phase");
+}
+
+fn drive_combat_phase(
+    waves: Res<Waves>,
+    kill_cam: Res<KillCam>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    mut next_phase: ResMut<NextState<TurnPhase>>,
+) {
+    if waves.in_break() {
+        next_phase.set(TurnPhase::Planning);
+    } else if kill_cam.timer.is_none() && virtual_time.relative_speed() != COMBAT_SPEED {
+        virtual_time.set_relative_speed(COMBAT_SPEED);
+    }
+}
+
+pub struct TurnBasedPlugin;
+
+impl Plugin for TurnBasedPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<TurnPhase>();
+        app.add_systems(Startup, start_turn_based);
+        app.add_systems(OnEnter(TurnPhase::Planning), enter_planning_phase);
+        app.add_systems(OnEnter(TurnPhase::Combat), enter_combat_phase);
+        app.add_systems(
+            Update,
+            (
+                start_combat_phase.run_if(in_state(TurnPhase::Planning).and(simulating)),
+                drive_combat_phase.run_if(
+                    in_state(TurnPhase::Combat)
+                        .and(in_state(GameState::Playing))
+                        .and(not(resource_exists::<SlowMotion>)),
+                ),
+            ),
+        );
+    }
+}
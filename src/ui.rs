@@ -0,0 +1,1599 @@
+use crate::accessibility::VisualAccessibility;
+use crate::combat::{
+    effective_damage, enemy_bounds, BossKilled, ChainArc, EnemyReachedBase, ProjectileHit,
+    ACID_SHRED, BURN_DAMAGE, FROST_SLOW, POISON_DAMAGE,
+};
+use crate::common::{
+    Base, Bounty, Damage, Enemy, FrameSet, GameAssets, Gold, Hp, Kills, Lives, LoadErrors, MaxHp,
+    Mutators, RunClock, Selected, Shield, Tower, HEALTH_BAR_WIDTH,
+};
+use crate::economy::BountyConfig;
+use crate::enemy::{
+    AirPath, Armor, Corpse, EnemyKind, EnemySpawnTimer, Enrage, NextSpawn, Path, Resistances,
+    Revealed, WaveStarted, Waves,
+};
+use crate::level::Level;
+use crate::loadout::Loadout;
+use crate::projectile::{FiredBy, Lobbed, ProjectileKind, CHAIN_JUMPS, SPLASH_RADIUS};
+use crate::run_end::LastStand;
+use crate::status::{StatusEffects, StatusKind, BURN_PERIOD};
+use crate::theme::{ThemedPanel, ThemedText};
+use crate::tower::{
+    cycle_build_kind, execute_planned_towers, rebuild_cost, shield_cap, tower_dps,
+    update_tower_synergies, BaseUpgrades, BuildKind, Commander, Cooldown, FireRate, FocusTarget,
+    GridMap, Placement, PlannedTower, Range, Rubble, ShieldGenerator, Synergy, TargetingStrategy,
+    TowerKind, UltimateCharge, PLACEMENT_CELL, ULTIMATE_CHARGE,
+};
+use bevy::asset::AssetLoadFailedEvent;
+use bevy::input::gamepad::{GamepadRumbleIntensity, GamepadRumbleRequest};
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+use rand::prelude::*;
+use std::time::Duration;
+
+fn debuff_color(kind: StatusKind) -> Option<Color> {
+    match kind {
+        StatusKind::ArmorShred(_) => Some(Color::hsl(90., 0.9, 0.5)),
+        StatusKind::Poisoned(_) => Some(Color::hsl(290., 0.8, 0.6)),
+        StatusKind::Burning(_) => Some(Color::hsl(25., 1.0, 0.55)),
+        StatusKind::Stunned => Some(Color::hsl(55., 1.0, 0.6)),
+        StatusKind::Slowed(_) => None,
+    }
+}
+
+fn draw_debuff_icons(
+    mut gizmos: Gizmos,
+    enemies: Query<(&Transform, &StatusEffects), With<Enemy>>,
+) {
+    for (transform, effects) in &enemies {
+        let colors = effects
+            .0
+            .iter()
+            .filter_map(|effect| debuff_color(effect.kind));
+        for (stack, color) in colors.enumerate() {
+            let offset = Vec2::new(-8.0 + stack as f32 * 4.0, 14.0);
+            gizmos.circle_2d(transform.translation.truncate() + offset, 1.5, color);
+        }
+    }
+}
+
+const FOCUS_MARKER_HEIGHT: f32 = 22.0;
+
+fn draw_focus_marker(
+    mut gizmos: Gizmos,
+    enemies: Query<&Transform, (With<Enemy>, With<FocusTarget>)>,
+) {
+    for transform in &enemies {
+        let tip = transform.translation.truncate() + Vec2::Y * FOCUS_MARKER_HEIGHT;
+        let color = Color::hsl(0., 1.0, 0.55);
+        gizmos.line_2d(tip, tip + Vec2::new(-5.0, 8.0), color);
+        gizmos.line_2d(tip, tip + Vec2::new(5.0, 8.0), color);
+        gizmos.line_2d(tip + Vec2::new(-5.0, 8.0), tip + Vec2::new(5.0, 8.0), color);
+    }
+}
+
+#[derive(Component)]
+pub struct HealthBar(pub Entity);
+
+#[derive(Component)]
+struct EnrageBar;
+
+fn setup_enrage_bar(mut commands: Commands, level: Option<Res<Level>>) {
+    if level.is_none_or(|level| level.enrage_after.is_none()) {
+        return;
+    }
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(12.0),
+                left: Val::Percent(35.0),
+                width: Val::Percent(30.0),
+                height: Val::Px(6.0),
+                ..default()
+            },
+            BackgroundColor(Color::hsla(0., 0., 0.2, 0.8)),
+        ))
+        .with_child((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                ..default()
+            },
+            BackgroundColor(Color::hsl(40., 0.9, 0.5)),
+            EnrageBar,
+        ));
+}
+
+const ENRAGE_STROBE_HZ: f32 = 6.0;
+
+fn update_enrage_bar(
+    level: Option<Res<Level>>,
+    enrage: Res<Enrage>,
+    real_time: Res<Time<Real>>,
+    visuals: Res<VisualAccessibility>,
+    bar: Single<(&mut Node, &mut BackgroundColor), With<EnrageBar>>,
+) {
+    let Some(limit) = level.and_then(|level| level.enrage_after) else {
+        return;
+    };
+    let (mut node, mut color) = bar.into_inner();
+    let remaining = (1.0 - enrage.elapsed / limit).clamp(0.0, 1.0);
+    node.width = Val::Percent(if enrage.stacks > 0 {
+        100.0
+    } else {
+        remaining * 100.0
+    });
+    let strobe_off =
+        visuals.strobes && (real_time.elapsed_secs() * ENRAGE_STROBE_HZ) as u32 % 2 == 1;
+    color.0 = if enrage.stacks > 0 && !strobe_off {
+        Color::hsl(0., 0.9, 0.5)
+    } else if enrage.stacks > 0 {
+        Color::hsl(0., 0.9, 0.25)
+    } else {
+        Color::hsl(40., 0.9, 0.5)
+    };
+}
+
+#[derive(Component)]
+struct BossBarPanel;
+
+#[derive(Component)]
+struct BossBar;
+
+fn setup_boss_bar(mut commands: Commands) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(28.0),
+                left: Val::Percent(30.0),
+                width: Val::Percent(40.0),
+                height: Val::Px(10.0),
+                ..default()
+            },
+            BackgroundColor(Color::hsla(0., 0., 0.2, 0.8)),
+            Visibility::Hidden,
+            BossBarPanel,
+        ))
+        .with_child((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                ..default()
+            },
+            BackgroundColor(Color::hsl(320., 0.9, 0.45)),
+            BossBar,
+        ));
+}
+
+fn update_boss_bar(
+    enemies: Query<(&EnemyKind, &Hp, &MaxHp), With<Enemy>>,
+    panel: Single<&mut Visibility, With<BossBarPanel>>,
+    bar: Single<&mut Node, With<BossBar>>,
+) {
+    let boss = enemies
+        .iter()
+        .find(|(kind, ..)| **kind == EnemyKind::Warlord);
+    let mut visibility = panel.into_inner();
+    let Some((_, hp, max_hp)) = boss else {
+        visibility.set_if_neq(Visibility::Hidden);
+        return;
+    };
+    visibility.set_if_neq(Visibility::Visible);
+    bar.into_inner().width = Val::Percent(100.0 * hp.0 as f32 / max_hp.0 as f32);
+}
+
+const FRAME_BUDGET: f32 = 1.0 / 50.0;
+const FRAME_HEADROOM: f32 = 0.7;
+const FRAME_SMOOTHING: f32 = 0.05;
+
+#[derive(Resource, Default)]
+pub struct FrameWatchdog {
+    pub average: f32,
+    pub degraded: bool,
+}
+
+fn watch_frame_budget(
+    mut commands: Commands,
+    real_time: Res<Time<Real>>,
+    mut watchdog: ResMut<FrameWatchdog>,
+    corpses: Query<Entity, With<Corpse>>,
+) {
+    watchdog.average += (real_time.delta_secs() - watchdog.average) * FRAME_SMOOTHING;
+    if !watchdog.degraded && watchdog.average > FRAME_BUDGET {
+        watchdog.degraded = true;
+        for corpse in &corpses {
+            commands.entity(corpse).despawn();
+        }
+        info!("frame budget exceeded, reducing visuals");
+    } else if watchdog.degraded && watchdog.average < FRAME_BUDGET * FRAME_HEADROOM {
+        watchdog.degraded = false;
+        info!("frame budget recovered, restoring visuals");
+    }
+}
+
+fn full_visuals(watchdog: Res<FrameWatchdog>) -> bool {
+    !watchdog.degraded
+}
+
+pub fn update_health_bars(
+    mut health_bars: Query<(&HealthBar, &mut Transform)>,
+    enemies: Query<(&Transform, &Hp, &MaxHp), Without<HealthBar>>,
+) {
+    for (health_bar, mut transform) in &mut health_bars {
+        if let Ok((enemy_transform, hp, max_hp)) = enemies.get(health_bar.0) {
+            let fraction = hp.0 as f32 / max_hp.0 as f32;
+            transform.translation = enemy_transform.translation
+                + Vec3::new(
+                    -HEALTH_BAR_WIDTH * (1.0 - fraction) / 2.0,
+                    10.0 * enemy_transform.scale.y,
+                    1.0,
+                );
+            transform.scale.x = fraction;
+        }
+    }
+}
+
+pub trait Lens: Send + Sync + 'static {
+    type Target: Component;
+
+    fn lerp(&self, target: &mut Self::Target, ratio: f32);
+}
+
+#[derive(Component)]
+pub struct Tween<T: Lens> {
+    pub lens: T,
+    pub timer: Timer,
+    pub easing: EaseFunction,
+}
+
+impl<T: Lens> Tween<T> {
+    pub fn new(lens: T, seconds: f32, easing: EaseFunction) -> Self {
+        Tween {
+            lens,
+            timer: Timer::from_seconds(seconds, TimerMode::Once),
+            easing,
+        }
+    }
+}
+
+#[derive(Event)]
+struct TweenCompleted(Entity);
+
+pub struct TextAlphaLens {
+    pub start: f32,
+    pub end: f32,
+}
+
+impl Lens for TextAlphaLens {
+    type Target = TextColor;
+
+    fn lerp(&self, target: &mut TextColor, ratio: f32) {
+        target.0.set_alpha(self.start.lerp(self.end, ratio));
+    }
+}
+
+fn animate_tweens<T: Lens, C: Default + Send + Sync + 'static>(
+    mut commands: Commands,
+    time: Res<Time<C>>,
+    mut completed: EventWriter<TweenCompleted>,
+    mut query: Query<(Entity, &mut Tween<T>, &mut T::Target)>,
+) {
+    for (entity, mut tween, mut target) in &mut query {
+        tween.timer.tick(time.delta());
+        let ratio = EasingCurve::new(0.0, 1.0, tween.easing).sample_clamped(tween.timer.fraction());
+        tween.lens.lerp(&mut target, ratio);
+
+        if tween.timer.finished() {
+            commands.entity(entity).remove::<Tween<T>>();
+            completed.send(TweenCompleted(entity));
+        }
+    }
+}
+
+pub struct ScaleLens {
+    pub start: Vec3,
+    pub end: Vec3,
+}
+
+impl Lens for ScaleLens {
+    type Target = Transform;
+
+    fn lerp(&self, target: &mut Transform, ratio: f32) {
+        target.scale = self.start.lerp(self.end, ratio);
+    }
+}
+
+#[derive(Component)]
+pub struct DespawnOnTweenComplete;
+
+fn report_failed_assets<A: Asset>(
+    mut failures: EventReader<AssetLoadFailedEvent<A>>,
+    mut load_errors: ResMut<LoadErrors>,
+) {
+    for failure in failures.read() {
+        load_errors.report(format!(
+            "failed to load {}: {}",
+            failure.path, failure.error
+        ));
+    }
+}
+
+fn show_load_errors(mut commands: Commands, mut load_errors: ResMut<LoadErrors>) {
+    if !load_errors.has_pending() {
+        return;
+    }
+    let errors = load_errors.take();
+    let message = match errors.len() {
+        1 => errors[0].clone(),
+        count => format!("{} (+{} more, see log)", errors[0], count - 1),
+    };
+    spawn_toast(&mut commands, &message);
+}
+
+pub fn spawn_toast(commands: &mut Commands, message: &str) -> Entity {
+    commands
+        .spawn((
+            Text::new(message),
+            TextColor(Color::WHITE),
+            ThemedText,
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(12.0),
+                right: Val::Px(12.0),
+                ..default()
+            },
+            DespawnOnTweenComplete,
+            Tween::new(
+                TextAlphaLens {
+                    start: 1.0,
+                    end: 0.0,
+                },
+                1.5,
+                EaseFunction::QuadraticIn,
+            ),
+        ))
+        .id()
+}
+
+#[derive(Component)]
+pub struct WaveBanner;
+
+const PLACEMENT_HOTKEYS: [KeyCode; 6] = [
+    KeyCode::Tab,
+    KeyCode::KeyG,
+    KeyCode::KeyR,
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+];
+
+#[derive(Resource, Default)]
+struct BufferedHotkeys {
+    pending: Vec<KeyCode>,
+    replayed: Vec<KeyCode>,
+}
+
+fn guard_gameplay_input(
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    mut mouse: ResMut<ButtonInput<MouseButton>>,
+    mut buffered: ResMut<BufferedHotkeys>,
+    interactions: Query<&Interaction>,
+    banners: Query<(), With<WaveBanner>>,
+    dialog: Option<Res<ConfirmDialog>>,
+) {
+    if dialog.is_some()
+        || interactions
+            .iter()
+            .any(|interaction| *interaction != Interaction::None)
+    {
+        mouse.clear_just_pressed(MouseButton::Left);
+    }
+    if dialog.is_some() {
+        for key in PLACEMENT_HOTKEYS.into_iter().chain([KeyCode::KeyS]) {
+            keys.clear_just_pressed(key);
+        }
+    }
+
+    let buffered = &mut *buffered;
+    for key in buffered.replayed.drain(..) {
+        keys.release(key);
+    }
+    if !banners.is_empty() {
+        for key in PLACEMENT_HOTKEYS {
+            if keys.clear_just_pressed(key) {
+                buffered.pending.push(key);
+            }
+        }
+        return;
+    }
+    for key in buffered.pending.drain(..) {
+        keys.press(key);
+        buffered.replayed.push(key);
+    }
+}
+
+fn despawn_on_tween_complete(
+    mut commands: Commands,
+    mut completed: EventReader<TweenCompleted>,
+    query: Query<(), With<DespawnOnTweenComplete>>,
+) {
+    for &TweenCompleted(entity) in completed.read() {
+        if query.contains(entity) {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum ConfirmAction {
+    OverwriteSave,
+    RestartRun,
+    SellTower(Entity),
+}
+
+impl ConfirmAction {
+    pub fn prompt(self) -> &'static str {
+        match self {
+            ConfirmAction::OverwriteSave => "Overwrite the existing save?",
+            ConfirmAction::RestartRun => "Restart the run? All progress will be lost.",
+            ConfirmAction::SellTower(_) => "Sell this veteran tower?",
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct ConfirmDialog {
+    pub action: ConfirmAction,
+    pub confirm_selected: bool,
+}
+
+#[derive(Component)]
+pub struct ConfirmDialogText;
+
+#[derive(Event)]
+pub struct Confirmed(pub ConfirmAction);
+
+fn confirm_dialog_text(dialog: &ConfirmDialog) -> String {
+    let (yes, no) = if dialog.confirm_selected {
+        ("[Yes]", " No ")
+    } else {
+        (" Yes ", "[No]")
+    };
+    format!("{}\n\n{yes}   {no}", dialog.action.prompt())
+}
+
+const CONFIRM_DIALOG_PANEL: Color = Color::srgba(0.0, 0.0, 0.0, 0.85);
+
+pub fn open_confirm_dialog(commands: &mut Commands, action: ConfirmAction) {
+    let dialog = ConfirmDialog {
+        action,
+        confirm_selected: false,
+    };
+    commands.spawn((
+        Text::new(confirm_dialog_text(&dialog)),
+        TextLayout::new_with_justify(JustifyText::Center),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(40.0),
+            left: Val::Percent(35.0),
+            width: Val::Percent(30.0),
+            padding: UiRect::all(Val::Px(16.0)),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        BackgroundColor(CONFIRM_DIALOG_PANEL),
+        ThemedPanel(CONFIRM_DIALOG_PANEL),
+        ThemedText,
+        Interaction::default(),
+        ConfirmDialogText,
+    ));
+    commands.insert_resource(dialog);
+}
+
+pub fn navigate_confirm_dialog(
+    mut commands: Commands,
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    dialog: Option<ResMut<ConfirmDialog>>,
+    mut text: Query<(Entity, &mut Text), With<ConfirmDialogText>>,
+    mut confirmed: EventWriter<Confirmed>,
+) {
+    let Some(mut dialog) = dialog else {
+        return;
+    };
+    let pressed = |key: KeyCode, button: GamepadButton| {
+        keys.just_pressed(key) || gamepads.iter().any(|gamepad| gamepad.just_pressed(button))
+    };
+    if pressed(KeyCode::ArrowLeft, GamepadButton::DPadLeft)
+        || pressed(KeyCode::ArrowRight, GamepadButton::DPadRight)
+    {
+        dialog.confirm_selected = !dialog.confirm_selected;
+    }
+    let accept = pressed(KeyCode::Enter, GamepadButton::South);
+    let cancel = pressed(KeyCode::Escape, GamepadButton::East);
+    if accept || cancel {
+        if accept && dialog.confirm_selected {
+            confirmed.send(Confirmed(dialog.action));
+        }
+        keys.clear_just_pressed(KeyCode::Escape);
+        commands.remove_resource::<ConfirmDialog>();
+        for (entity, _) in &text {
+            commands.entity(entity).despawn();
+        }
+        return;
+    }
+    for (_, mut text) in &mut text {
+        text.0 = confirm_dialog_text(&dialog);
+    }
+}
+
+const HIT_FLASH_SECONDS: f32 = 0.08;
+
+#[derive(Component)]
+struct HitFlash(Timer);
+
+fn flash_hit_enemies(
+    mut commands: Commands,
+    mut hits: EventReader<ProjectileHit>,
+    visuals: Res<VisualAccessibility>,
+    assets: Res<GameAssets>,
+    mut enemies: Query<&mut MeshMaterial2d<ColorMaterial>, With<Enemy>>,
+) {
+    for hit in hits.read() {
+        if !visuals.flashing {
+            continue;
+        }
+        if let Ok(mut material) = enemies.get_mut(hit.enemy) {
+            material.0 = assets.hit_flash_material.clone();
+            commands
+                .entity(hit.enemy)
+                .insert(HitFlash(Timer::from_seconds(
+                    HIT_FLASH_SECONDS,
+                    TimerMode::Once,
+                )));
+        }
+    }
+}
+
+fn update_hit_flashes(
+    mut commands: Commands,
+    time: Res<Time>,
+    assets: Res<GameAssets>,
+    mut flashing: Query<(
+        Entity,
+        &mut HitFlash,
+        &EnemyKind,
+        &mut MeshMaterial2d<ColorMaterial>,
+    )>,
+) {
+    for (entity, mut flash, kind, mut material) in &mut flashing {
+        if flash.0.tick(time.delta()).finished() {
+            material.0 = kind.material(&assets);
+            commands.entity(entity).remove::<HitFlash>();
+        }
+    }
+}
+
+fn tint_afflicted_enemies(
+    assets: Res<GameAssets>,
+    mut enemies: Query<
+        (
+            &EnemyKind,
+            &StatusEffects,
+            &mut MeshMaterial2d<ColorMaterial>,
+            Has<Revealed>,
+        ),
+        (With<Enemy>, Without<HitFlash>),
+    >,
+) {
+    for (kind, effects, mut material, revealed) in &mut enemies {
+        let tint = if effects.is_slowed() {
+            assets.slowed_material.clone()
+        } else if effects.is_burning() {
+            assets.burning_material.clone()
+        } else if effects.is_poisoned() {
+            assets.poisoned_material.clone()
+        } else if revealed {
+            assets.revealed_material.clone()
+        } else {
+            kind.material(&assets)
+        };
+        if material.0 != tint {
+            material.0 = tint;
+        }
+    }
+}
+
+const TRACER_SECONDS: f32 = 0.2;
+
+#[derive(Resource, Default)]
+struct Tracers(Vec<(Vec2, Vec2, f32)>);
+
+fn record_tracers(
+    time: Res<Time>,
+    mut hits: EventReader<ProjectileHit>,
+    towers: Query<&Transform, With<Tower>>,
+    mut tracers: ResMut<Tracers>,
+) {
+    for hit in hits.read() {
+        if !hit.kind.is_hitscan() {
+            continue;
+        }
+        if let Ok(transform) = towers.get(hit.tower) {
+            let from = transform.translation.truncate();
+            tracers.0.push((from, hit.position, time.elapsed_secs()));
+        }
+    }
+}
+
+fn draw_tracers(mut gizmos: Gizmos, time: Res<Time>, mut tracers: ResMut<Tracers>) {
+    let now = time.elapsed_secs();
+    tracers.0.retain(|&(_, _, at)| now - at <= TRACER_SECONDS);
+    for &(from, to, at) in &tracers.0 {
+        let fade = 1.0 - (now - at) / TRACER_SECONDS;
+        gizmos.line_2d(from, to, Color::hsla(0., 0., 1.0, fade));
+    }
+}
+
+#[derive(Resource, Default)]
+struct ChainArcs(Vec<(Vec<Vec2>, f32)>);
+
+fn record_chain_arcs(
+    time: Res<Time>,
+    mut arcs: EventReader<ChainArc>,
+    mut chain_arcs: ResMut<ChainArcs>,
+) {
+    for ChainArc(points) in arcs.read() {
+        chain_arcs.0.push((points.clone(), time.elapsed_secs()));
+    }
+}
+
+fn draw_chain_arcs(mut gizmos: Gizmos, time: Res<Time>, mut chain_arcs: ResMut<ChainArcs>) {
+    let now = time.elapsed_secs();
+    chain_arcs.0.retain(|&(_, at)| now - at <= TRACER_SECONDS);
+    for (points, at) in &chain_arcs.0 {
+        let fade = 1.0 - (now - at) / TRACER_SECONDS;
+        gizmos.linestrip_2d(points.iter().copied(), Color::hsla(200., 1.0, 0.8, fade));
+    }
+}
+
+fn draw_lobbed_shadows(
+    mut gizmos: Gizmos,
+    shells: Query<(&Lobbed, &ProjectileKind, &FiredBy)>,
+    synergies: Query<&Synergy>,
+) {
+    for (lobbed, &kind, fired_by) in &shells {
+        gizmos.circle_2d(lobbed.ground, 4.0, Color::srgba(0.0, 0.0, 0.0, 0.6));
+        let splash = match synergies.get(fired_by.0) {
+            Ok(synergy) => synergy.splash_radius(kind),
+            Err(_) => kind.splash_radius(),
+        };
+        if let Some(radius) = splash {
+            gizmos.circle_2d(lobbed.landing, radius, Color::hsla(10., 0.9, 0.5, 0.35));
+        }
+    }
+}
+
+const SHAKE_TRAUMA_PER_HIT: f32 = 0.5;
+const SHAKE_DECAY: f32 = 1.5;
+const SHAKE_MAX_OFFSET: f32 = 8.0;
+
+#[derive(Resource, Default)]
+struct ScreenShake {
+    trauma: f32,
+    offset: Vec2,
+}
+
+fn shake_on_base_hit(
+    mut reached: EventReader<EnemyReachedBase>,
+    visuals: Res<VisualAccessibility>,
+    mut shake: ResMut<ScreenShake>,
+) {
+    let hits = reached.read().count();
+    if hits > 0 && visuals.allows_shake() {
+        shake.trauma = (shake.trauma + SHAKE_TRAUMA_PER_HIT * hits as f32).min(1.0);
+    }
+}
+
+fn shake_camera(
+    real_time: Res<Time<Real>>,
+    visuals: Res<VisualAccessibility>,
+    mut shake: ResMut<ScreenShake>,
+    mut camera: Single<&mut Transform, With<Camera2d>>,
+) {
+    if shake.trauma <= 0.0 && shake.offset == Vec2::ZERO {
+        return;
+    }
+    camera.translation -= shake.offset.extend(0.0);
+    shake.trauma = if visuals.allows_shake() {
+        (shake.trauma - SHAKE_DECAY * real_time.delta_secs()).max(0.0)
+    } else {
+        0.0
+    };
+    let mut rng = rand::thread_rng();
+    let jitter = Vec2::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0));
+    shake.offset = jitter * shake.trauma * shake.trauma * SHAKE_MAX_OFFSET;
+    camera.translation += shake.offset.extend(0.0);
+}
+
+const CAMERA_PAN_SPEED: f32 = 600.0;
+
+#[derive(Resource)]
+pub struct CameraFraming {
+    pub home: Vec2,
+    pub zoom: f32,
+    pub bounds: Option<Rect>,
+}
+
+fn pan_camera(
+    real_time: Res<Time<Real>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    kill_cam: Res<KillCam>,
+    dialog: Option<Res<ConfirmDialog>>,
+    mut camera: Single<&mut Transform, With<Camera2d>>,
+) {
+    if kill_cam.timer.is_some() || dialog.is_some() {
+        return;
+    }
+    let mut direction = Vec2::ZERO;
+    for (key, step) in [
+        (KeyCode::ArrowLeft, Vec2::NEG_X),
+        (KeyCode::ArrowRight, Vec2::X),
+        (KeyCode::ArrowDown, Vec2::NEG_Y),
+        (KeyCode::ArrowUp, Vec2::Y),
+    ] {
+        if keys.pressed(key) {
+            direction += step;
+        }
+    }
+    camera.translation += (direction * CAMERA_PAN_SPEED * real_time.delta_secs()).extend(0.0);
+}
+
+pub fn clamp_camera(
+    framing: Res<CameraFraming>,
+    window: Single<&Window>,
+    camera: Single<(&mut Transform, &OrthographicProjection), With<Camera2d>>,
+) {
+    let Some(bounds) = framing.bounds else {
+        return;
+    };
+    let (mut transform, projection) = camera.into_inner();
+    let half_view = window.size() / 2.0 * projection.scale;
+    let min = bounds.min + half_view;
+    let max = bounds.max - half_view;
+    let center = bounds.center();
+    let position = transform.translation.truncate();
+    let clamped = Vec2::new(
+        if min.x > max.x {
+            center.x
+        } else {
+            position.x.clamp(min.x, max.x)
+        },
+        if min.y > max.y {
+            center.y
+        } else {
+            position.y.clamp(min.y, max.y)
+        },
+    );
+    transform.translation = clamped.extend(transform.translation.z);
+}
+
+const KILL_CAM_TIME_SCALE: f32 = 0.3;
+const KILL_CAM_SECONDS: f32 = 1.0;
+const KILL_CAM_ZOOM: f32 = 0.7;
+
+#[derive(Resource)]
+pub struct KillCam {
+    pub enabled: bool,
+    pub timer: Option<Timer>,
+}
+
+impl Default for KillCam {
+    fn default() -> Self {
+        KillCam {
+            enabled: true,
+            timer: None,
+        }
+    }
+}
+
+fn toggle_kill_cam(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut kill_cam: ResMut<KillCam>,
+) {
+    if keys.just_pressed(KeyCode::KeyK) {
+        kill_cam.enabled = !kill_cam.enabled;
+        let message = if kill_cam.enabled {
+            "Kill cam on"
+        } else {
+            "Kill cam off"
+        };
+        spawn_toast(&mut commands, message);
+    }
+}
+
+fn start_kill_cam(
+    mut boss_killed: EventReader<BossKilled>,
+    visuals: Res<VisualAccessibility>,
+    mut kill_cam: ResMut<KillCam>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    camera: Single<(&mut Transform, &mut OrthographicProjection), With<Camera2d>>,
+) {
+    let Some(BossKilled(position)) = boss_killed.read().last() else {
+        return;
+    };
+    if !kill_cam.enabled || visuals.reduced_motion {
+        return;
+    }
+
+    let (mut camera_transform, mut projection) = camera.into_inner();
+    camera_transform.translation = position.truncate().extend(camera_transform.translation.z);
+    projection.scale = KILL_CAM_ZOOM;
+    virtual_time.set_relative_speed(KILL_CAM_TIME_SCALE);
+    kill_cam.timer = Some(Timer::from_seconds(KILL_CAM_SECONDS, TimerMode::Once));
+}
+
+fn update_kill_cam(
+    real_time: Res<Time<Real>>,
+    framing: Res<CameraFraming>,
+    mut kill_cam: ResMut<KillCam>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    camera: Single<(&mut Transform, &mut OrthographicProjection), With<Camera2d>>,
+) {
+    let Some(timer) = kill_cam.timer.as_mut() else {
+        return;
+    };
+    if !timer.tick(real_time.delta()).finished() {
+        return;
+    }
+
+    let (mut camera_transform, mut projection) = camera.into_inner();
+    camera_transform.translation = framing.home.extend(camera_transform.translation.z);
+    projection.scale = framing.zoom;
+    virtual_time.set_relative_speed(1.0);
+    kill_cam.timer = None;
+}
+
+pub const HEATMAP_TILE_SIZE: f32 = PLACEMENT_CELL;
+const HEATMAP_LAYER: f32 = -2.0;
+pub const HEATMAP_SHADES: usize = 8;
+
+#[derive(Resource)]
+pub struct HeatmapAssets {
+    pub tile_mesh: Handle<Mesh>,
+    pub materials: Vec<Handle<ColorMaterial>>,
+}
+
+impl FromWorld for HeatmapAssets {
+    fn from_world(world: &mut World) -> Self {
+        let tile_mesh = world
+            .resource_mut::<Assets<Mesh>>()
+            .add(Rectangle::new(HEATMAP_TILE_SIZE, HEATMAP_TILE_SIZE));
+        let mut materials = world.resource_mut::<Assets<ColorMaterial>>();
+        HeatmapAssets {
+            tile_mesh,
+            materials: (1..=HEATMAP_SHADES)
+                .map(|shade| {
+                    let intensity = shade as f32 / HEATMAP_SHADES as f32;
+                    materials.add(Color::hsla(120.0 * (1.0 - intensity), 0.9, 0.5, 0.35))
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Component)]
+struct HeatmapTile;
+
+#[derive(Resource, Default)]
+struct DangerHeatmap(bool);
+
+fn toggle_danger_heatmap(keys: Res<ButtonInput<KeyCode>>, mut heatmap: ResMut<DangerHeatmap>) {
+    if keys.just_pressed(KeyCode::KeyH) {
+        heatmap.0 = !heatmap.0;
+    }
+}
+
+fn update_danger_heatmap(
+    mut commands: Commands,
+    heatmap: Res<DangerHeatmap>,
+    tiles: Query<Entity, With<HeatmapTile>>,
+    added: Query<(), Added<Tower>>,
+    mut removed: RemovedComponents<Tower>,
+    towers: Query<(&Transform, &Range, &Damage, &FireRate, &Cooldown), With<Tower>>,
+    grid: Res<GridMap>,
+    air_path: Res<AirPath>,
+    assets: Res<HeatmapAssets>,
+) {
+    let towers_changed = removed.read().count() > 0 || !added.is_empty();
+    if !heatmap.is_changed() && (!heatmap.0 || !towers_changed) {
+        return;
+    }
+    for tile in &tiles {
+        commands.entity(tile).despawn();
+    }
+    if !heatmap.0 {
+        return;
+    }
+
+    let max_dps: f32 = towers
+        .iter()
+        .map(|(_, _, damage, fire_rate, cooldown)| tower_dps(damage, fire_rate, cooldown))
+        .sum();
+    if max_dps <= 0.0 {
+        return;
+    }
+    let cells: HashSet<IVec2> = grid
+        .path_cells()
+        .chain(grid.route_cells(&air_path.0))
+        .collect();
+    for cell in cells {
+        let center = grid.center(cell);
+        let dps: f32 = towers
+            .iter()
+            .filter(|(transform, range, _, _, _)| {
+                transform.translation.truncate().distance(center) <= range.0
+            })
+            .map(|(_, _, damage, fire_rate, cooldown)| tower_dps(damage, fire_rate, cooldown))
+            .sum();
+        if dps <= 0.0 {
+            continue;
+        }
+
+        let shade = ((dps / max_dps) * HEATMAP_SHADES as f32).ceil() as usize;
+        commands.spawn((
+            Mesh2d(assets.tile_mesh.clone()),
+            MeshMaterial2d(assets.materials[shade.clamp(1, HEATMAP_SHADES) - 1].clone()),
+            Transform::from_translation(center.extend(HEATMAP_LAYER)),
+            HeatmapTile,
+        ));
+    }
+}
+
+const RADIAL_MENU_BUTTON: GamepadButton = GamepadButton::LeftTrigger2;
+const RADIAL_MENU_SIZE: f32 = 240.0;
+const RADIAL_MENU_RADIUS: f32 = 80.0;
+const RADIAL_MENU_DEADZONE: f32 = 0.5;
+const RADIAL_TICK_SECONDS: f32 = 0.04;
+const RADIAL_TICK_INTENSITY: f32 = 0.4;
+
+#[derive(Resource, Default)]
+struct RadialMenu {
+    selected: Option<usize>,
+}
+
+#[derive(Component)]
+struct RadialMenuRoot;
+
+#[derive(Component)]
+struct RadialSlot(usize);
+
+fn radial_slot_angle(index: usize, slots: usize) -> f32 {
+    std::f32::consts::FRAC_PI_2 + std::f32::consts::TAU * index as f32 / slots as f32
+}
+
+const RADIAL_MENU_PANEL: Color = Color::srgba(0.0, 0.0, 0.0, 0.6);
+
+fn setup_radial_menu(mut commands: Commands) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Px(RADIAL_MENU_SIZE),
+            height: Val::Px(RADIAL_MENU_SIZE),
+            left: Val::Percent(50.0),
+            top: Val::Percent(50.0),
+            margin: UiRect {
+                left: Val::Px(-RADIAL_MENU_SIZE / 2.0),
+                top: Val::Px(-RADIAL_MENU_SIZE / 2.0),
+                ..default()
+            },
+            ..default()
+        },
+        BackgroundColor(RADIAL_MENU_PANEL),
+        ThemedPanel(RADIAL_MENU_PANEL),
+        BorderRadius::MAX,
+        Visibility::Hidden,
+        RadialMenuRoot,
+    ));
+}
+
+fn populate_radial_menu(
+    mut commands: Commands,
+    loadout: Res<Loadout>,
+    root: Single<Entity, With<RadialMenuRoot>>,
+) {
+    commands
+        .entity(*root)
+        .despawn_descendants()
+        .with_children(|menu| {
+            for (index, kind) in loadout.0.iter().enumerate() {
+                let offset = Vec2::from_angle(radial_slot_angle(index, loadout.0.len()))
+                    * RADIAL_MENU_RADIUS;
+                menu.spawn((
+                    Text::new(kind.name()),
+                    TextColor(Color::WHITE),
+                    ThemedText,
+                    Node {
+                        position_type: PositionType::Absolute,
+                        left: Val::Px(RADIAL_MENU_SIZE / 2.0 + offset.x - 20.0),
+                        top: Val::Px(RADIAL_MENU_SIZE / 2.0 - offset.y - 10.0),
+                        ..default()
+                    },
+                    RadialSlot(index),
+                ));
+            }
+        });
+}
+
+fn update_radial_menu(
+    mut commands: Commands,
+    gamepads: Query<(Entity, &Gamepad)>,
+    mut rumble: EventWriter<GamepadRumbleRequest>,
+    mut radial: ResMut<RadialMenu>,
+    loadout: Res<Loadout>,
+    mut build_kind: ResMut<BuildKind>,
+    mut root: Single<&mut Visibility, With<RadialMenuRoot>>,
+    mut slots: Query<(&RadialSlot, &mut TextColor)>,
+) {
+    if let Some((entity, gamepad)) = gamepads
+        .iter()
+        .find(|(_, gamepad)| gamepad.pressed(RADIAL_MENU_BUTTON))
+    {
+        **root = Visibility::Visible;
+        let stick = gamepad.left_stick();
+        if stick.length() > RADIAL_MENU_DEADZONE {
+            let step = std::f32::consts::TAU / loadout.0.len() as f32;
+            let slot = ((stick.to_angle() - std::f32::consts::FRAC_PI_2) / step).round() as i32;
+            let slot = slot.rem_euclid(loadout.0.len() as i32) as usize;
+            if radial.selected != Some(slot) {
+                radial.selected = Some(slot);
+                rumble.send(GamepadRumbleRequest::Add {
+                    duration: Duration::from_secs_f32(RADIAL_TICK_SECONDS),
+                    intensity: GamepadRumbleIntensity::weak_motor(RADIAL_TICK_INTENSITY),
+                    gamepad: entity,
+                });
+            }
+        }
+        for (slot, mut color) in &mut slots {
+            color.0 = if radial.selected == Some(slot.0) {
+                Color::hsl(50., 0.9, 0.6)
+            } else {
+                Color::WHITE
+            };
+        }
+        return;
+    }
+
+    **root = Visibility::Hidden;
+    if let Some(slot) = radial.selected.take() {
+        build_kind.0 = loadout.0[slot];
+        spawn_toast(
+            &mut commands,
+            &format!("Building {} towers", build_kind.0.name()),
+        );
+    }
+}
+
+#[derive(Component)]
+struct PlacementGhost;
+
+#[derive(Component)]
+struct PlacementTooltip;
+
+fn setup_placement_ghost(mut commands: Commands, assets: Res<GameAssets>) {
+    commands.spawn((
+        Mesh2d(assets.tower_mesh.clone()),
+        MeshMaterial2d(assets.valid_placement_material.clone()),
+        Transform::default(),
+        Visibility::Hidden,
+        PlacementGhost,
+    ));
+    commands.spawn((
+        Text::default(),
+        TextColor(Color::hsl(0., 0.8, 0.6)),
+        Node {
+            position_type: PositionType::Absolute,
+            ..default()
+        },
+        Visibility::Hidden,
+        PlacementTooltip,
+    ));
+}
+
+fn update_placement_ghost(
+    mut gizmos: Gizmos,
+    window: Single<&Window>,
+    camera: Single<(&Camera, &GlobalTransform)>,
+    assets: Res<GameAssets>,
+    build_kind: Res<BuildKind>,
+    placement: Placement,
+    ghost: Single<
+        (
+            &mut Transform,
+            &mut MeshMaterial2d<ColorMaterial>,
+            &mut Visibility,
+        ),
+        (
+            With<PlacementGhost>,
+            Without<Tower>,
+            Without<Rubble>,
+            Without<ShieldGenerator>,
+            Without<Base>,
+            Without<PlannedTower>,
+            Without<Enemy>,
+        ),
+    >,
+    tooltip: Single<
+        (&mut Text, &mut Node, &mut Visibility),
+        (With<PlacementTooltip>, Without<PlacementGhost>),
+    >,
+) {
+    let (mut ghost_transform, mut material, mut ghost_visibility) = ghost.into_inner();
+    let (mut text, mut node, mut tooltip_visibility) = tooltip.into_inner();
+    let (camera, camera_transform) = camera.into_inner();
+    let Some((cursor, position)) = window.cursor_position().and_then(|cursor| {
+        let position = camera.viewport_to_world_2d(camera_transform, cursor).ok()?;
+        Some((cursor, position))
+    }) else {
+        *ghost_visibility = Visibility::Hidden;
+        *tooltip_visibility = Visibility::Hidden;
+        return;
+    };
+
+    let cell = placement.snap(position);
+    let result = placement.validate(cell, build_kind.0);
+    ghost_transform.translation = cell.extend(0.0);
+    *ghost_visibility = Visibility::Visible;
+    gizmos.rect_2d(
+        cell,
+        Vec2::splat(PLACEMENT_CELL),
+        Color::hsla(0., 0., 1.0, 0.3),
+    );
+    gizmos.circle_2d(cell, build_kind.0.range(), Color::hsla(0., 0., 1.0, 0.2));
+    match result {
+        Ok(()) => {
+            material.0 = assets.valid_placement_material.clone();
+            *tooltip_visibility = Visibility::Hidden;
+        }
+        Err(error) => {
+            material.0 = assets.invalid_placement_material.clone();
+            text.0 = error.reason().to_string();
+            node.left = Val::Px(cursor.x + 16.0);
+            node.top = Val::Px(cursor.y + 16.0);
+            *tooltip_visibility = Visibility::Visible;
+        }
+    }
+}
+
+#[derive(Component)]
+struct MatchupTooltip;
+
+fn setup_matchup_tooltip(mut commands: Commands) {
+    commands.spawn((
+        Text::default(),
+        TextColor(Color::hsl(50., 0.9, 0.7)),
+        Node {
+            position_type: PositionType::Absolute,
+            ..default()
+        },
+        Visibility::Hidden,
+        MatchupTooltip,
+    ));
+}
+
+fn update_matchup_preview(
+    window: Single<&Window>,
+    camera: Single<(&Camera, &GlobalTransform)>,
+    tower: Query<(&Damage, &TowerKind), (With<Tower>, With<Selected>)>,
+    enemies: Query<(&Transform, &EnemyKind, &Armor, &Resistances, &StatusEffects), With<Enemy>>,
+    tooltip: Single<(&mut Text, &mut Node, &mut Visibility), With<MatchupTooltip>>,
+) {
+    let (mut text, mut node, mut visibility) = tooltip.into_inner();
+    let (camera, camera_transform) = camera.into_inner();
+    let hovered = window.cursor_position().and_then(|cursor| {
+        let position = camera.viewport_to_world_2d(camera_transform, cursor).ok()?;
+        let enemy = enemies
+            .iter()
+            .find(|(transform, ..)| enemy_bounds(transform).closest_point(position) == position)?;
+        Some((cursor, enemy))
+    });
+    let (Ok((&damage, &tower_kind)), Some((cursor, (_, kind, armor, resistances, effects)))) =
+        (tower.get_single(), hovered)
+    else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+
+    let projectile = tower_kind.projectile();
+    text.0 = format!(
+        "{} ({} lane): {} damage per hit ({} - {} armor)",
+        kind.name(),
+        kind.lane().name(),
+        effective_damage(damage, projectile, armor, resistances, effects).min(kind.max_hit()),
+        damage.0,
+        effects.armor(armor)
+    );
+    let damage_type = projectile.damage_type();
+    let resist = resistances.percent(damage_type);
+    if resist > 0 {
+        text.0
+            .push_str(&format!(", resists {resist}% {}", damage_type.name()));
+    }
+    match projectile {
+        ProjectileKind::Acid => text.0.push_str(&format!(
+            ", shreds {ACID_SHRED} armor, poisons for {POISON_DAMAGE}/s"
+        )),
+        ProjectileKind::Frost => text.0.push_str(&format!(", slows {FROST_SLOW}% per stack")),
+        ProjectileKind::Bomb => text.0.push_str(&format!(
+            ", {SPLASH_RADIUS} splash, burns {BURN_DAMAGE} every {BURN_PERIOD}s"
+        )),
+        ProjectileKind::Missile => text.0.push_str(", homing"),
+        ProjectileKind::Lightning => text
+            .0
+            .push_str(&format!(", chains {CHAIN_JUMPS} times, stuns")),
+        ProjectileKind::Shell | ProjectileKind::Bolt | ProjectileKind::Slug => {}
+    }
+    node.left = Val::Px(cursor.x + 16.0);
+    node.top = Val::Px(cursor.y - 24.0);
+    *visibility = Visibility::Visible;
+}
+
+#[derive(Component)]
+struct GoldText;
+
+fn setup_gold_hud(mut commands: Commands) {
+    commands.spawn((
+        Text::default(),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(12.0),
+            right: Val::Px(12.0),
+            ..default()
+        },
+        ThemedText,
+        Interaction::default(),
+        GoldText,
+    ));
+}
+
+fn update_gold_hud(
+    gold: Res<Gold>,
+    lives: Res<Lives>,
+    shield: Res<Shield>,
+    generators: Query<(), With<ShieldGenerator>>,
+    last_stand: Res<LastStand>,
+    commander: Res<Commander>,
+    mutators: Res<Mutators>,
+    bounties: Res<BountyConfig>,
+    waves: Res<Waves>,
+    spawn_timer: Res<EnemySpawnTimer>,
+    upgrades: Res<BaseUpgrades>,
+    enemies: Query<&Bounty, With<Enemy>>,
+    planned: Query<&PlannedTower>,
+    mut text: Single<&mut Text, With<GoldText>>,
+) {
+    let incoming_bounties: u32 = enemies
+        .iter()
+        .copied()
+        .chain(waves.pending.iter().map(|kind| Bounty(kind.bounty())))
+        .map(|bounty| bounties.bounty(*mutators, bounty, waves.number, 0))
+        .sum();
+    let interest = upgrades.trickle_within(waves.seconds_until_next(&spawn_timer));
+    let queued_costs: u32 = planned
+        .iter()
+        .map(|planned| planned.0.cost(*commander))
+        .sum();
+    let forecast = (gold.0 + incoming_bounties + interest).saturating_sub(queued_costs);
+    text.0 = format!(
+        "Lives: {}  Shield: {}/{}  Gold: {}  Forecast: {forecast}",
+        lives.0,
+        shield.0,
+        shield_cap(generators.iter().count()),
+        gold.0
+    );
+    if let Some(timer) = &last_stand.timer {
+        text.0.push_str(&format!(
+            "  LAST STAND {:.0}s",
+            timer.remaining_secs().ceil()
+        ));
+    } else if waves.in_break() {
+        text.0.push_str(&format!(
+            "  Next wave in {:.0}s",
+            waves.break_timer.remaining_secs().ceil()
+        ));
+    }
+}
+
+#[derive(Component)]
+struct RunTimerText;
+
+pub fn format_run_time(seconds: f32) -> String {
+    let centiseconds = (seconds * 100.0) as u32;
+    format!(
+        "{:02}:{:02}.{:02}",
+        centiseconds / 6000,
+        centiseconds / 100 % 60,
+        centiseconds % 100
+    )
+}
+
+fn setup_run_timer(mut commands: Commands) {
+    commands.spawn((
+        Text::default(),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(40.0),
+            right: Val::Px(12.0),
+            ..default()
+        },
+        ThemedText,
+        Interaction::default(),
+        RunTimerText,
+    ));
+}
+
+fn update_run_timer(
+    keys: Res<ButtonInput<KeyCode>>,
+    clock: Res<RunClock>,
+    mut wave_started: EventReader<WaveStarted>,
+    mut split: Local<Option<(u32, f32)>>,
+    timer_text: Single<(&mut Text, &mut Visibility), With<RunTimerText>>,
+) {
+    let (mut text, mut visibility) = timer_text.into_inner();
+    if keys.just_pressed(KeyCode::KeyT) {
+        visibility.toggle_visible_hidden();
+    }
+    if let Some(&WaveStarted(wave)) = wave_started.read().last() {
+        *split = Some((wave, clock.0));
+    }
+    if split.is_some_and(|(_, time)| time > clock.0) {
+        *split = None;
+    }
+    text.0 = format_run_time(clock.0);
+    if let Some((wave, time)) = *split {
+        text.0
+            .push_str(&format!("\nWave {wave}  {}", format_run_time(time)));
+    }
+}
+
+#[derive(Component)]
+struct TowerPanelText;
+
+fn setup_tower_panel(mut commands: Commands) {
+    commands.spawn((
+        Text::default(),
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(40.0),
+            left: Val::Px(12.0),
+            ..default()
+        },
+        ThemedText,
+        Interaction::default(),
+        TowerPanelText,
+    ));
+}
+
+fn update_tower_panel(
+    commander: Res<Commander>,
+    upgrades: Res<BaseUpgrades>,
+    selected: Query<(&TowerKind, &UltimateCharge, &Kills, &TargetingStrategy), With<Selected>>,
+    selected_rubble: Query<&Rubble, With<Selected>>,
+    selected_base: Query<(), (With<Base>, With<Selected>)>,
+    mut text: Single<&mut Text, With<TowerPanelText>>,
+) {
+    if !selected_base.is_empty() {
+        text.0 = upgrades.panel_text();
+        return;
+    }
+    text.0 = match selected.get_single() {
+        Ok((kind, charge, kills, strategy)) if charge.0 >= ULTIMATE_CHARGE => format!(
+            "{} tower  Kills: {}  Targeting {} [M]  {} ready [Q]",
+            kind.name(),
+            kills.0,
+            strategy.name(),
+            kind.ultimate().name()
+        ),
+        Ok((kind, charge, kills, strategy)) => format!(
+            "{} tower  Kills: {}  Targeting {} [M]  {} {}%",
+            kind.name(),
+            kills.0,
+            strategy.name(),
+            kind.ultimate().name(),
+            charge.0 * 100 / ULTIMATE_CHARGE
+        ),
+        Err(_) => match selected_rubble.get_single() {
+            Ok(rubble) => format!(
+                "Rubble ({} kills)  Rebuild for {} [R]",
+                rubble.kills,
+                rebuild_cost(*commander, rubble.kind)
+            ),
+            Err(_) => String::new(),
+        },
+    };
+}
+
+fn draw_synergy_links(
+    mut gizmos: Gizmos,
+    towers: Query<(Entity, &Transform, &Synergy), With<Tower>>,
+    transforms: Query<&Transform, With<Tower>>,
+) {
+    for (entity, transform, synergy) in &towers {
+        for &other in &synergy.links {
+            if entity < other {
+                if let Ok(other_transform) = transforms.get(other) {
+                    gizmos.line_2d(
+                        transform.translation.truncate(),
+                        other_transform.translation.truncate(),
+                        Color::hsla(50., 1.0, 0.6, 0.6),
+                    );
+                }
+            }
+        }
+    }
+}
+
+const PATH_PREVIEW_SPACING: f32 = 20.0;
+const PATH_PREVIEW_SPEED: f32 = 40.0;
+const AIR_LANE_COLOR: Color = Color::hsla(200., 0.9, 0.75, 0.5);
+
+fn draw_path_preview(
+    mut gizmos: Gizmos,
+    real_time: Res<Time<Real>>,
+    virtual_time: Res<Time<Virtual>>,
+    visuals: Res<VisualAccessibility>,
+    waves: Res<Waves>,
+    next_spawn: Res<NextSpawn>,
+    path: Res<Path>,
+    air_path: Res<AirPath>,
+    base: Single<&Transform, With<Base>>,
+    mut route: Local<Vec<Vec2>>,
+) {
+    let Some(spawn) = next_spawn.0 else {
+        return;
+    };
+    if !waves.in_break() && !virtual_time.is_paused() {
+        return;
+    }
+    let air = (!air_path.0.is_empty()).then_some((&air_path.0, AIR_LANE_COLOR));
+    for (waypoints, color) in std::iter::once((&path.0, Color::hsla(0., 0.8, 0.7, 0.5))).chain(air)
+    {
+        route.clear();
+        route.push(spawn.truncate());
+        route.extend(waypoints.iter().copied());
+        route.push(base.translation.truncate());
+        let mut distance = if visuals.reduced_motion {
+            0.0
+        } else {
+            (real_time.elapsed_secs() * PATH_PREVIEW_SPEED) % PATH_PREVIEW_SPACING
+        };
+        for segment in route.windows(2) {
+            let (start, end) = (segment[0], segment[1]);
+            let length = start.distance(end);
+            let direction = (end - start).normalize_or_zero();
+            while distance < length {
+                gizmos.circle_2d(start + direction * distance, 2.0, color);
+                distance += PATH_PREVIEW_SPACING;
+            }
+            distance -= length;
+        }
+    }
+}
+
+fn draw_air_corridors(
+    mut gizmos: Gizmos,
+    air_path: Res<AirPath>,
+    base: Single<&Transform, With<Base>>,
+) {
+    if air_path.0.is_empty() {
+        return;
+    }
+    let corridor = air_path
+        .0
+        .iter()
+        .copied()
+        .chain([base.translation.truncate()]);
+    gizmos.linestrip_2d(corridor, AIR_LANE_COLOR.with_alpha(0.25));
+}
+
+pub struct UiPlugin;
+
+impl Plugin for UiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<TweenCompleted>();
+        app.add_event::<Confirmed>();
+        app.init_resource::<HeatmapAssets>();
+        app.init_resource::<DangerHeatmap>();
+        app.init_resource::<KillCam>();
+        app.init_resource::<RadialMenu>();
+        app.init_resource::<BufferedHotkeys>();
+        app.init_resource::<FrameWatchdog>();
+        app.init_resource::<ScreenShake>();
+        app.init_resource::<Tracers>();
+        app.init_resource::<ChainArcs>();
+        app.add_systems(
+            Startup,
+            (
+                setup_gold_hud,
+                setup_run_timer,
+                setup_tower_panel,
+                setup_placement_ghost,
+                setup_matchup_tooltip,
+                setup_radial_menu,
+                setup_enrage_bar,
+                setup_boss_bar,
+            ),
+        );
+        app.add_systems(
+            PreUpdate,
+            guard_gameplay_input
+                .after(bevy::input::InputSystem)
+                .after(bevy::ui::UiSystem::Focus),
+        );
+        app.add_systems(Update, show_load_errors);
+        if app.world().contains_resource::<Assets<Font>>() {
+            app.add_systems(Update, report_failed_assets::<Font>);
+        }
+        if app.world().contains_resource::<Assets<Image>>() {
+            app.add_systems(Update, report_failed_assets::<Image>);
+        }
+        app.add_systems(
+            Update,
+            (
+                navigate_confirm_dialog,
+                update_health_bars,
+                watch_frame_budget,
+                draw_synergy_links
+                    .run_if(full_visuals)
+                    .after(update_tower_synergies),
+                (draw_path_preview, draw_air_corridors).run_if(full_visuals),
+                draw_debuff_icons,
+                draw_focus_marker,
+                update_enrage_bar,
+                update_boss_bar,
+                update_matchup_preview,
+                toggle_kill_cam,
+                (toggle_danger_heatmap, update_danger_heatmap).chain(),
+                (
+                    populate_radial_menu.run_if(resource_changed::<Loadout>),
+                    update_radial_menu,
+                    update_placement_ghost,
+                )
+                    .chain()
+                    .after(cycle_build_kind)
+                    .before(execute_planned_towers)
+                    .in_set(FrameSet::Input),
+                (update_gold_hud, update_run_timer, update_tower_panel)
+                    .chain()
+                    .in_set(FrameSet::Hud),
+                (
+                    flash_hit_enemies,
+                    update_hit_flashes,
+                    tint_afflicted_enemies,
+                )
+                    .chain(),
+                (record_tracers, draw_tracers).chain(),
+                (record_chain_arcs, draw_chain_arcs).chain(),
+                draw_lobbed_shadows,
+                (
+                    update_kill_cam,
+                    start_kill_cam,
+                    pan_camera,
+                    clamp_camera,
+                    shake_on_base_hit,
+                    shake_camera,
+                )
+                    .chain(),
+                (
+                    animate_tweens::<TextAlphaLens, Real>,
+                    animate_tweens::<ScaleLens, Virtual>,
+                    despawn_on_tween_complete,
+                )
+                    .chain(),
+            ),
+        );
+    }
+}